@@ -1,28 +1,13 @@
-use crate::db::pool::PoolManager;
-use crate::models::ConnectionConfig;
+use crate::db::pool::PoolStats;
+use crate::db::tunnel::open_tunnel;
+use crate::db::connect;
+use crate::error::{AppError, ErrorCategory, ErrorContext};
+use crate::models::{ConnectionConfig, SshTunnelConfig, SslMode};
 use crate::security::keyring;
+use crate::state::AppState;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::RwLock;
-
-static POOL_MANAGER: tokio::sync::OnceCell<Arc<PoolManager>> = tokio::sync::OnceCell::const_new();
-static CONNECTIONS: tokio::sync::OnceCell<Arc<RwLock<Vec<ConnectionConfig>>>> =
-    tokio::sync::OnceCell::const_new();
-
-async fn get_pool_manager() -> Arc<PoolManager> {
-    POOL_MANAGER
-        .get_or_init(|| async { Arc::new(PoolManager::new()) })
-        .await
-        .clone()
-}
-
-pub(crate) async fn get_connections_storage() -> Arc<RwLock<Vec<ConnectionConfig>>> {
-    CONNECTIONS
-        .get_or_init(|| async { Arc::new(RwLock::new(Vec::new())) })
-        .await
-        .clone()
-}
+use tauri::State;
 
 #[derive(Debug, Deserialize)]
 pub struct TestConnectionRequest {
@@ -31,40 +16,105 @@ pub struct TestConnectionRequest {
     pub database: String,
     pub username: String,
     pub password: String,
+    #[serde(default)]
+    pub ssl_mode: SslMode,
+    #[serde(default)]
+    pub ssl_root_cert: Option<String>,
+    #[serde(default)]
+    pub ssl_client_cert: Option<String>,
+    #[serde(default)]
+    pub ssl_client_key: Option<String>,
+    #[serde(default)]
+    pub ssh_tunnel: Option<SshTunnelConfig>,
+    /// SSH password or private key passphrase.
+    #[serde(default)]
+    pub ssh_secret: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct TestConnectionResponse {
     pub success: bool,
     pub message: String,
+    /// Negotiated TLS session, or `None` if the connection is unencrypted.
+    pub ssl: Option<SslInfo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SslInfo {
+    pub version: Option<String>,
+    pub cipher: Option<String>,
 }
 
 #[tauri::command]
-pub async fn test_connection(request: TestConnectionRequest) -> Result<TestConnectionResponse, String> {
-    let (client, connection) = tokio_postgres::connect(
-        &format!(
-            "host={} port={} dbname={} user={} password={}",
-            request.host, request.port, request.database, request.username, request.password
+pub async fn test_connection(
+    request: TestConnectionRequest,
+) -> Result<TestConnectionResponse, AppError> {
+    let config = ConnectionConfig {
+        id: String::new(),
+        name: String::new(),
+        host: request.host,
+        port: request.port,
+        database: request.database,
+        username: request.username,
+        ssl_mode: request.ssl_mode,
+        ssl_root_cert: request.ssl_root_cert,
+        ssl_client_cert: request.ssl_client_cert,
+        ssl_client_key: request.ssl_client_key,
+        ssh_tunnel: request.ssh_tunnel,
+    };
+
+    let tunnel = match &config.ssh_tunnel {
+        Some(ssh) => Some(
+            open_tunnel(ssh, request.ssh_secret.as_deref(), &config.host, config.port)
+                .await
+                .map_err(|e| AppError::new(ErrorCategory::Tunnel, e.to_string()))?,
         ),
-        tokio_postgres::NoTls,
-    )
-    .await
-    .map_err(|e| format!("Connection failed: {}", e))?;
-
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("connection error: {}", e);
-        }
-    });
+        None => None,
+    };
+
+    let client = connect::connect(&config, &request.password, tunnel.as_ref())
+        .await
+        .map_err(|e| {
+            let error = AppError::from_anyhow(e, ErrorCategory::Connection);
+            match error.category {
+                ErrorCategory::Tls => error.context("TLS handshake failed"),
+                _ => error.context("Connection failed"),
+            }
+        })?;
 
     client
         .simple_query("SELECT 1")
         .await
-        .map_err(|e| format!("Query failed: {}", e))?;
+        .context("Query failed")?;
+
+    // pg_stat_ssl reports what the server actually negotiated, which matters
+    // for `prefer` where we may have silently fallen back to plaintext.
+    let ssl = client
+        .query_opt(
+            "SELECT version, cipher FROM pg_stat_ssl WHERE pid = pg_backend_pid() AND ssl",
+            &[],
+        )
+        .await
+        .ok()
+        .flatten()
+        .map(|row| SslInfo {
+            version: row.get("version"),
+            cipher: row.get("cipher"),
+        });
+
+    let message = match &ssl {
+        Some(info) => format!(
+            "Connection successful ({}, {})",
+            info.version.as_deref().unwrap_or("TLS"),
+            info.cipher.as_deref().unwrap_or("unknown cipher")
+        ),
+        None => "Connection successful (unencrypted)".to_string(),
+    };
 
     Ok(TestConnectionResponse {
         success: true,
-        message: "Connection successful".to_string(),
+        message,
+        ssl,
     })
 }
 
@@ -77,10 +127,27 @@ pub struct SaveConnectionRequest {
     pub database: String,
     pub username: String,
     pub password: Option<String>,
+    #[serde(default)]
+    pub ssl_mode: SslMode,
+    #[serde(default)]
+    pub ssl_root_cert: Option<String>,
+    #[serde(default)]
+    pub ssl_client_cert: Option<String>,
+    #[serde(default)]
+    pub ssl_client_key: Option<String>,
+    #[serde(default)]
+    pub ssh_tunnel: Option<SshTunnelConfig>,
+    /// SSH password or key passphrase; follows the same keep-if-empty rule as
+    /// `password` when editing.
+    #[serde(default)]
+    pub ssh_secret: Option<String>,
 }
 
 #[tauri::command]
-pub async fn save_connection(request: SaveConnectionRequest) -> Result<String, String> {
+pub async fn save_connection(
+    state: State<'_, AppState>,
+    request: SaveConnectionRequest,
+) -> Result<String, AppError> {
     let is_editing = request.id.is_some();
     let connection_id = request.id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
 
@@ -88,17 +155,30 @@ pub async fn save_connection(request: SaveConnectionRequest) -> Result<String, S
     if let Some(password) = request.password {
         if !password.is_empty() {
             keyring::save_password(&connection_id, &password)
-                .map_err(|e| format!("Failed to save password: {}", e))?;
+                .context("Failed to save password")?;
         } else if is_editing {
             // Editing but password is empty - keep existing password (don't update)
             // This allows editing other fields without requiring password re-entry
         } else {
             // New connection but password is empty - error
-            return Err("Password is required for new connections".to_string());
+            return Err(AppError::validation("Password is required for new connections"));
         }
     } else if !is_editing {
         // New connection but no password provided - error
-        return Err("Password is required for new connections".to_string());
+        return Err(AppError::validation("Password is required for new connections"));
+    }
+
+    match (&request.ssh_tunnel, request.ssh_secret.as_deref()) {
+        (Some(_), Some(secret)) if !secret.is_empty() => {
+            keyring::save_ssh_secret(&connection_id, secret)
+                .context("Failed to save SSH secret")?;
+        }
+        (Some(_), _) => {
+            // Keep any existing secret; key files may not need one at all
+        }
+        (None, _) => {
+            keyring::delete_ssh_secret(&connection_id).ok();
+        }
     }
 
     // Save connection config
@@ -109,51 +189,71 @@ pub async fn save_connection(request: SaveConnectionRequest) -> Result<String, S
         port: request.port,
         database: request.database,
         username: request.username,
+        ssl_mode: request.ssl_mode,
+        ssl_root_cert: request.ssl_root_cert,
+        ssl_client_cert: request.ssl_client_cert,
+        ssl_client_key: request.ssl_client_key,
+        ssh_tunnel: request.ssh_tunnel,
     };
 
-    let connections = get_connections_storage().await;
-    let mut conns = connections.write().await;
+    state
+        .connections
+        .upsert(config)
+        .await
+        .map_err(|e| {
+            AppError::from_anyhow(e, ErrorCategory::Storage).context("Failed to save connection")
+        })?;
 
-    if let Some(existing) = conns.iter_mut().find(|c| c.id == connection_id) {
-        *existing = config;
-    } else {
-        conns.push(config);
-    }
+    // Drop any live client so the next command reconnects with the new settings
+    state.sessions.close_for_connection(&connection_id).await;
+    state.pool_manager.remove_connection(&connection_id).await;
 
     Ok(connection_id)
 }
 
 #[tauri::command]
-pub async fn get_connections() -> Result<Vec<ConnectionConfig>, String> {
-    let connections = get_connections_storage().await;
-    let conns = connections.read().await;
-    Ok(conns.clone())
-}
-
-#[derive(Debug, Deserialize)]
-pub struct DeleteConnectionRequest {
-    pub id: String,
+pub async fn get_connections(state: State<'_, AppState>) -> Result<Vec<ConnectionConfig>, AppError> {
+    Ok(state.connections.list().await)
 }
 
 #[tauri::command]
-pub async fn get_connection_password(id: String) -> Result<String, String> {
-    keyring::get_password(&id)
-        .map_err(|e| format!("Failed to get password: {}", e))
+pub async fn get_connection_password(id: String) -> Result<String, AppError> {
+    keyring::get_password(&id).context("Failed to get password")
 }
 
 #[tauri::command]
-pub async fn delete_connection(id: String) -> Result<(), String> {
+pub async fn delete_connection(state: State<'_, AppState>, id: String) -> Result<(), AppError> {
+    // Remove from storage first so a failed write leaves the password usable
+    state
+        .connections
+        .remove(&id)
+        .await
+        .map_err(|e| {
+            AppError::from_anyhow(e, ErrorCategory::Storage).context("Failed to delete connection")
+        })?;
+
     // Remove from keyring
     keyring::delete_password(&id).ok();
-
-    // Remove from storage
-    let connections = get_connections_storage().await;
-    let mut conns = connections.write().await;
-    conns.retain(|c| c.id != id);
+    keyring::delete_ssh_secret(&id).ok();
 
     // Remove from pool
-    let pool_manager = get_pool_manager().await;
-    pool_manager.remove_connection(&id).await;
+    state.sessions.close_for_connection(&id).await;
+    state.pool_manager.remove_connection(&id).await;
 
     Ok(())
 }
+
+/// Closes the live client for a connection; the next command reconnects.
+/// Open sessions on it are closed, rolling back their transactions.
+#[tauri::command]
+pub async fn disconnect_connection(state: State<'_, AppState>, id: String) -> Result<(), AppError> {
+    state.sessions.close_for_connection(&id).await;
+    state.pool_manager.remove_connection(&id).await;
+    Ok(())
+}
+
+/// Reports size and usage counters for every open connection pool.
+#[tauri::command]
+pub async fn get_pool_stats(state: State<'_, AppState>) -> Result<Vec<PoolStats>, AppError> {
+    Ok(state.pool_manager.stats().await)
+}
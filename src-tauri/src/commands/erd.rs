@@ -1,98 +1,63 @@
-use crate::commands::connection::get_connections_storage;
-use crate::db::pool::PoolManager;
-use crate::models::{ERDData, ERDEdge, ERDNode};
-use crate::security::keyring;
+use crate::db::schema::list_relations;
+use crate::error::{AppError, ErrorContext};
+use crate::models::{ERDData, ERDEdge, ERDNode, RelationKind};
+use crate::state::AppState;
 use anyhow::Result;
 use serde::Deserialize;
-use std::sync::Arc;
-
-async fn get_pool_manager() -> Arc<PoolManager> {
-    static POOL_MANAGER: tokio::sync::OnceCell<Arc<PoolManager>> =
-        tokio::sync::OnceCell::const_new();
-    POOL_MANAGER
-        .get_or_init(|| async { Arc::new(PoolManager::new()) })
-        .await
-        .clone()
-}
-
-async fn get_client_for_connection(connection_id: &str) -> Result<Arc<tokio_postgres::Client>, String> {
-    let connections = get_connections_storage().await;
-    let conns = connections.read().await;
-
-    let config = conns
-        .iter()
-        .find(|c| c.id == connection_id)
-        .ok_or_else(|| "Connection not found".to_string())?;
-
-    let password = keyring::get_password(connection_id)
-        .map_err(|e| format!("Failed to get password: {}", e))?;
-
-    let pool_manager = get_pool_manager().await;
-    pool_manager
-        .get_client(
-            connection_id,
-            &config.host,
-            config.port,
-            &config.database,
-            &config.username,
-            &password,
-        )
-        .await
-        .map_err(|e| format!("Failed to get client: {}", e))
-}
+use tauri::State;
 
 #[derive(Debug, Deserialize)]
 pub struct GetERDDataRequest {
     pub connection_id: String,
     pub schema: Option<String>,
+    /// Relation kinds to draw; empty draws everything but sequences.
+    #[serde(default)]
+    pub kinds: Vec<RelationKind>,
 }
 
+const DEFAULT_ERD_KINDS: [RelationKind; 5] = [
+    RelationKind::Table,
+    RelationKind::PartitionedTable,
+    RelationKind::View,
+    RelationKind::MaterializedView,
+    RelationKind::ForeignTable,
+];
+
 #[tauri::command]
-pub async fn get_erd_data(request: GetERDDataRequest) -> Result<ERDData, String> {
-    let client = get_client_for_connection(&request.connection_id).await?;
+pub async fn get_erd_data(
+    state: State<'_, AppState>,
+    request: GetERDDataRequest,
+) -> Result<ERDData, AppError> {
+    let client = state.client(&request.connection_id).await?;
 
     // Get all tables
-    let schema_filter = request
-        .schema
-        .as_ref()
-        .map(|s| format!("AND table_schema = '{}'", s))
-        .unwrap_or_else(|| "".to_string());
-
-    let tables_query = format!(
-        r#"
-        SELECT DISTINCT table_schema, table_name
-        FROM information_schema.tables
-        WHERE table_type = 'BASE TABLE'
-        {}
-        ORDER BY table_schema, table_name
-    "#,
-        schema_filter
-    );
-
-    let table_rows = client
-        .query(&tables_query, &[])
+    let kinds = if request.kinds.is_empty() {
+        &DEFAULT_ERD_KINDS[..]
+    } else {
+        &request.kinds[..]
+    };
+    let relations = list_relations(&client, request.schema.as_deref(), kinds)
         .await
-        .map_err(|e| format!("Failed to query tables: {}", e))?;
+        .context("Failed to query tables")?;
 
     let mut nodes = Vec::new();
     let mut node_ids = std::collections::HashMap::new();
 
-    for row in table_rows {
-        let schema: String = row.get("table_schema");
-        let table: String = row.get("table_name");
-        let node_id = format!("{}.{}", schema, table);
+    // Partitions inherit their parent's foreign keys, so only the parent is drawn
+    for relation in relations.into_iter().filter(|r| !r.is_partition) {
+        let node_id = format!("{}.{}", relation.schema, relation.name);
         node_ids.insert(node_id.clone(), nodes.len());
         nodes.push(ERDNode {
             id: node_id.clone(),
-            label: table.clone(),
-            schema: schema.clone(),
-            table: table.clone(),
+            label: relation.name.clone(),
+            schema: relation.schema,
+            table: relation.name,
+            kind: relation.kind,
         });
     }
 
     // Get foreign key relationships
-    let fk_query = format!(
-        r#"
+    let fk_query = r#"
         SELECT
             tc.table_schema AS from_schema,
             tc.table_name AS from_table,
@@ -109,16 +74,14 @@ pub async fn get_erd_data(request: GetERDDataRequest) -> Result<ERDData, String>
             ON ccu.constraint_name = tc.constraint_name
             AND ccu.table_schema = tc.table_schema
         WHERE tc.constraint_type = 'FOREIGN KEY'
-        {}
+        AND ($1::text IS NULL OR tc.table_schema = $1)
         ORDER BY tc.table_schema, tc.table_name
-    "#,
-        schema_filter
-    );
+    "#;
 
     let fk_rows = client
-        .query(&fk_query, &[])
+        .query(fk_query, &[&request.schema])
         .await
-        .map_err(|e| format!("Failed to query foreign keys: {}", e))?;
+        .context("Failed to query foreign keys")?;
 
     let mut edges = Vec::new();
 
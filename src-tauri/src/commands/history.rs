@@ -0,0 +1,25 @@
+use crate::error::{AppError, ErrorCategory};
+use crate::state::AppState;
+use crate::storage::history::{HistoryEntry, HistoryFilter, PruneHistory};
+use tauri::State;
+
+/// Statements run through `execute_query`, newest first.
+#[tauri::command]
+pub async fn search_history(
+    state: State<'_, AppState>,
+    filter: HistoryFilter,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    state
+        .history
+        .search(&filter)
+        .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Storage).context("Failed to search query history"))
+}
+
+/// Deletes history entries by age or count and returns how many went.
+#[tauri::command]
+pub async fn prune_history(state: State<'_, AppState>, prune: PruneHistory) -> Result<usize, AppError> {
+    state
+        .history
+        .prune(&prune)
+        .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Storage).context("Failed to prune query history"))
+}
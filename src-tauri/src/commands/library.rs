@@ -0,0 +1,106 @@
+use crate::db::script::named_parameters;
+use crate::error::{AppError, ErrorCategory};
+use crate::state::AppState;
+use crate::storage::library::{ImportSummary, SavedQuery, SavedQueryParameter};
+use serde::Deserialize;
+use std::path::Path;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_saved_queries(state: State<'_, AppState>) -> Result<Vec<SavedQuery>, AppError> {
+    Ok(state.library.list().await)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveQueryRequest {
+    /// Unset saves a new query.
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub folder: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub sql: String,
+    #[serde(default)]
+    pub default_connection_id: Option<String>,
+    /// Descriptions and defaults for the placeholders in `sql`; the list
+    /// saved always matches the placeholders.
+    #[serde(default)]
+    pub parameters: Vec<SavedQueryParameter>,
+}
+
+#[tauri::command]
+pub async fn save_query(
+    state: State<'_, AppState>,
+    request: SaveQueryRequest,
+) -> Result<SavedQuery, AppError> {
+    if request.name.trim().is_empty() {
+        return Err(AppError::validation("A saved query needs a name"));
+    }
+    let query = SavedQuery {
+        id: request.id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        name: request.name,
+        folder: request.folder,
+        description: request.description,
+        tags: request.tags,
+        sql: request.sql,
+        default_connection_id: request.default_connection_id,
+        parameters: request.parameters,
+        created_at: 0,
+        updated_at: 0,
+    };
+    state
+        .library
+        .upsert(query)
+        .await
+        .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Storage).context("Failed to save query"))
+}
+
+#[tauri::command]
+pub async fn delete_saved_query(state: State<'_, AppState>, id: String) -> Result<(), AppError> {
+    state
+        .library
+        .remove(&id)
+        .await
+        .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Storage).context("Failed to delete saved query"))
+}
+
+/// Writes the library to one file for sharing. Returns how many queries it
+/// holds.
+#[tauri::command]
+pub async fn export_saved_queries(state: State<'_, AppState>, path: String) -> Result<usize, AppError> {
+    state
+        .library
+        .export(Path::new(&path))
+        .await
+        .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Storage).context("Failed to export saved queries"))
+}
+
+/// Merges a file written by `export_saved_queries` into the library.
+#[tauri::command]
+pub async fn import_saved_queries(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<ImportSummary, AppError> {
+    let connection_ids: Vec<String> = state
+        .connections
+        .list()
+        .await
+        .into_iter()
+        .map(|c| c.id)
+        .collect();
+    state
+        .library
+        .import(Path::new(&path), &connection_ids)
+        .await
+        .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Storage).context("Failed to import saved queries"))
+}
+
+/// The `:name` placeholders `execute_query` expects values for, in order
+/// of first use.
+#[tauri::command]
+pub async fn get_query_parameters(sql: String) -> Result<Vec<String>, AppError> {
+    Ok(named_parameters(&sql).names)
+}
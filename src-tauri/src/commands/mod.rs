@@ -1,4 +1,9 @@
 pub mod connection;
 pub mod erd;
+pub mod history;
+pub mod library;
+pub mod objects;
 pub mod query;
+pub mod session;
+pub mod settings;
 pub mod table;
@@ -0,0 +1,95 @@
+use crate::db::ddl::{generate_ddl, DdlObjectKind};
+use crate::db::objects::{list_extensions, list_functions, list_sequences, list_types};
+use crate::error::{AppError, ErrorContext};
+use crate::models::{ExtensionInfo, FunctionInfo, SequenceInfo, TypeInfo};
+use crate::state::AppState;
+use serde::Deserialize;
+use tauri::State;
+
+#[derive(Debug, Deserialize)]
+pub struct GetSchemaObjectsRequest {
+    pub connection_id: String,
+    pub schema: String,
+}
+
+/// Functions, procedures and aggregates in a schema, with their source.
+#[tauri::command]
+pub async fn get_functions(
+    state: State<'_, AppState>,
+    request: GetSchemaObjectsRequest,
+) -> Result<Vec<FunctionInfo>, AppError> {
+    let client = state.client(&request.connection_id).await?;
+    list_functions(&client, &request.schema)
+        .await
+        .context("Failed to query functions")
+}
+
+#[tauri::command]
+pub async fn get_sequences(
+    state: State<'_, AppState>,
+    request: GetSchemaObjectsRequest,
+) -> Result<Vec<SequenceInfo>, AppError> {
+    let client = state.client(&request.connection_id).await?;
+    list_sequences(&client, &request.schema)
+        .await
+        .context("Failed to query sequences")
+}
+
+/// Enum, composite and domain types in a schema.
+#[tauri::command]
+pub async fn get_types(
+    state: State<'_, AppState>,
+    request: GetSchemaObjectsRequest,
+) -> Result<Vec<TypeInfo>, AppError> {
+    let client = state.client(&request.connection_id).await?;
+    list_types(&client, &request.schema)
+        .await
+        .context("Failed to query types")
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetExtensionsRequest {
+    pub connection_id: String,
+}
+
+#[tauri::command]
+pub async fn get_extensions(
+    state: State<'_, AppState>,
+    request: GetExtensionsRequest,
+) -> Result<Vec<ExtensionInfo>, AppError> {
+    let client = state.client(&request.connection_id).await?;
+    list_extensions(&client)
+        .await
+        .context("Failed to query extensions")
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetDdlRequest {
+    pub connection_id: String,
+    pub kind: DdlObjectKind,
+    pub schema: String,
+    pub name: String,
+    /// Identity arguments of the function to script, e.g. `integer, text`;
+    /// only needed when the name is overloaded.
+    #[serde(default)]
+    pub arguments: Option<String>,
+}
+
+/// A runnable script that recreates the object, with its indexes,
+/// constraints, comments, owner and grants.
+#[tauri::command]
+pub async fn get_ddl(
+    state: State<'_, AppState>,
+    request: GetDdlRequest,
+) -> Result<String, AppError> {
+    let client = state.client(&request.connection_id).await?;
+    generate_ddl(
+        &client,
+        request.kind,
+        &request.schema,
+        &request.name,
+        request.arguments.as_deref(),
+    )
+    .await
+    .context("Failed to generate DDL")
+}
@@ -1,135 +1,452 @@
-use crate::db::pool::PoolManager;
-use crate::models::QueryResult;
-use crate::security::keyring;
+use crate::db::explain::{explain, ExplainOptions};
+use crate::db::plan_diff;
+use crate::db::pool::PooledClient;
+use crate::db::results::RowReader;
+use crate::db::script::{
+    command_tag, is_read_only, is_transaction_control, named_parameters, rollback_open_transaction,
+    split_statements, NamedStatement,
+};
+use crate::db::session::Session;
+use crate::db::types::JsonParam;
+use crate::error::{AppError, ErrorCategory, ErrorContext};
+use crate::models::{
+    ExplainResult, PlanComparison, QueryResult, ResultPage, ScriptResult, StatementResult, StatementStatus,
+};
+use crate::state::AppState;
+use crate::storage::history::HistoryEntry;
 use anyhow::Result;
 use serde::Deserialize;
-use std::sync::Arc;
+use serde_json::{Map, Value};
+use std::time::Instant;
+use tauri::State;
+use tokio::sync::OwnedMutexGuard;
+use tokio_postgres::types::ToSql;
 
-async fn get_pool_manager() -> Arc<PoolManager> {
-    static POOL_MANAGER: tokio::sync::OnceCell<Arc<PoolManager>> =
-        tokio::sync::OnceCell::const_new();
-    POOL_MANAGER
-        .get_or_init(|| async { Arc::new(PoolManager::new()) })
-        .await
-        .clone()
+#[derive(Debug, Deserialize)]
+pub struct ExecuteQueryRequest {
+    pub connection_id: String,
+    pub query: String,
+    /// Caller-chosen id that `cancel_query` can target while this runs.
+    #[serde(default)]
+    pub execution_id: Option<String>,
+    /// Rows to return up front; the rest stay on the server behind
+    /// `result_id` for `fetch_more`. Unset reads the whole result, up to the
+    /// `max_result_rows` setting. Ignored in a session, whose pinned client
+    /// can't run the next statement while a result is still open.
+    #[serde(default)]
+    pub page_size: Option<usize>,
+    /// Run on the session's pinned client, inside its transaction, instead
+    /// of on any pooled client.
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Values for the `:name` placeholders in `query`, bound as parameters
+    /// of whatever type the server infers for each. Unset runs `query` as
+    /// written.
+    #[serde(default)]
+    pub params: Option<Map<String, Value>>,
 }
 
-async fn get_client_for_connection(connection_id: &str) -> Result<Arc<tokio_postgres::Client>, String> {
-    use crate::commands::connection::get_connections_storage;
+/// Where statements run: any pooled client, or a session's pinned one.
+enum Target {
+    Pooled(PooledClient),
+    Session(OwnedMutexGuard<Session>),
+}
+
+impl Target {
+    async fn open(
+        state: &AppState,
+        connection_id: &str,
+        session_id: Option<&str>,
+    ) -> Result<Self, AppError> {
+        let Some(session_id) = session_id else {
+            return Ok(Self::Pooled(state.client(connection_id).await?));
+        };
+        let session = state.sessions.lock(session_id).await?;
+        if session.connection_id() != connection_id {
+            return Err(AppError::validation(
+                "Session belongs to a different connection",
+            ));
+        }
+        Ok(Self::Session(session))
+    }
+
+    fn client(&self) -> &PooledClient {
+        match self {
+            Self::Pooled(client) => client,
+            Self::Session(session) => session.client(),
+        }
+    }
 
-    let connections = get_connections_storage().await;
-    let conns = connections.read().await;
+    async fn before_statement(&mut self, sql: &str) -> Result<(), AppError> {
+        match self {
+            Self::Pooled(_) => Ok(()),
+            Self::Session(session) => session.before_statement(sql).await,
+        }
+    }
+
+    async fn after_statement(&mut self, sql: &str, succeeded: bool) {
+        if let Self::Session(session) = self {
+            session.after_statement(sql, succeeded).await;
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn execute_query(
+    state: State<'_, AppState>,
+    request: ExecuteQueryRequest,
+) -> Result<QueryResult, AppError> {
+    let max_rows = state.settings.get().await.max_result_rows;
+    let mut target = Target::open(
+        &state,
+        &request.connection_id,
+        request.session_id.as_deref(),
+    )
+    .await?;
+    let named = request.params.as_ref().map(|_| named_parameters(&request.query));
+    let (sql, values) = match (&named, &request.params) {
+        (Some(named), Some(params)) => (named.sql.as_str(), bind_named(named, params)?),
+        _ => (request.query.as_str(), Vec::new()),
+    };
+    let params: Vec<&(dyn ToSql + Sync)> = values.iter().map(|v| v as &(dyn ToSql + Sync)).collect();
+    // A pooled client would go back to the pool with the transaction open
+    if matches!(target, Target::Pooled(_)) && is_transaction_control(sql) {
+        return Err(AppError::validation(
+            "Transaction control statements need a session; turn auto-commit off or use the transaction buttons",
+        ));
+    }
+    target.before_statement(sql).await?;
+
+    let execution = state.running_queries.start(request.execution_id.clone(), target.client());
+    let (page_size, cancelable) = match target {
+        Target::Pooled(_) => (request.page_size, is_read_only(sql)),
+        // Canceling would abort the session's transaction
+        Target::Session(_) => (None, false),
+    };
+    let started_at = chrono::Utc::now().timestamp_millis();
+    let started = Instant::now();
+    let result = execution
+        .finish(start_query(target.client(), sql, &params, page_size, max_rows, cancelable).await)
+        .map_err(|e| match &named {
+            Some(named) => e.map_position(|p| named.original_position(p)),
+            None => e,
+        });
+    let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+    target.after_statement(sql, result.is_ok()).await;
 
-    let config = conns
+    let row_count = result.as_ref().map(|(result, reader)| {
+        reader
+            .rows_affected()
+            .or((!result.has_more).then_some(result.row_count as u64))
+    });
+    record_history(&state, &request, started_at, duration_ms, row_count);
+    let (mut result, reader) = result?;
+
+    if let Target::Pooled(client) = target {
+        if result.has_more {
+            result.result_id = Some(state.open_results.insert(client, reader));
+        }
+    }
+    Ok(result)
+}
+
+/// The values of a rewritten statement's parameters, in `$n` order.
+fn bind_named<'a>(
+    named: &NamedStatement,
+    params: &'a Map<String, Value>,
+) -> Result<Vec<JsonParam<'a>>, AppError> {
+    let missing: Vec<String> = named
+        .names
         .iter()
-        .find(|c| c.id == connection_id)
-        .ok_or_else(|| "Connection not found".to_string())?;
-
-    let password = keyring::get_password(connection_id)
-        .map_err(|e| format!("Failed to get password: {}", e))?;
-
-    let pool_manager = get_pool_manager().await;
-    pool_manager
-        .get_client(
-            connection_id,
-            &config.host,
-            config.port,
-            &config.database,
-            &config.username,
-            &password,
-        )
+        .filter(|name| !params.contains_key(name.as_str()))
+        .map(|name| format!(":{}", name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::validation(format!(
+            "No value given for {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(named
+        .names
+        .iter()
+        .map(|name| JsonParam(&params[name.as_str()]))
+        .collect())
+}
+
+/// Adds a statement run by `execute_query` to the query history. Failing to
+/// record it doesn't fail the statement.
+fn record_history(
+    state: &AppState,
+    request: &ExecuteQueryRequest,
+    started_at: i64,
+    duration_ms: f64,
+    row_count: Result<Option<u64>, &AppError>,
+) {
+    let error = row_count.as_ref().err();
+    let entry = HistoryEntry {
+        id: 0,
+        connection_id: request.connection_id.clone(),
+        sql: request.query.clone(),
+        started_at,
+        duration_ms,
+        row_count: row_count.as_ref().ok().copied().flatten().map(|count| count as i64),
+        succeeded: error.is_none(),
+        error_code: error.and_then(|e| e.db.as_ref()).map(|db| db.code.clone()),
+        error_message: error.map(|e| e.message.clone()),
+    };
+    if let Err(e) = state.history.record(&entry) {
+        eprintln!("failed to record query history: {:#}", e);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FetchMoreRequest {
+    pub result_id: String,
+    pub page_size: usize,
+}
+
+/// Reads the next page of a result opened by `execute_query`. The handle is
+/// released once the last page has been returned.
+#[tauri::command]
+pub async fn fetch_more(
+    state: State<'_, AppState>,
+    request: FetchMoreRequest,
+) -> Result<ResultPage, AppError> {
+    state
+        .open_results
+        .fetch(&request.result_id, request.page_size)
+        .await
+        .map_err(|e| AppError::from_anyhow(e, ErrorCategory::NotFound))
+}
+
+/// Discards the unread rows of an open result. Returns false if it was
+/// already closed.
+#[tauri::command]
+pub async fn close_result(state: State<'_, AppState>, result_id: String) -> Result<bool, AppError> {
+    state
+        .open_results
+        .close(&result_id)
         .await
-        .map_err(|e| format!("Failed to get client: {}", e))
+        .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Connection))
 }
 
 #[derive(Debug, Deserialize)]
-pub struct ExecuteQueryRequest {
+pub struct ExecuteScriptRequest {
     pub connection_id: String,
-    pub query: String,
+    pub script: String,
+    /// Caller-chosen id that `cancel_query` can target while this runs.
+    #[serde(default)]
+    pub execution_id: Option<String>,
+    /// Keep going after a failed statement instead of stopping there.
+    #[serde(default)]
+    pub continue_on_error: bool,
+    /// Run inside a session's transaction; see `ExecuteQueryRequest`.
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
+/// Runs a semicolon-separated script statement by statement on one
+/// connection, so session state such as temp tables carries over, and
+/// reports each statement's outcome. Outside a session, a transaction the
+/// script leaves open is rolled back.
 #[tauri::command]
-pub async fn execute_query(request: ExecuteQueryRequest) -> Result<QueryResult, String> {
-    let client = get_client_for_connection(&request.connection_id).await?;
+pub async fn execute_script(
+    state: State<'_, AppState>,
+    request: ExecuteScriptRequest,
+) -> Result<ScriptResult, AppError> {
+    let max_rows = state.settings.get().await.max_result_rows;
+    let mut target = Target::open(
+        &state,
+        &request.connection_id,
+        request.session_id.as_deref(),
+    )
+    .await?;
+    let execution = state.running_queries.start(request.execution_id, target.client());
 
-    let rows = client
-        .query(&request.query, &[])
-        .await
-        .map_err(|e| format!("Query execution failed: {}", e))?;
+    let mut statements = Vec::new();
+    let mut stop = false;
+    let mut controls_transaction = false;
 
-    if rows.is_empty() {
-        return Ok(QueryResult {
-            columns: vec![],
-            rows: vec![],
-            row_count: 0,
-        });
+    for statement in split_statements(&request.script) {
+        let offset = statement.offset as i64;
+        let mut result = StatementResult {
+            sql: statement.sql,
+            line: statement.line,
+            status: StatementStatus::Skipped,
+            command_tag: None,
+            rows_affected: None,
+            result: None,
+            duration_ms: 0.0,
+            error: None,
+        };
+        if stop {
+            statements.push(result);
+            continue;
+        }
+        controls_transaction |= is_transaction_control(&result.sql);
+
+        // Canceling unwanted rows is only harmless outside a transaction
+        let cancelable = matches!(target, Target::Pooled(_))
+            && !controls_transaction
+            && is_read_only(&result.sql);
+        let started = Instant::now();
+        let outcome = match target.before_statement(&result.sql).await {
+            Ok(()) => {
+                start_query(target.client(), &result.sql, &[], None, max_rows, cancelable).await
+            }
+            Err(e) => Err(e),
+        };
+        result.duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+        target.after_statement(&result.sql, outcome.is_ok()).await;
+
+        match outcome {
+            Ok((rows, reader)) => {
+                result.status = StatementStatus::Ok;
+                result.rows_affected = reader.rows_affected();
+                result.command_tag = Some(command_tag(&result.sql, result.rows_affected));
+                if !rows.columns.is_empty() {
+                    result.result = Some(rows);
+                }
+            }
+            Err(e) => {
+                result.status = StatementStatus::Error;
+                if execution.is_canceled() {
+                    result.error = Some(AppError::canceled());
+                    stop = true;
+                } else {
+                    result.error = Some(e.shift_position(offset));
+                    stop = !request.continue_on_error;
+                }
+            }
+        }
+        statements.push(result);
     }
+    drop(execution);
+
+    let rolled_back_open_transaction = if let Target::Pooled(client) = &target {
+        controls_transaction
+            && rollback_open_transaction(client)
+                .await
+                .context("Failed to roll back open transaction")?
+    } else {
+        false
+    };
 
-    let columns: Vec<String> = rows[0]
+    Ok(ScriptResult {
+        statements,
+        rolled_back_open_transaction,
+    })
+}
+
+/// Runs a single statement and reads the whole result, up to `max_rows`.
+pub(crate) async fn run_query(
+    client: &PooledClient,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+    max_rows: usize,
+) -> Result<QueryResult, AppError> {
+    let (result, _) = start_query(client, query, params, None, max_rows, is_read_only(query)).await?;
+    Ok(result)
+}
+
+/// Runs a single statement and converts its first `page_size` rows (all of
+/// them when unset) to JSON. The statement is prepared first so column
+/// names and types are known even when no rows come back. The reader is
+/// returned positioned after the first page, or finished if no page
+/// follows; see `RowReader::close` for `cancelable`.
+async fn start_query(
+    client: &PooledClient,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+    page_size: Option<usize>,
+    max_rows: usize,
+    cancelable: bool,
+) -> Result<(QueryResult, RowReader), AppError> {
+    let statement = client.prepare(query).await?;
+    let mut reader = RowReader::start(client, &statement, params, max_rows, cancelable).await?;
+    let page = reader.next_page(page_size.unwrap_or(usize::MAX)).await?;
+    if !page.has_more {
+        reader.close(client).await?;
+    }
+
+    let columns = statement
         .columns()
         .iter()
         .map(|c| c.name().to_string())
         .collect();
-
-    let result_rows: Vec<Vec<serde_json::Value>> = rows
+    let column_types = statement
+        .columns()
         .iter()
-        .map(|row| {
-            columns
-                .iter()
-                .enumerate()
-                .map(|(i, _)| {
-                    let value: serde_json::Value = match row.try_get::<_, String>(i) {
-                        Ok(v) => serde_json::Value::String(v),
-                        Err(_) => {
-                            // Try other types
-                            if let Ok(v) = row.try_get::<_, i32>(i) {
-                                serde_json::Value::Number(v.into())
-                            } else if let Ok(v) = row.try_get::<_, i64>(i) {
-                                serde_json::Value::Number(v.into())
-                            } else if let Ok(v) = row.try_get::<_, f64>(i) {
-                                serde_json::Value::Number(
-                                    serde_json::Number::from_f64(v).unwrap_or(0.into())
-                                )
-                            } else if let Ok(v) = row.try_get::<_, bool>(i) {
-                                serde_json::Value::Bool(v)
-                            } else {
-                                serde_json::Value::Null
-                            }
-                        }
-                    };
-                    value
-                })
-                .collect()
-        })
+        .map(|c| c.type_().name().to_string())
         .collect();
 
-    let row_count = result_rows.len();
-
-    Ok(QueryResult {
+    let result = QueryResult {
         columns,
-        rows: result_rows,
-        row_count,
-    })
+        column_types,
+        rows: page.rows,
+        row_count: page.row_count,
+        undecodable_cells: page.undecodable_cells,
+        result_id: None,
+        has_more: page.has_more,
+        truncated: page.truncated,
+    };
+    Ok((result, reader))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ExplainQueryRequest {
+    pub connection_id: String,
+    pub query: String,
+    #[serde(default)]
+    pub execution_id: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub options: ExplainOptions,
+}
+
+/// Explains a statement. With ANALYZE the statement runs and its changes
+/// are rolled back; see `db::explain::explain`.
 #[tauri::command]
-pub async fn explain_query(request: ExecuteQueryRequest) -> Result<String, String> {
-    let client = get_client_for_connection(&request.connection_id).await?;
+pub async fn explain_query(
+    state: State<'_, AppState>,
+    request: ExplainQueryRequest,
+) -> Result<ExplainResult, AppError> {
+    let mut target = Target::open(
+        &state,
+        &request.connection_id,
+        request.session_id.as_deref(),
+    )
+    .await?;
+    target.before_statement(&request.query).await?;
+    let execution = state.running_queries.start(request.execution_id, target.client());
 
-    let explain_query = format!("EXPLAIN ANALYZE {}", request.query);
+    let result = execution.finish(explain(target.client(), &request.query, &request.options).await);
+    target.after_statement(&request.query, result.is_ok()).await;
+    result
+}
 
-    let rows = client
-        .query(&explain_query, &[])
-        .await
-        .map_err(|e| format!("EXPLAIN query failed: {}", e))?;
+#[derive(Debug, Deserialize)]
+pub struct ComparePlansRequest {
+    pub before: Value,
+    pub after: Value,
+}
 
-    let result: String = rows
-        .iter()
-        .map(|row| {
-            row.get::<_, String>(0)
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
+/// Diffs two plans, each a plan from `explain_query` or pasted
+/// `EXPLAIN (FORMAT JSON)` output.
+#[tauri::command]
+pub async fn compare_plans(request: ComparePlansRequest) -> Result<PlanComparison, AppError> {
+    plan_diff::compare_plans(request.before, request.after)
+}
 
-    Ok(result)
+/// Cancels a running `execute_query` or `explain_query` by execution id.
+/// Returns false if nothing with that id is running anymore.
+#[tauri::command]
+pub async fn cancel_query(state: State<'_, AppState>, execution_id: String) -> Result<bool, AppError> {
+    state
+        .running_queries
+        .cancel(&execution_id)
+        .await
+        .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Connection).context("Failed to cancel query"))
 }
@@ -0,0 +1,139 @@
+use crate::error::AppError;
+use crate::models::SessionInfo;
+use crate::state::AppState;
+use serde::Deserialize;
+use tauri::State;
+
+#[derive(Debug, Deserialize)]
+pub struct OpenSessionRequest {
+    /// Caller-chosen id, typically the editor tab's.
+    pub session_id: String,
+    pub connection_id: String,
+    #[serde(default = "default_auto_commit")]
+    pub auto_commit: bool,
+}
+
+fn default_auto_commit() -> bool {
+    true
+}
+
+/// Pins a dedicated client, outside the connection's pool, to a session so `execute_query` and
+/// `execute_script` calls that pass its id share one transaction. A session
+/// already open under the same id is closed, rolling back its transaction.
+#[tauri::command]
+pub async fn open_session(
+    state: State<'_, AppState>,
+    request: OpenSessionRequest,
+) -> Result<SessionInfo, AppError> {
+    let client = state.dedicated_client(&request.connection_id).await?;
+    Ok(state
+        .sessions
+        .open(
+            &request.session_id,
+            &request.connection_id,
+            client,
+            request.auto_commit,
+        )
+        .await)
+}
+
+#[tauri::command]
+pub async fn get_session(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<SessionInfo, AppError> {
+    Ok(state.sessions.lock(&session_id).await?.info(&session_id))
+}
+
+/// Rolls back any open transaction and releases the session's client.
+/// Returns false if it was already closed.
+#[tauri::command]
+pub async fn close_session(state: State<'_, AppState>, session_id: String) -> Result<bool, AppError> {
+    Ok(state.sessions.close(&session_id).await)
+}
+
+#[tauri::command]
+pub async fn begin_transaction(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<SessionInfo, AppError> {
+    let mut session = state.sessions.lock(&session_id).await?;
+    session.begin().await?;
+    Ok(session.info(&session_id))
+}
+
+#[tauri::command]
+pub async fn commit_transaction(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<SessionInfo, AppError> {
+    let mut session = state.sessions.lock(&session_id).await?;
+    session.commit().await?;
+    Ok(session.info(&session_id))
+}
+
+#[tauri::command]
+pub async fn rollback_transaction(
+    state: State<'_, AppState>,
+    session_id: String,
+) -> Result<SessionInfo, AppError> {
+    let mut session = state.sessions.lock(&session_id).await?;
+    session.rollback().await?;
+    Ok(session.info(&session_id))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SavepointRequest {
+    pub session_id: String,
+    pub name: String,
+}
+
+/// Sets a savepoint, opening a transaction first when auto-commit is off.
+#[tauri::command]
+pub async fn create_savepoint(
+    state: State<'_, AppState>,
+    request: SavepointRequest,
+) -> Result<SessionInfo, AppError> {
+    if request.name.is_empty() {
+        return Err(AppError::validation("Savepoint name is required"));
+    }
+    let mut session = state.sessions.lock(&request.session_id).await?;
+    session.savepoint(&request.name).await?;
+    Ok(session.info(&request.session_id))
+}
+
+#[tauri::command]
+pub async fn rollback_to_savepoint(
+    state: State<'_, AppState>,
+    request: SavepointRequest,
+) -> Result<SessionInfo, AppError> {
+    let mut session = state.sessions.lock(&request.session_id).await?;
+    session.rollback_to(&request.name).await?;
+    Ok(session.info(&request.session_id))
+}
+
+#[tauri::command]
+pub async fn release_savepoint(
+    state: State<'_, AppState>,
+    request: SavepointRequest,
+) -> Result<SessionInfo, AppError> {
+    let mut session = state.sessions.lock(&request.session_id).await?;
+    session.release(&request.name).await?;
+    Ok(session.info(&request.session_id))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetAutoCommitRequest {
+    pub session_id: String,
+    pub enabled: bool,
+}
+
+#[tauri::command]
+pub async fn set_auto_commit(
+    state: State<'_, AppState>,
+    request: SetAutoCommitRequest,
+) -> Result<SessionInfo, AppError> {
+    let mut session = state.sessions.lock(&request.session_id).await?;
+    session.set_auto_commit(request.enabled)?;
+    Ok(session.info(&request.session_id))
+}
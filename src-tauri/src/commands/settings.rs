@@ -0,0 +1,54 @@
+use crate::error::{AppError, ErrorCategory};
+use crate::state::AppState;
+use crate::storage::settings::Settings;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_settings(state: State<'_, AppState>) -> Result<Settings, AppError> {
+    Ok(state.settings.get().await)
+}
+
+#[tauri::command]
+pub async fn update_settings(state: State<'_, AppState>, settings: Settings) -> Result<(), AppError> {
+    validate(&settings)?;
+    state
+        .settings
+        .update(settings)
+        .await
+        .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Storage).context("Failed to save settings"))
+}
+
+/// Rejects settings the pool or the result readers can't work with.
+fn validate(settings: &Settings) -> Result<(), AppError> {
+    if settings.table_page_size <= 0 {
+        return Err(AppError::validation("Table page size must be at least 1"));
+    }
+    if settings.max_result_rows == 0 {
+        return Err(AppError::validation("Maximum result rows must be at least 1"));
+    }
+
+    let pool = &settings.pool;
+    if pool.max_size == 0 {
+        return Err(AppError::validation("Maximum pool size must be at least 1"));
+    }
+    if pool.min_size > pool.max_size {
+        return Err(AppError::validation(format!(
+            "Minimum pool size ({}) can't exceed the maximum ({})",
+            pool.min_size, pool.max_size
+        )));
+    }
+    for (name, secs) in [
+        ("Idle timeout", pool.idle_timeout_secs),
+        ("Maximum client lifetime", pool.max_lifetime_secs),
+        ("Health check interval", pool.health_check_interval_secs),
+        ("Acquire timeout", pool.acquire_timeout_secs),
+    ] {
+        if secs == 0 {
+            return Err(AppError::validation(format!(
+                "{} must be at least 1 second",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
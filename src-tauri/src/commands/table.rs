@@ -1,47 +1,18 @@
-use crate::db::pool::PoolManager;
-use crate::db::schema::get_table_schema;
-use crate::models::TableSchema;
-use crate::security::keyring;
+use crate::commands::query::run_query;
+use crate::db::browse::{
+    count_rows, order_by_clause, where_clause, Cursor, Filter, Keyset, SortColumn,
+};
+use crate::db::editing::{apply_edits, row_identity, RowEdit};
+use crate::db::schema::{get_table_details, get_table_schema, list_relations};
+use crate::db::sql::{qualified_name, Params};
+use crate::error::{AppError, ErrorContext};
+use crate::models::{
+    PagingMode, QueryResult, RelationKind, TableData, TableDetails, TableInfo, TableSchema,
+};
+use crate::state::AppState;
 use anyhow::Result;
 use serde::Deserialize;
-use std::sync::Arc;
-
-async fn get_pool_manager() -> Arc<PoolManager> {
-    static POOL_MANAGER: tokio::sync::OnceCell<Arc<PoolManager>> =
-        tokio::sync::OnceCell::const_new();
-    POOL_MANAGER
-        .get_or_init(|| async { Arc::new(PoolManager::new()) })
-        .await
-        .clone()
-}
-
-async fn get_client_for_connection(connection_id: &str) -> Result<Arc<tokio_postgres::Client>, String> {
-    use crate::commands::connection::get_connections_storage;
-
-    let connections = get_connections_storage().await;
-    let conns = connections.read().await;
-
-    let config = conns
-        .iter()
-        .find(|c| c.id == connection_id)
-        .ok_or_else(|| "Connection not found".to_string())?;
-
-    let password = keyring::get_password(connection_id)
-        .map_err(|e| format!("Failed to get password: {}", e))?;
-
-    let pool_manager = get_pool_manager().await;
-    pool_manager
-        .get_client(
-            connection_id,
-            &config.host,
-            config.port,
-            &config.database,
-            &config.username,
-            &password,
-        )
-        .await
-        .map_err(|e| format!("Failed to get client: {}", e))
-}
+use tauri::State;
 
 #[derive(Debug, Deserialize)]
 pub struct GetTableSchemaRequest {
@@ -52,12 +23,25 @@ pub struct GetTableSchemaRequest {
 
 #[tauri::command]
 pub async fn get_table_schema_cmd(
+    state: State<'_, AppState>,
     request: GetTableSchemaRequest,
-) -> Result<TableSchema, String> {
-    let client = get_client_for_connection(&request.connection_id).await?;
+) -> Result<TableSchema, AppError> {
+    let client = state.client(&request.connection_id).await?;
     get_table_schema(&client, &request.schema, &request.table)
         .await
-        .map_err(|e| format!("Failed to get table schema: {}", e))
+        .context("Failed to get table schema")
+}
+
+/// Columns along with the table's indexes, constraints and triggers.
+#[tauri::command]
+pub async fn get_table_details_cmd(
+    state: State<'_, AppState>,
+    request: GetTableSchemaRequest,
+) -> Result<TableDetails, AppError> {
+    let client = state.client(&request.connection_id).await?;
+    get_table_details(&client, &request.schema, &request.table)
+        .await
+        .context("Failed to get table details")
 }
 
 #[derive(Debug, Deserialize)]
@@ -66,27 +50,155 @@ pub struct GetTableDataRequest {
     pub schema: String,
     pub table: String,
     pub limit: Option<i64>,
+    /// Only used for tables without a row identity; see `PagingMode`.
     pub offset: Option<i64>,
+    /// `next_cursor` or `prev_cursor` from the previous page. Unset starts
+    /// from the first page.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub order_by: Vec<SortColumn>,
+    /// Combined with `AND`.
+    #[serde(default)]
+    pub filters: Vec<Filter>,
 }
 
+/// Reads one page of a table, sorted and filtered on the server, along with
+/// the number of matching rows. Tables with a primary or unique key are
+/// paged by keyset, others by offset.
 #[tauri::command]
-pub async fn get_table_data(request: GetTableDataRequest) -> Result<crate::models::QueryResult, String> {
-    use crate::commands::query::execute_query;
-    use crate::commands::query::ExecuteQueryRequest;
+pub async fn get_table_data(
+    state: State<'_, AppState>,
+    request: GetTableDataRequest,
+) -> Result<TableData, AppError> {
+    let client = state.client(&request.connection_id).await?;
+
+    let settings = state.settings.get().await;
+    let limit = request.limit.unwrap_or(settings.table_page_size);
+    let (page_size, keyset_rows) = page_size(limit, settings.max_result_rows)?;
+    if request.offset.is_some_and(|offset| offset < 0) {
+        return Err(AppError::validation("Offset can't be negative"));
+    }
+    let table = qualified_name(&request.schema, &request.table);
+
+    // Only the first page looks up the row identity; cursors carry it on
+    let cursor = request.cursor.as_deref().map(Cursor::decode).transpose()?;
+    let identity = match &cursor {
+        Some(cursor) => Some(cursor.identity().to_vec()),
+        None => row_identity(&client, &request.schema, &request.table)
+            .await
+            .context("Failed to look up row identity")?
+            .map(|identity| identity.columns),
+    };
+    let keyset = identity.map(|identity| Keyset::new(&request.order_by, &identity));
+    if let (Some(keyset), Some(cursor)) = (&keyset, &cursor) {
+        keyset.check_cursor(cursor)?;
+    }
 
-    let limit = request.limit.unwrap_or(100);
-    let offset = request.offset.unwrap_or(0);
+    let mut params = Params::default();
+    let mut filter = where_clause(&request.filters, &mut params)?;
+    let (paging, next_cursor, prev_cursor, result) = match &keyset {
+        Some(keyset) => {
+            let backward = cursor.as_ref().is_some_and(Cursor::backward);
+            if let Some(cursor) = &cursor {
+                let condition = keyset.condition(cursor, &mut params);
+                filter = if filter.is_empty() {
+                    format!(" WHERE {}", condition)
+                } else {
+                    format!("{} AND {}", filter, condition)
+                };
+            }
 
-    let query = format!(
-        "SELECT * FROM {}.{} LIMIT {} OFFSET {}",
-        request.schema, request.table, limit, offset
-    );
+            // One extra row tells whether another page follows
+            let query = format!(
+                "SELECT * FROM {}{}{} LIMIT {}",
+                table,
+                filter,
+                keyset.order_by_clause(backward),
+                params.push(limit.saturating_add(1))
+            );
+            let mut result = run_query(&client, &query, &params.as_refs(), keyset_rows)
+                .await
+                .context("Failed to read table")?;
+            let more = result.rows.len() > page_size;
+            truncate_rows(&mut result, page_size);
+            if backward {
+                reverse_rows(&mut result);
+            }
 
-    execute_query(ExecuteQueryRequest {
-        connection_id: request.connection_id,
-        query,
+            let (has_next, has_prev) = if backward {
+                (true, more)
+            } else {
+                (more, cursor.is_some())
+            };
+            let next_cursor = result
+                .rows
+                .last()
+                .filter(|_| has_next)
+                .and_then(|row| keyset.cursor(&result.columns, row, false));
+            let prev_cursor = result
+                .rows
+                .first()
+                .filter(|_| has_prev)
+                .and_then(|row| keyset.cursor(&result.columns, row, true));
+            (PagingMode::Keyset, next_cursor, prev_cursor, result)
+        }
+        None => {
+            let query = format!(
+                "SELECT * FROM {}{}{} LIMIT {} OFFSET {}",
+                table,
+                filter,
+                order_by_clause(&request.order_by),
+                params.push(limit),
+                params.push(request.offset.unwrap_or(0))
+            );
+            let result = run_query(&client, &query, &params.as_refs(), page_size)
+                .await
+                .context("Failed to read table")?;
+            (PagingMode::Offset, None, None, result)
+        }
+    };
+
+    let (total_rows, total_is_estimate) =
+        count_rows(&client, &request.schema, &request.table, &request.filters)
+            .await
+            .context("Failed to count rows")?;
+
+    Ok(TableData {
+        result,
+        total_rows,
+        total_is_estimate,
+        paging,
+        next_cursor,
+        prev_cursor,
     })
-    .await
+}
+
+/// Checks a requested page size against `max_result_rows`. Returns it along
+/// with the number of rows a keyset page reads: the extra row that tells
+/// whether another page follows is read even for a page of the maximum size.
+fn page_size(limit: i64, max_result_rows: usize) -> Result<(usize, usize), AppError> {
+    match usize::try_from(limit) {
+        Ok(size) if (1..=max_result_rows).contains(&size) => Ok((size, size + 1)),
+        _ => Err(AppError::validation(format!(
+            "Page size must be between 1 and {}",
+            max_result_rows
+        ))),
+    }
+}
+
+fn truncate_rows(result: &mut QueryResult, len: usize) {
+    result.rows.truncate(len);
+    result.row_count = result.rows.len();
+    result.undecodable_cells.retain(|[row, _]| *row < len);
+}
+
+fn reverse_rows(result: &mut QueryResult) {
+    let len = result.rows.len();
+    result.rows.reverse();
+    for cell in &mut result.undecodable_cells {
+        cell[0] = len - 1 - cell[0];
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -94,56 +206,18 @@ pub struct UpdateTableDataRequest {
     pub connection_id: String,
     pub schema: String,
     pub table: String,
-    pub updates: Vec<TableUpdate>,
-}
-
-#[derive(Debug, Deserialize)]
-pub struct TableUpdate {
-    pub r#type: String, // "INSERT", "UPDATE", "DELETE"
-    pub data: serde_json::Value,
-    pub where_clause: Option<String>,
+    pub updates: Vec<RowEdit>,
 }
 
+/// Applies row edits as parameterized statements in a single transaction.
+/// Returns the number of rows affected.
 #[tauri::command]
-pub async fn update_table_data(request: UpdateTableDataRequest) -> Result<usize, String> {
-    let client = get_client_for_connection(&request.connection_id).await?;
-
-    let mut affected_rows = 0;
-
-    for update in request.updates {
-        match update.r#type.as_str() {
-            "INSERT" => {
-                // This is a simplified version - in production, you'd want proper SQL building
-                let query = format!("INSERT INTO {}.{} DEFAULT VALUES", request.schema, request.table);
-                let rows = client
-                    .execute(&query, &[])
-                    .await
-                    .map_err(|e| format!("INSERT failed: {}", e))?;
-                affected_rows += rows as usize;
-            }
-            "UPDATE" => {
-                let where_clause = update.where_clause.unwrap_or_else(|| "1=0".to_string());
-                let query = format!("UPDATE {}.{} SET ... WHERE {}", request.schema, request.table, where_clause);
-                let rows = client
-                    .execute(&query, &[])
-                    .await
-                    .map_err(|e| format!("UPDATE failed: {}", e))?;
-                affected_rows += rows as usize;
-            }
-            "DELETE" => {
-                let where_clause = update.where_clause.unwrap_or_else(|| "1=0".to_string());
-                let query = format!("DELETE FROM {}.{} WHERE {}", request.schema, request.table, where_clause);
-                let rows = client
-                    .execute(&query, &[])
-                    .await
-                    .map_err(|e| format!("DELETE failed: {}", e))?;
-                affected_rows += rows as usize;
-            }
-            _ => return Err("Invalid update type".to_string()),
-        }
-    }
-
-    Ok(affected_rows)
+pub async fn update_table_data(
+    state: State<'_, AppState>,
+    request: UpdateTableDataRequest,
+) -> Result<u64, AppError> {
+    let client = state.client(&request.connection_id).await?;
+    apply_edits(&client, &request.schema, &request.table, &request.updates).await
 }
 
 #[derive(Debug, Deserialize)]
@@ -157,8 +231,11 @@ pub struct SchemaInfo {
 }
 
 #[tauri::command]
-pub async fn get_schemas(request: GetSchemasRequest) -> Result<Vec<SchemaInfo>, String> {
-    let client = get_client_for_connection(&request.connection_id).await?;
+pub async fn get_schemas(
+    state: State<'_, AppState>,
+    request: GetSchemasRequest,
+) -> Result<Vec<SchemaInfo>, AppError> {
+    let client = state.client(&request.connection_id).await?;
 
     let query = r#"
         SELECT DISTINCT schema_name
@@ -170,7 +247,7 @@ pub async fn get_schemas(request: GetSchemasRequest) -> Result<Vec<SchemaInfo>,
     let rows = client
         .query(query, &[])
         .await
-        .map_err(|e| format!("Failed to query schemas: {}", e))?;
+        .context("Failed to query schemas")?;
 
     let schemas: Vec<SchemaInfo> = rows
         .iter()
@@ -186,38 +263,43 @@ pub async fn get_schemas(request: GetSchemasRequest) -> Result<Vec<SchemaInfo>,
 pub struct GetTablesRequest {
     pub connection_id: String,
     pub schema: String,
-}
-
-#[derive(Debug, serde::Serialize)]
-pub struct TableInfo {
-    pub name: String,
-    pub schema: String,
+    /// Relation kinds to list; empty lists every kind.
+    #[serde(default)]
+    pub kinds: Vec<RelationKind>,
 }
 
 #[tauri::command]
-pub async fn get_tables(request: GetTablesRequest) -> Result<Vec<TableInfo>, String> {
-    let client = get_client_for_connection(&request.connection_id).await?;
-
-    let query = r#"
-        SELECT table_name
-        FROM information_schema.tables
-        WHERE table_schema = $1
-        AND table_type = 'BASE TABLE'
-        ORDER BY table_name
-    "#;
-
-    let rows = client
-        .query(query, &[&request.schema])
+pub async fn get_tables(
+    state: State<'_, AppState>,
+    request: GetTablesRequest,
+) -> Result<Vec<TableInfo>, AppError> {
+    let client = state.client(&request.connection_id).await?;
+    let kinds = if request.kinds.is_empty() {
+        &RelationKind::ALL[..]
+    } else {
+        &request.kinds[..]
+    };
+
+    list_relations(&client, Some(&request.schema), kinds)
         .await
-        .map_err(|e| format!("Failed to query tables: {}", e))?;
+        .context("Failed to query tables")
+}
 
-    let tables: Vec<TableInfo> = rows
-        .iter()
-        .map(|row| TableInfo {
-            name: row.get("table_name"),
-            schema: request.schema.clone(),
-        })
-        .collect();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_a_probe_row_past_the_largest_page() {
+        assert_eq!(page_size(1, 1000).unwrap(), (1, 2));
+        assert_eq!(page_size(1000, 1000).unwrap(), (1000, 1001));
+    }
 
-    Ok(tables)
+    #[test]
+    fn rejects_page_sizes_out_of_range() {
+        for limit in [0, -1, 1001] {
+            let err = page_size(limit, 1000).unwrap_err();
+            assert_eq!(err.message, "Page size must be between 1 and 1000");
+        }
+    }
 }
@@ -0,0 +1,548 @@
+use crate::db::sql::{qualified_name, quote_identifier, Params};
+use crate::db::types::{decode_row, JsonParam};
+use crate::error::AppError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio_postgres::Client;
+
+/// Matches above this many rows, by the planner's estimate, are not counted
+/// exactly; a full count would scan the whole result just to show a total.
+const EXACT_COUNT_LIMIT: f64 = 100_000.0;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SortColumn {
+    pub column: String,
+    #[serde(default)]
+    pub descending: bool,
+    /// Where NULLs go; unset uses PostgreSQL's default (last ascending,
+    /// first descending).
+    #[serde(default)]
+    pub nulls: Option<NullsOrder>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FilterOperator {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    /// Pattern match on the column's text form, so it works on any type.
+    Like,
+    NotLike,
+    #[serde(rename = "ilike")]
+    ILike,
+    #[serde(rename = "not-ilike")]
+    NotILike,
+    /// `value` is a list.
+    In,
+    NotIn,
+    /// `value` is `[low, high]`, both inclusive; a `null` bound is open.
+    Between,
+    IsNull,
+    IsNotNull,
+}
+
+/// One predicate on a column. Filters are combined with `AND`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Filter {
+    pub column: String,
+    pub operator: FilterOperator,
+    /// Compared as the column's type; strings are parsed by the server, so
+    /// dates, numerics and the like can be given in their text form.
+    #[serde(default)]
+    pub value: Value,
+}
+
+/// `WHERE` clause for `filters`, or an empty string when there are none.
+pub fn where_clause<'a>(filters: &'a [Filter], params: &mut Params<'a>) -> Result<String, AppError> {
+    if filters.is_empty() {
+        return Ok(String::new());
+    }
+    let conditions = filters
+        .iter()
+        .map(|filter| condition(filter, params))
+        .collect::<Result<Vec<_>, AppError>>()?;
+    Ok(format!(" WHERE {}", conditions.join(" AND ")))
+}
+
+fn condition<'a>(filter: &'a Filter, params: &mut Params<'a>) -> Result<String, AppError> {
+    use FilterOperator::*;
+
+    let column = quote_identifier(&filter.column);
+    let value = &filter.value;
+    let missing = || {
+        AppError::validation(format!("Filter on \"{}\" needs a value", filter.column))
+    };
+
+    let condition = match filter.operator {
+        IsNull => format!("{} IS NULL", column),
+        IsNotNull => format!("{} IS NOT NULL", column),
+        // Comparing with NULL matches nothing, which is never what was meant
+        _ if value.is_null() => return Err(missing()),
+        Eq | NotEq | Lt | LtEq | Gt | GtEq => {
+            let op = match filter.operator {
+                Eq => "=",
+                NotEq => "<>",
+                Lt => "<",
+                LtEq => "<=",
+                Gt => ">",
+                _ => ">=",
+            };
+            format!("{} {} {}", column, op, params.push(JsonParam(value)))
+        }
+        Like | NotLike | ILike | NotILike => {
+            let op = match filter.operator {
+                Like => "LIKE",
+                NotLike => "NOT LIKE",
+                ILike => "ILIKE",
+                _ => "NOT ILIKE",
+            };
+            format!("{}::text {} {}", column, op, params.push(JsonParam(value)))
+        }
+        In | NotIn => {
+            if !value.is_array() {
+                return Err(AppError::validation(format!(
+                    "Filter on \"{}\" needs a list of values",
+                    filter.column
+                )));
+            }
+            // One array parameter, typed as an array of the column's type
+            let list = params.push(JsonParam(value));
+            if filter.operator == In {
+                format!("{} = ANY({})", column, list)
+            } else {
+                format!("NOT ({} = ANY({}))", column, list)
+            }
+        }
+        Between => {
+            let bounds = match value.as_array().map(Vec::as_slice) {
+                Some([low, high]) => [(low, ">="), (high, "<=")],
+                _ => {
+                    return Err(AppError::validation(format!(
+                        "Range filter on \"{}\" needs [low, high]",
+                        filter.column
+                    )))
+                }
+            };
+            let parts = bounds
+                .into_iter()
+                .filter(|(bound, _)| !bound.is_null())
+                .map(|(bound, op)| format!("{} {} {}", column, op, params.push(JsonParam(bound))))
+                .collect::<Vec<_>>();
+            if parts.is_empty() {
+                return Err(missing());
+            }
+            parts.join(" AND ")
+        }
+    };
+    Ok(format!("({})", condition))
+}
+
+/// `ORDER BY` clause for `columns`, or an empty string when there are none.
+pub fn order_by_clause(columns: &[SortColumn]) -> String {
+    if columns.is_empty() {
+        return String::new();
+    }
+    let terms = columns
+        .iter()
+        .map(|sort| {
+            let mut term = quote_identifier(&sort.column);
+            if sort.descending {
+                term.push_str(" DESC");
+            }
+            match sort.nulls {
+                Some(NullsOrder::First) => term.push_str(" NULLS FIRST"),
+                Some(NullsOrder::Last) => term.push_str(" NULLS LAST"),
+                None => {}
+            }
+            term
+        })
+        .collect::<Vec<_>>();
+    format!(" ORDER BY {}", terms.join(", "))
+}
+
+/// One column of a keyset sort order, with NULL placement made explicit.
+#[derive(Debug, Clone)]
+struct SortKey {
+    column: String,
+    descending: bool,
+    nulls_first: bool,
+    /// Identity columns are `NOT NULL`, which keeps their conditions simple.
+    not_null: bool,
+}
+
+impl SortKey {
+    fn reversed(&self) -> Self {
+        Self {
+            descending: !self.descending,
+            nulls_first: !self.nulls_first,
+            ..self.clone()
+        }
+    }
+}
+
+/// Where a keyset page starts: the sort key values of the row just before
+/// it. Sent to the client as an opaque token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Cursor {
+    /// The sort order the values belong to, so a token from another sort
+    /// is rejected instead of skipping rows.
+    order: String,
+    values: Vec<Value>,
+    /// Page towards the start of the table.
+    backward: bool,
+    /// The table's row identity columns, so later pages needn't look them
+    /// up again.
+    identity: Vec<String>,
+}
+
+impl Cursor {
+    /// Reads a token from `Keyset::cursor`. It still has to be checked
+    /// against the keyset with `Keyset::check_cursor`.
+    pub fn decode(token: &str) -> Result<Self, AppError> {
+        let invalid = || AppError::validation("Invalid page cursor");
+        let json = URL_SAFE_NO_PAD.decode(token).map_err(|_| invalid())?;
+        serde_json::from_slice(&json).map_err(|_| invalid())
+    }
+
+    pub fn backward(&self) -> bool {
+        self.backward
+    }
+
+    pub fn identity(&self) -> &[String] {
+        &self.identity
+    }
+}
+
+/// Keyset paging: each page continues from the last row of the previous
+/// one (`WHERE key > last_seen`) instead of skipping `OFFSET` rows, so deep
+/// pages cost the same as the first. The requested sort is made total by
+/// appending the table's row identity columns.
+pub struct Keyset {
+    keys: Vec<SortKey>,
+    identity: Vec<String>,
+}
+
+impl Keyset {
+    pub fn new(order_by: &[SortColumn], identity: &[String]) -> Self {
+        let mut keys: Vec<SortKey> = order_by
+            .iter()
+            .map(|sort| SortKey {
+                column: sort.column.clone(),
+                descending: sort.descending,
+                // PostgreSQL's default: NULLs sort as if larger than any value
+                nulls_first: match sort.nulls {
+                    Some(nulls) => nulls == NullsOrder::First,
+                    None => sort.descending,
+                },
+                not_null: identity.contains(&sort.column),
+            })
+            .collect();
+        for column in identity {
+            if !keys.iter().any(|key| &key.column == column) {
+                keys.push(SortKey {
+                    column: column.clone(),
+                    descending: false,
+                    nulls_first: false,
+                    not_null: true,
+                });
+            }
+        }
+        Self {
+            keys,
+            identity: identity.to_vec(),
+        }
+    }
+
+    /// `ORDER BY` for reading forward, or in reverse for a backward page.
+    pub fn order_by_clause(&self, backward: bool) -> String {
+        let terms = self
+            .keys(backward)
+            .iter()
+            .map(|key| {
+                format!(
+                    "{}{}{}",
+                    quote_identifier(&key.column),
+                    if key.descending { " DESC" } else { "" },
+                    if key.not_null {
+                        ""
+                    } else if key.nulls_first {
+                        " NULLS FIRST"
+                    } else {
+                        " NULLS LAST"
+                    }
+                )
+            })
+            .collect::<Vec<_>>();
+        format!(" ORDER BY {}", terms.join(", "))
+    }
+
+    fn keys(&self, backward: bool) -> Vec<SortKey> {
+        if backward {
+            self.keys.iter().map(SortKey::reversed).collect()
+        } else {
+            self.keys.clone()
+        }
+    }
+
+    /// Rejects a cursor made for another sort order.
+    pub fn check_cursor(&self, cursor: &Cursor) -> Result<(), AppError> {
+        if cursor.order != self.order_by_clause(false) || cursor.values.len() != self.keys.len() {
+            return Err(AppError::validation(
+                "Page cursor belongs to a different sort order; start from the first page",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Token for the page after (or, going backward, before) `row`, which has
+    /// the given result columns. `None` if a key column isn't in the result.
+    pub fn cursor(&self, columns: &[String], row: &[Value], backward: bool) -> Option<String> {
+        let values = self
+            .keys
+            .iter()
+            .map(|key| {
+                let index = columns.iter().position(|c| *c == key.column)?;
+                row.get(index).cloned()
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let cursor = Cursor {
+            order: self.order_by_clause(false),
+            values,
+            backward,
+            identity: self.identity.clone(),
+        };
+        let json = serde_json::to_vec(&cursor).ok()?;
+        Some(URL_SAFE_NO_PAD.encode(json))
+    }
+
+    /// Condition selecting the rows that follow the cursor in its direction.
+    pub fn condition<'a>(&self, cursor: &'a Cursor, params: &mut Params<'a>) -> String {
+        let keys = self.keys(cursor.backward);
+
+        // A row comparison can use a multi-column index directly, but only
+        // works when every key sorts the same way and can't be NULL
+        if keys.iter().all(|key| key.not_null && key.descending == keys[0].descending) {
+            let columns = keys
+                .iter()
+                .map(|key| quote_identifier(&key.column))
+                .collect::<Vec<_>>();
+            let values = cursor
+                .values
+                .iter()
+                .map(|value| params.push(JsonParam(value)))
+                .collect::<Vec<_>>();
+            let op = if keys[0].descending { "<" } else { ">" };
+            return format!("(({}) {} ({}))", columns.join(", "), op, values.join(", "));
+        }
+
+        // Otherwise: the first key comes after its cursor value, or the
+        // first is equal and the second comes after, and so on
+        let mut alternatives = Vec::new();
+        let mut equal_prefix: Vec<String> = Vec::new();
+        for (key, value) in keys.iter().zip(&cursor.values) {
+            let column = quote_identifier(&key.column);
+            let placeholder = (!value.is_null()).then(|| params.push(JsonParam(value)));
+
+            let after = match &placeholder {
+                Some(placeholder) => {
+                    let op = if key.descending { "<" } else { ">" };
+                    let after = format!("{} {} {}", column, op, placeholder);
+                    if key.not_null || key.nulls_first {
+                        Some(after)
+                    } else {
+                        Some(format!("({} OR {} IS NULL)", after, column))
+                    }
+                }
+                // Only non-NULL values follow a NULL, and only if NULLs
+                // come first
+                None if key.nulls_first => Some(format!("{} IS NOT NULL", column)),
+                None => None,
+            };
+            if let Some(after) = after {
+                alternatives.push(
+                    equal_prefix
+                        .iter()
+                        .cloned()
+                        .chain(std::iter::once(after))
+                        .collect::<Vec<_>>()
+                        .join(" AND "),
+                );
+            }
+
+            equal_prefix.push(match &placeholder {
+                Some(placeholder) => format!("{} = {}", column, placeholder),
+                None => format!("{} IS NULL", column),
+            });
+        }
+        if alternatives.is_empty() {
+            return "false".to_string();
+        }
+        format!(
+            "({})",
+            alternatives
+                .iter()
+                .map(|alternative| format!("({})", alternative))
+                .collect::<Vec<_>>()
+                .join(" OR ")
+        )
+    }
+}
+
+/// Rows of `schema.table` matching `filters`. Counts them exactly unless the
+/// planner expects more than `EXACT_COUNT_LIMIT`, in which case its estimate
+/// is returned instead. Returns the count and whether it is an estimate.
+pub async fn count_rows(
+    client: &Client,
+    schema: &str,
+    table: &str,
+    filters: &[Filter],
+) -> Result<(u64, bool), AppError> {
+    let mut params = Params::default();
+    let from = format!(
+        "FROM {}{}",
+        qualified_name(schema, table),
+        where_clause(filters, &mut params)?
+    );
+    let params = params.as_refs();
+
+    let explain = format!("EXPLAIN (FORMAT JSON) SELECT 1 {}", from);
+    let (plan, _) = decode_row(&client.query_one(&explain, &params).await?);
+    let estimate = plan[0][0]["Plan"]["Plan Rows"].as_f64().unwrap_or(0.0);
+    if estimate > EXACT_COUNT_LIMIT {
+        return Ok((estimate as u64, true));
+    }
+
+    let count = format!("SELECT count(*) {}", from);
+    let count: i64 = client.query_one(&count, &params).await?.get(0);
+    Ok((count as u64, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sort(column: &str, descending: bool, nulls: Option<NullsOrder>) -> SortColumn {
+        SortColumn {
+            column: column.to_string(),
+            descending,
+            nulls,
+        }
+    }
+
+    fn columns(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn appends_identity_columns_to_the_order() {
+        let keyset = Keyset::new(&[sort("name", false, None)], &columns(&["id"]));
+        assert_eq!(
+            keyset.order_by_clause(false),
+            r#" ORDER BY "name" NULLS LAST, "id""#
+        );
+        assert_eq!(
+            keyset.order_by_clause(true),
+            r#" ORDER BY "name" DESC NULLS FIRST, "id" DESC"#
+        );
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let keyset = Keyset::new(&[sort("name", true, None)], &columns(&["id"]));
+        let token = keyset
+            .cursor(&columns(&["id", "name"]), &[json!(7), json!("x")], true)
+            .unwrap();
+
+        let cursor = Cursor::decode(&token).unwrap();
+        keyset.check_cursor(&cursor).unwrap();
+        assert_eq!(cursor.values, [json!("x"), json!(7)]);
+        assert!(cursor.backward());
+        assert_eq!(cursor.identity(), ["id"]);
+    }
+
+    #[test]
+    fn cursor_keeps_null_sort_keys() {
+        let keyset = Keyset::new(&[sort("name", false, None)], &columns(&["id"]));
+        let token = keyset
+            .cursor(&columns(&["id", "name"]), &[json!(3), Value::Null], false)
+            .unwrap();
+        let cursor = Cursor::decode(&token).unwrap();
+        keyset.check_cursor(&cursor).unwrap();
+        assert_eq!(cursor.values, [Value::Null, json!(3)]);
+
+        // NULLs sort last, so only later ids with a NULL name follow
+        let mut params = Params::default();
+        assert_eq!(
+            keyset.condition(&cursor, &mut params),
+            r#"(("name" IS NULL AND "id" > $1))"#
+        );
+        assert_eq!(params.as_refs().len(), 1);
+    }
+
+    #[test]
+    fn null_sort_key_first_is_followed_by_values() {
+        let keyset = Keyset::new(
+            &[sort("name", false, Some(NullsOrder::First))],
+            &columns(&["id"]),
+        );
+        let token = keyset
+            .cursor(&columns(&["id", "name"]), &[json!(3), Value::Null], false)
+            .unwrap();
+        let cursor = Cursor::decode(&token).unwrap();
+        let mut params = Params::default();
+        assert_eq!(
+            keyset.condition(&cursor, &mut params),
+            r#"(("name" IS NOT NULL) OR ("name" IS NULL AND "id" > $1))"#
+        );
+    }
+
+    #[test]
+    fn uses_a_row_comparison_for_not_null_keys() {
+        let keyset = Keyset::new(&[], &columns(&["a", "b"]));
+        let token = keyset
+            .cursor(&columns(&["a", "b"]), &[json!(1), json!(2)], false)
+            .unwrap();
+        let cursor = Cursor::decode(&token).unwrap();
+        let mut params = Params::default();
+        assert_eq!(
+            keyset.condition(&cursor, &mut params),
+            r#"(("a", "b") > ($1, $2))"#
+        );
+    }
+
+    #[test]
+    fn rejects_cursors_from_another_order() {
+        let identity = columns(&["id"]);
+        let by_name = Keyset::new(&[sort("name", false, None)], &identity);
+        let token = by_name
+            .cursor(&columns(&["id", "name"]), &[json!(1), json!("a")], false)
+            .unwrap();
+        let cursor = Cursor::decode(&token).unwrap();
+
+        let by_name_desc = Keyset::new(&[sort("name", true, None)], &identity);
+        assert!(by_name_desc.check_cursor(&cursor).is_err());
+        assert!(Cursor::decode("not a cursor").is_err());
+    }
+
+    #[test]
+    fn no_cursor_without_the_key_columns() {
+        let keyset = Keyset::new(&[sort("name", false, None)], &columns(&["id"]));
+        assert!(keyset
+            .cursor(&columns(&["name"]), &[json!("a")], false)
+            .is_none());
+    }
+}
@@ -0,0 +1,99 @@
+use crate::db::pool::PooledClient;
+use crate::db::tls;
+use crate::error::AppError;
+use crate::models::ConnectionConfig;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio_postgres::CancelToken;
+
+struct RunningQuery {
+    token: CancelToken,
+    config: ConnectionConfig,
+    canceled: Arc<AtomicBool>,
+}
+
+/// Statements currently executing, keyed by execution id, so they can be
+/// canceled from another command while the original one is still awaiting.
+#[derive(Default)]
+pub struct RunningQueries {
+    queries: Mutex<HashMap<String, RunningQuery>>,
+}
+
+impl RunningQueries {
+    /// Registers an execution on `client`. The returned guard unregisters it
+    /// when dropped.
+    pub fn start(&self, execution_id: Option<String>, client: &PooledClient) -> Execution<'_> {
+        let id = execution_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let canceled = Arc::new(AtomicBool::new(false));
+
+        self.queries.lock().unwrap().insert(
+            id.clone(),
+            RunningQuery {
+                token: client.cancel_token(),
+                config: client.connection_config().clone(),
+                canceled: Arc::clone(&canceled),
+            },
+        );
+
+        Execution {
+            id,
+            registry: self,
+            canceled,
+        }
+    }
+
+    /// Asks the server to cancel an execution. Returns false if it is no
+    /// longer running.
+    pub async fn cancel(&self, execution_id: &str) -> Result<bool> {
+        let (token, config) = {
+            let queries = self.queries.lock().unwrap();
+            let Some(query) = queries.get(execution_id) else {
+                return Ok(false);
+            };
+            query.canceled.store(true, Ordering::SeqCst);
+            (query.token.clone(), query.config.clone())
+        };
+
+        // The cancel request opens its own connection, so it needs the same
+        // TLS settings (and goes through the same tunnel port) as the query.
+        token.cancel_query(tls::make_connector(&config)?).await?;
+        Ok(true)
+    }
+}
+
+pub struct Execution<'a> {
+    id: String,
+    registry: &'a RunningQueries,
+    canceled: Arc<AtomicBool>,
+}
+
+impl Execution<'_> {
+    pub fn is_canceled(&self) -> bool {
+        self.canceled.load(Ordering::SeqCst)
+    }
+
+    /// Replaces the error of a canceled execution with a clear message; the
+    /// server only reports a generic "canceling statement" error.
+    pub fn finish<T>(self, result: Result<T, AppError>) -> Result<T, AppError> {
+        match result {
+            Err(_) if self.is_canceled() => Err(AppError::canceled()),
+            other => other,
+        }
+    }
+}
+
+impl Drop for Execution<'_> {
+    fn drop(&mut self) {
+        self.registry.queries.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Cancels whatever `client` is running right now, e.g. to stop a statement
+/// whose remaining rows are no longer wanted.
+pub async fn cancel_running(client: &PooledClient) -> Result<()> {
+    let connector = tls::make_connector(client.connection_config())?;
+    client.cancel_token().cancel_query(connector).await?;
+    Ok(())
+}
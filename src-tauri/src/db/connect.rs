@@ -0,0 +1,47 @@
+use crate::db::tls;
+use crate::db::tunnel::SshTunnel;
+use crate::models::ConnectionConfig;
+use anyhow::Result;
+use std::net::{IpAddr, Ipv4Addr};
+use tokio_postgres::{Client, Config};
+
+pub fn pg_config(config: &ConnectionConfig, password: &str) -> Config {
+    let mut pg_config = Config::new();
+    pg_config
+        .host(&config.host)
+        .port(config.port)
+        .dbname(&config.database)
+        .user(&config.username)
+        .password(password)
+        .ssl_mode(tls::pg_ssl_mode(config.ssl_mode))
+        .application_name("pgsql-studio");
+    pg_config
+}
+
+/// Opens a new client for `config` and spawns its connection task. With a
+/// tunnel, the socket goes to the tunnel's loopback port while `host` is still
+/// used for TLS server name verification.
+pub async fn connect(
+    config: &ConnectionConfig,
+    password: &str,
+    tunnel: Option<&SshTunnel>,
+) -> Result<Client> {
+    let connector = tls::make_connector(config)?;
+
+    let mut pg_config = pg_config(config, password);
+    if let Some(tunnel) = tunnel {
+        pg_config
+            .hostaddr(IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .port(tunnel.local_port());
+    }
+
+    let (client, connection) = pg_config.connect(connector).await?;
+
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("connection error: {}", e);
+        }
+    });
+
+    Ok(client)
+}
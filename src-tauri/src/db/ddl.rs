@@ -0,0 +1,1468 @@
+use crate::db::sql::{qualified_name, quote_identifier, quote_literal};
+use crate::error::AppError;
+use serde::Deserialize;
+use tokio_postgres::{Client, Row};
+
+/// Objects `generate_ddl` can script.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DdlObjectKind {
+    /// Ordinary, partitioned and foreign tables.
+    Table,
+    View,
+    MaterializedView,
+    /// Functions and procedures.
+    Function,
+    Sequence,
+    /// Enum, composite and domain types.
+    Type,
+}
+
+/// Builds a script that recreates an object: its `CREATE` statement
+/// followed by indexes, foreign keys, triggers, comments, owner and grants,
+/// in the order pg_dump would emit them. `arguments` picks a function among
+/// its overloads by identity arguments, e.g. `integer, text`.
+pub async fn generate_ddl(
+    client: &Client,
+    kind: DdlObjectKind,
+    schema: &str,
+    name: &str,
+    arguments: Option<&str>,
+) -> Result<String, AppError> {
+    let mut script = Vec::new();
+    match kind {
+        DdlObjectKind::Table => table_ddl(client, schema, name, &mut script).await?,
+        DdlObjectKind::View | DdlObjectKind::MaterializedView => {
+            view_ddl(client, kind, schema, name, &mut script).await?
+        }
+        DdlObjectKind::Function => {
+            function_ddl(client, schema, name, arguments, &mut script).await?
+        }
+        DdlObjectKind::Sequence => sequence_ddl(client, schema, name, &mut script).await?,
+        DdlObjectKind::Type => type_ddl(client, schema, name, &mut script).await?,
+    }
+    Ok(script.join("\n\n") + "\n")
+}
+
+/// The `pg_class` row of a table, view or sequence.
+struct Relation {
+    oid: u32,
+    relkind: String,
+    owner: String,
+    comment: Option<String>,
+    options: Vec<String>,
+    unlogged: bool,
+    is_partition: bool,
+    is_populated: bool,
+}
+
+async fn relation(
+    client: &Client,
+    schema: &str,
+    name: &str,
+    relkinds: &[&str],
+    description: &str,
+) -> Result<Relation, AppError> {
+    let query = r#"
+        SELECT
+            c.oid,
+            c.relkind::text AS relkind,
+            pg_get_userbyid(c.relowner) AS owner,
+            obj_description(c.oid, 'pg_class') AS comment,
+            coalesce(c.reloptions, '{}') AS options,
+            c.relpersistence = 'u' AS unlogged,
+            c.relispartition AS is_partition,
+            c.relispopulated AS is_populated
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = $1 AND c.relname = $2 AND c.relkind::text = ANY($3)
+    "#;
+
+    let row = client
+        .query_opt(query, &[&schema, &name, &relkinds])
+        .await?
+        .ok_or_else(|| {
+            AppError::not_found(format!("{} {}.{} not found", description, schema, name))
+        })?;
+    Ok(Relation {
+        oid: row.get("oid"),
+        relkind: row.get("relkind"),
+        owner: row.get("owner"),
+        comment: row.get("comment"),
+        options: row.get("options"),
+        unlogged: row.get("unlogged"),
+        is_partition: row.get("is_partition"),
+        is_populated: row.get("is_populated"),
+    })
+}
+
+async fn table_ddl(
+    client: &Client,
+    schema: &str,
+    name: &str,
+    script: &mut Vec<String>,
+) -> Result<(), AppError> {
+    let table = relation(client, schema, name, &["r", "p", "f"], "Table").await?;
+    let target = qualified_name(schema, name);
+    let keyword = if table.relkind == "f" {
+        "FOREIGN TABLE"
+    } else {
+        "TABLE"
+    };
+
+    // Sequences behind serial columns have to exist before their defaults
+    let serials = client
+        .query(
+            r#"
+            SELECT s.oid, n.nspname AS schema, s.relname AS name, a.attname AS column_name
+            FROM pg_depend d
+            JOIN pg_class s ON s.oid = d.objid AND s.relkind = 'S'
+            JOIN pg_namespace n ON n.oid = s.relnamespace
+            JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+            WHERE d.classid = 'pg_class'::regclass AND d.refclassid = 'pg_class'::regclass
+              AND d.refobjid = $1 AND d.deptype = 'a'
+            ORDER BY a.attnum
+            "#,
+            &[&table.oid],
+        )
+        .await?;
+    for serial in &serials {
+        let sequence = qualified_name(serial.get("schema"), serial.get("name"));
+        script.push(create_sequence(client, serial.get("oid"), &sequence).await?);
+    }
+
+    let mut create = CreateTable {
+        keyword,
+        target: target.clone(),
+        unlogged: table.unlogged,
+        partition_of: None,
+        body: Vec::new(),
+        parents: Vec::new(),
+        partition_key: None,
+        server: None,
+        options: table.options.clone(),
+    };
+    if table.is_partition {
+        let row = client
+            .query_one(
+                r#"
+                SELECT i.inhparent::regclass::text AS parent, pg_get_expr(c.relpartbound, c.oid) AS bound
+                FROM pg_class c
+                JOIN pg_inherits i ON i.inhrelid = c.oid
+                WHERE c.oid = $1
+                "#,
+                &[&table.oid],
+            )
+            .await?;
+        create.partition_of = Some((row.get("parent"), row.get("bound")));
+    } else {
+        create.body = column_definitions(client, table.oid).await?;
+        let constraints = client
+            .query(
+                r#"
+                SELECT conname, pg_get_constraintdef(oid) AS definition
+                FROM pg_constraint
+                WHERE conrelid = $1 AND conislocal AND convalidated
+                  AND contype IN ('p', 'u', 'c', 'x')
+                ORDER BY array_position(ARRAY['p', 'u', 'c', 'x'], contype::text), conname
+                "#,
+                &[&table.oid],
+            )
+            .await?;
+        for constraint in &constraints {
+            create.body.push(format!(
+                "CONSTRAINT {} {}",
+                quote_identifier(constraint.get("conname")),
+                constraint.get::<_, &str>("definition")
+            ));
+        }
+
+        create.parents = client
+            .query(
+                r#"
+                SELECT inhparent::regclass::text AS parent
+                FROM pg_inherits
+                WHERE inhrelid = $1
+                ORDER BY inhseqno
+                "#,
+                &[&table.oid],
+            )
+            .await?
+            .iter()
+            .map(|row| row.get("parent"))
+            .collect();
+    }
+    if table.relkind == "p" {
+        let key: String = client
+            .query_one("SELECT pg_get_partkeydef($1)", &[&table.oid])
+            .await?
+            .get(0);
+        create.partition_key = Some(key);
+    }
+    if table.relkind == "f" {
+        let row = client
+            .query_one(
+                r#"
+                SELECT s.srvname AS server, coalesce(f.ftoptions, '{}') AS options
+                FROM pg_foreign_table f
+                JOIN pg_foreign_server s ON s.oid = f.ftserver
+                WHERE f.ftrelid = $1
+                "#,
+                &[&table.oid],
+            )
+            .await?;
+        create.server = Some((row.get("server"), row.get("options")));
+    }
+    script.push(create.statement());
+
+    for serial in &serials {
+        script.push(format!(
+            "ALTER SEQUENCE {} OWNED BY {}.{};",
+            qualified_name(serial.get("schema"), serial.get("name")),
+            target,
+            quote_identifier(serial.get("column_name"))
+        ));
+    }
+
+    // Foreign keys and constraints added NOT VALID can't go in CREATE TABLE,
+    // and neither can any constraint of a partition
+    let constraints = client
+        .query(
+            r#"
+            SELECT conname, pg_get_constraintdef(oid) AS definition
+            FROM pg_constraint
+            WHERE conrelid = $1 AND conislocal
+              AND contype IN ('p', 'u', 'c', 'x', 'f')
+              AND (contype = 'f' OR NOT convalidated OR $2)
+            ORDER BY contype = 'f', conname
+            "#,
+            &[&table.oid, &table.is_partition],
+        )
+        .await?;
+    for constraint in &constraints {
+        script.push(format!(
+            "ALTER {} {} ADD CONSTRAINT {} {};",
+            keyword,
+            target,
+            quote_identifier(constraint.get("conname")),
+            constraint.get::<_, &str>("definition")
+        ));
+    }
+
+    indexes(client, table.oid, script).await?;
+
+    let cloned_filter = cloned_trigger_filter(server_version(client).await?);
+    let triggers = client
+        .query(
+            &format!(
+                r#"
+                SELECT tgname, pg_get_triggerdef(oid) AS definition, tgenabled = 'D' AS disabled
+                FROM pg_trigger
+                WHERE tgrelid = $1 AND NOT tgisinternal {cloned_filter}
+                ORDER BY tgname
+                "#
+            ),
+            &[&table.oid],
+        )
+        .await?;
+    for trigger in &triggers {
+        script.push(format!("{};", trigger.get::<_, &str>("definition")));
+        if trigger.get("disabled") {
+            script.push(format!(
+                "ALTER {} {} DISABLE TRIGGER {};",
+                keyword,
+                target,
+                quote_identifier(trigger.get("tgname"))
+            ));
+        }
+    }
+
+    relation_footer(client, &table, keyword, &target, script).await
+}
+
+/// The parts of a `CREATE TABLE` statement.
+struct CreateTable {
+    keyword: &'static str,
+    target: String,
+    unlogged: bool,
+    /// Parent and bound of a partition, which gets its columns from the
+    /// parent.
+    partition_of: Option<(String, String)>,
+    /// Column and constraint lines.
+    body: Vec<String>,
+    /// Tables inherited from.
+    parents: Vec<String>,
+    partition_key: Option<String>,
+    /// Server and options of a foreign table.
+    server: Option<(String, Vec<String>)>,
+    /// Storage parameters.
+    options: Vec<String>,
+}
+
+impl CreateTable {
+    fn statement(&self) -> String {
+        let mut statement = format!(
+            "CREATE {}{} {}",
+            if self.unlogged { "UNLOGGED " } else { "" },
+            self.keyword,
+            self.target
+        );
+        if let Some((parent, bound)) = &self.partition_of {
+            statement += &format!(" PARTITION OF {}\n{}", parent, bound);
+        } else {
+            statement += &if self.body.is_empty() {
+                " ()".to_string()
+            } else {
+                format!(" (\n    {}\n)", self.body.join(",\n    "))
+            };
+            if !self.parents.is_empty() {
+                statement += &format!("\nINHERITS ({})", self.parents.join(", "));
+            }
+        }
+        if let Some(key) = &self.partition_key {
+            statement += &format!("\nPARTITION BY {}", key);
+        }
+        if let Some((server, options)) = &self.server {
+            statement += &format!("\nSERVER {}", quote_identifier(server));
+            if !options.is_empty() {
+                statement += &format!("\nOPTIONS ({})", fdw_options(options));
+            }
+        }
+        if !self.options.is_empty() {
+            statement += &format!("\nWITH ({})", self.options.join(", "));
+        }
+        statement.push(';');
+        statement
+    }
+}
+
+/// Condition leaving out triggers cloned from a partitioned parent, which
+/// come back with it. Before PostgreSQL 13, which added tgparentid, clones
+/// were marked internal and are left out anyway.
+fn cloned_trigger_filter(server_version: i32) -> &'static str {
+    if server_version >= 130000 {
+        "AND tgparentid = 0"
+    } else {
+        ""
+    }
+}
+
+/// Column lines of a `CREATE TABLE`, leaving out inherited columns.
+async fn column_definitions(client: &Client, oid: u32) -> Result<Vec<String>, AppError> {
+    let query = r#"
+        SELECT
+            a.attname,
+            format_type(a.atttypid, a.atttypmod) AS data_type,
+            CASE WHEN a.attcollation <> t.typcollation
+                THEN format('%I.%I', cn.nspname, co.collname)
+            END AS collation,
+            a.attidentity::text AS identity,
+            a.attgenerated::text AS generated,
+            pg_get_expr(ad.adbin, ad.adrelid) AS default_expr,
+            a.attnotnull
+        FROM pg_attribute a
+        JOIN pg_type t ON t.oid = a.atttypid
+        LEFT JOIN pg_collation co ON co.oid = a.attcollation
+        LEFT JOIN pg_namespace cn ON cn.oid = co.collnamespace
+        LEFT JOIN pg_attrdef ad ON ad.adrelid = a.attrelid AND ad.adnum = a.attnum
+        WHERE a.attrelid = $1 AND a.attnum > 0 AND NOT a.attisdropped AND a.attislocal
+        ORDER BY a.attnum
+    "#;
+
+    let rows = client.query(query, &[&oid]).await?;
+    rows.iter()
+        .map(|row| {
+            Column {
+                name: row.get("attname"),
+                data_type: row.get("data_type"),
+                collation: row.get("collation"),
+                identity: row.get("identity"),
+                generated: row.get("generated"),
+                default_expr: row.get("default_expr"),
+                not_null: row.get("attnotnull"),
+            }
+            .definition()
+        })
+        .collect()
+}
+
+/// A column of a table, as far as its `CREATE TABLE` line goes.
+struct Column<'a> {
+    name: &'a str,
+    data_type: &'a str,
+    collation: Option<&'a str>,
+    /// `pg_attribute.attidentity`
+    identity: &'a str,
+    /// `pg_attribute.attgenerated`
+    generated: &'a str,
+    /// The default, or the expression of a generated column.
+    default_expr: Option<&'a str>,
+    not_null: bool,
+}
+
+impl Column<'_> {
+    fn definition(&self) -> Result<String, AppError> {
+        let mut column = format!("{} {}", quote_identifier(self.name), self.data_type);
+        if let Some(collation) = self.collation {
+            column += &format!(" COLLATE {}", collation);
+        }
+        match (self.identity, self.generated) {
+            ("a", _) => column += " GENERATED ALWAYS AS IDENTITY",
+            ("d", _) => column += " GENERATED BY DEFAULT AS IDENTITY",
+            (_, "s") => {
+                column += &format!(
+                    " GENERATED ALWAYS AS ({}) STORED",
+                    self.default_expr.unwrap_or_default()
+                )
+            }
+            (_, "v") => {
+                column += &format!(
+                    " GENERATED ALWAYS AS ({}) VIRTUAL",
+                    self.default_expr.unwrap_or_default()
+                )
+            }
+            (_, "") => {
+                if let Some(default_expr) = self.default_expr {
+                    column += &format!(" DEFAULT {}", default_expr);
+                }
+            }
+            (_, generated) => {
+                return Err(AppError::validation(format!(
+                    "Column {} is generated in a way this version can't script ({:?})",
+                    self.name, generated
+                )))
+            }
+        }
+        if self.not_null {
+            column += " NOT NULL";
+        }
+        Ok(column)
+    }
+}
+
+/// `CREATE INDEX` statements for indexes that don't back a constraint and
+/// weren't created through a partitioned parent.
+async fn indexes(client: &Client, oid: u32, script: &mut Vec<String>) -> Result<(), AppError> {
+    let query = r#"
+        SELECT pg_get_indexdef(i.indexrelid) AS definition
+        FROM pg_index i
+        JOIN pg_class c ON c.oid = i.indexrelid
+        WHERE i.indrelid = $1
+          AND NOT EXISTS (SELECT 1 FROM pg_constraint con WHERE con.conindid = i.indexrelid)
+          AND NOT EXISTS (SELECT 1 FROM pg_inherits inh WHERE inh.inhrelid = i.indexrelid)
+        ORDER BY c.relname
+    "#;
+
+    for row in client.query(query, &[&oid]).await? {
+        // Scripted on its own, a partitioned table's index should cascade to
+        // the partitions created later
+        let definition = row.get::<_, &str>("definition").replacen(" ON ONLY ", " ON ", 1);
+        script.push(format!("{};", definition));
+    }
+    Ok(())
+}
+
+/// Comments, owner and grants of a table, view or sequence.
+async fn relation_footer(
+    client: &Client,
+    relation: &Relation,
+    keyword: &str,
+    target: &str,
+    script: &mut Vec<String>,
+) -> Result<(), AppError> {
+    if let Some(comment) = &relation.comment {
+        script.push(format!(
+            "COMMENT ON {} {} IS {};",
+            keyword,
+            target,
+            quote_literal(comment)
+        ));
+    }
+    let columns = client
+        .query(
+            r#"
+            SELECT a.attname, col_description(a.attrelid, a.attnum) AS comment
+            FROM pg_attribute a
+            WHERE a.attrelid = $1 AND a.attnum > 0 AND NOT a.attisdropped
+              AND col_description(a.attrelid, a.attnum) IS NOT NULL
+            ORDER BY a.attnum
+            "#,
+            &[&relation.oid],
+        )
+        .await?;
+    for column in &columns {
+        script.push(format!(
+            "COMMENT ON COLUMN {}.{} IS {};",
+            target,
+            quote_identifier(column.get("attname")),
+            quote_literal(column.get("comment"))
+        ));
+    }
+
+    script.push(format!(
+        "ALTER {} {} OWNER TO {};",
+        keyword,
+        target,
+        quote_identifier(&relation.owner)
+    ));
+    let object = if relation.relkind == "S" {
+        format!("SEQUENCE {}", target)
+    } else {
+        format!("TABLE {}", target)
+    };
+    grants(client, Acl::Relation, relation.oid, &object, script).await?;
+    if relation.relkind != "S" {
+        column_grants(client, relation.oid, target, script).await?;
+    }
+    Ok(())
+}
+
+async fn view_ddl(
+    client: &Client,
+    kind: DdlObjectKind,
+    schema: &str,
+    name: &str,
+    script: &mut Vec<String>,
+) -> Result<(), AppError> {
+    let materialized = kind == DdlObjectKind::MaterializedView;
+    let (relkind, keyword) = if materialized {
+        ("m", "MATERIALIZED VIEW")
+    } else {
+        ("v", "VIEW")
+    };
+    let view = relation(client, schema, name, &[relkind], "View").await?;
+    let target = qualified_name(schema, name);
+
+    let definition: String = client
+        .query_one("SELECT pg_get_viewdef($1::oid, true)", &[&view.oid])
+        .await?
+        .get(0);
+    let populated = materialized.then_some(view.is_populated);
+    script.push(create_view(
+        keyword,
+        &target,
+        &view.options,
+        &definition,
+        populated,
+    ));
+
+    if materialized {
+        indexes(client, view.oid, script).await?;
+    }
+    relation_footer(client, &view, keyword, &target, script).await
+}
+
+/// `CREATE [MATERIALIZED] VIEW` from a view definition as
+/// `pg_get_viewdef` prints it. `populated` is only set for materialized
+/// views.
+fn create_view(
+    keyword: &str,
+    target: &str,
+    options: &[String],
+    definition: &str,
+    populated: Option<bool>,
+) -> String {
+    let mut statement = format!("CREATE {} {}", keyword, target);
+    if !options.is_empty() {
+        statement += &format!(" WITH ({})", options.join(", "));
+    }
+    statement += &format!(" AS\n{}", definition.trim().trim_end_matches(';'));
+    match populated {
+        Some(true) => statement += "\nWITH DATA",
+        Some(false) => statement += "\nWITH NO DATA",
+        None => {}
+    }
+    statement.push(';');
+    statement
+}
+
+async fn function_ddl(
+    client: &Client,
+    schema: &str,
+    name: &str,
+    arguments: Option<&str>,
+    script: &mut Vec<String>,
+) -> Result<(), AppError> {
+    let query = r#"
+        SELECT
+            p.oid,
+            p.prokind::text AS kind,
+            pg_get_function_identity_arguments(p.oid) AS arguments,
+            pg_get_userbyid(p.proowner) AS owner,
+            obj_description(p.oid, 'pg_proc') AS comment
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        WHERE n.nspname = $1 AND p.proname = $2
+          AND ($3::text IS NULL OR pg_get_function_identity_arguments(p.oid) = $3)
+    "#;
+
+    let rows = client.query(query, &[&schema, &name, &arguments]).await?;
+    let function = match rows.as_slice() {
+        [] => {
+            return Err(AppError::not_found(format!(
+                "Function {}.{}({}) not found",
+                schema,
+                name,
+                arguments.unwrap_or("")
+            )))
+        }
+        [function] => function,
+        _ => {
+            return Err(AppError::validation(format!(
+                "Function {}.{} has {} overloads; pass its arguments to pick one",
+                schema,
+                name,
+                rows.len()
+            )))
+        }
+    };
+    let keyword = function_keyword(function.get("kind"))?;
+    let oid: u32 = function.get("oid");
+    let signature = format!(
+        "{}({})",
+        qualified_name(schema, name),
+        function.get::<_, &str>("arguments")
+    );
+
+    let definition: String = client
+        .query_one("SELECT pg_get_functiondef($1)", &[&oid])
+        .await?
+        .get(0);
+    script.push(format!("{};", definition.trim_end()));
+
+    if let Some(comment) = function.get::<_, Option<&str>>("comment") {
+        script.push(format!(
+            "COMMENT ON {} {} IS {};",
+            keyword,
+            signature,
+            quote_literal(comment)
+        ));
+    }
+    script.push(format!(
+        "ALTER {} {} OWNER TO {};",
+        keyword,
+        signature,
+        quote_identifier(function.get("owner"))
+    ));
+    let object = format!("{} {}", keyword, signature);
+    grants(client, Acl::Function, oid, &object, script).await
+}
+
+/// How statements refer to a routine of `pg_proc.prokind` `kind`.
+fn function_keyword(kind: &str) -> Result<&'static str, AppError> {
+    match kind {
+        "p" => Ok("PROCEDURE"),
+        "a" => Err(AppError::validation("Aggregates can't be scripted")),
+        _ => Ok("FUNCTION"),
+    }
+}
+
+async fn sequence_ddl(
+    client: &Client,
+    schema: &str,
+    name: &str,
+    script: &mut Vec<String>,
+) -> Result<(), AppError> {
+    let sequence = relation(client, schema, name, &["S"], "Sequence").await?;
+    let target = qualified_name(schema, name);
+
+    let owner = client
+        .query_opt(
+            r#"
+            SELECT d.deptype::text AS deptype, d.refobjid::regclass::text AS table_name, a.attname
+            FROM pg_depend d
+            JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+            WHERE d.classid = 'pg_class'::regclass AND d.refclassid = 'pg_class'::regclass
+              AND d.objid = $1 AND d.deptype IN ('a', 'i')
+            "#,
+            &[&sequence.oid],
+        )
+        .await?;
+    if let Some(owner) = &owner {
+        if owner.get::<_, &str>("deptype") == "i" {
+            return Err(AppError::validation(format!(
+                "Sequence {}.{} belongs to identity column {}.{}; script the table instead",
+                schema,
+                name,
+                owner.get::<_, &str>("table_name"),
+                owner.get::<_, &str>("attname")
+            )));
+        }
+    }
+
+    script.push(create_sequence(client, sequence.oid, &target).await?);
+    if let Some(owner) = &owner {
+        script.push(format!(
+            "ALTER SEQUENCE {} OWNED BY {}.{};",
+            target,
+            owner.get::<_, &str>("table_name"),
+            quote_identifier(owner.get("attname"))
+        ));
+    }
+    relation_footer(client, &sequence, "SEQUENCE", &target, script).await
+}
+
+async fn create_sequence(client: &Client, oid: u32, target: &str) -> Result<String, AppError> {
+    let row = client
+        .query_one(
+            r#"
+            SELECT
+                format_type(seqtypid, NULL) AS data_type,
+                seqstart, seqincrement, seqmin, seqmax, seqcache, seqcycle
+            FROM pg_sequence
+            WHERE seqrelid = $1
+            "#,
+            &[&oid],
+        )
+        .await?;
+    let sequence = Sequence {
+        data_type: row.get("data_type"),
+        start: row.get("seqstart"),
+        increment: row.get("seqincrement"),
+        min: row.get("seqmin"),
+        max: row.get("seqmax"),
+        cache: row.get("seqcache"),
+        cycle: row.get("seqcycle"),
+    };
+    Ok(sequence.statement(target))
+}
+
+/// The `pg_sequence` row of a sequence.
+struct Sequence<'a> {
+    data_type: &'a str,
+    start: i64,
+    increment: i64,
+    min: i64,
+    max: i64,
+    cache: i64,
+    cycle: bool,
+}
+
+impl Sequence<'_> {
+    fn statement(&self, target: &str) -> String {
+        format!(
+            "CREATE SEQUENCE {}\n    AS {}\n    START WITH {}\n    INCREMENT BY {}\n    MINVALUE {}\n    MAXVALUE {}\n    CACHE {}{};",
+            target,
+            self.data_type,
+            self.start,
+            self.increment,
+            self.min,
+            self.max,
+            self.cache,
+            if self.cycle { "\n    CYCLE" } else { "" }
+        )
+    }
+}
+
+async fn type_ddl(
+    client: &Client,
+    schema: &str,
+    name: &str,
+    script: &mut Vec<String>,
+) -> Result<(), AppError> {
+    let query = r#"
+        SELECT
+            t.oid,
+            t.typtype::text AS kind,
+            c.relkind::text AS relkind,
+            pg_get_userbyid(t.typowner) AS owner,
+            obj_description(t.oid, 'pg_type') AS comment,
+            CASE WHEN t.typtype = 'd' THEN format_type(t.typbasetype, t.typtypmod) END AS base_type,
+            CASE WHEN t.typtype = 'd' AND t.typcollation <> b.typcollation
+                THEN format('%I.%I', cn.nspname, co.collname)
+            END AS collation,
+            t.typnotnull AS not_null,
+            t.typdefault AS default_value
+        FROM pg_type t
+        JOIN pg_namespace n ON n.oid = t.typnamespace
+        LEFT JOIN pg_class c ON c.oid = t.typrelid
+        LEFT JOIN pg_type b ON b.oid = t.typbasetype
+        LEFT JOIN pg_collation co ON co.oid = t.typcollation
+        LEFT JOIN pg_namespace cn ON cn.oid = co.collnamespace
+        WHERE n.nspname = $1 AND t.typname = $2
+    "#;
+
+    let row = client
+        .query_opt(query, &[&schema, &name])
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Type {}.{} not found", schema, name)))?;
+    let oid: u32 = row.get("oid");
+    let target = qualified_name(schema, name);
+
+    let keyword = match (row.get::<_, &str>("kind"), row.get::<_, Option<&str>>("relkind")) {
+        ("e", _) => {
+            let labels: Vec<String> = client
+                .query(
+                    "SELECT enumlabel::text FROM pg_enum WHERE enumtypid = $1 ORDER BY enumsortorder",
+                    &[&oid],
+                )
+                .await?
+                .iter()
+                .map(|label| label.get(0))
+                .collect();
+            script.push(create_enum(&target, &labels));
+            "TYPE"
+        }
+        ("c", Some("c")) => {
+            let attributes: Vec<String> = client
+                .query(
+                    r#"
+                    SELECT format('%I %s', attname, format_type(atttypid, atttypmod))
+                    FROM pg_attribute
+                    WHERE attrelid = (SELECT typrelid FROM pg_type WHERE oid = $1)
+                      AND attnum > 0 AND NOT attisdropped
+                    ORDER BY attnum
+                    "#,
+                    &[&oid],
+                )
+                .await?
+                .iter()
+                .map(|attribute| attribute.get(0))
+                .collect();
+            script.push(create_composite(&target, &attributes));
+            "TYPE"
+        }
+        ("d", _) => {
+            let constraints = client
+                .query(
+                    r#"
+                    SELECT conname, pg_get_constraintdef(oid) AS definition, convalidated
+                    FROM pg_constraint
+                    WHERE contypid = $1 AND contype = 'c'
+                    ORDER BY conname
+                    "#,
+                    &[&oid],
+                )
+                .await?;
+            let domain = Domain {
+                base_type: row.get("base_type"),
+                collation: row.get("collation"),
+                default_value: row.get("default_value"),
+                not_null: row.get("not_null"),
+                constraints: constraints
+                    .iter()
+                    .map(|constraint| DomainConstraint {
+                        name: constraint.get("conname"),
+                        definition: constraint.get("definition"),
+                        validated: constraint.get("convalidated"),
+                    })
+                    .collect(),
+            };
+            script.extend(domain.statements(&target));
+            "DOMAIN"
+        }
+        _ => {
+            return Err(AppError::validation(
+                "Only enum, composite and domain types can be scripted",
+            ))
+        }
+    };
+
+    if let Some(comment) = row.get::<_, Option<&str>>("comment") {
+        script.push(format!(
+            "COMMENT ON {} {} IS {};",
+            keyword,
+            target,
+            quote_literal(comment)
+        ));
+    }
+    script.push(format!(
+        "ALTER {} {} OWNER TO {};",
+        keyword,
+        target,
+        quote_identifier(row.get("owner"))
+    ));
+    let object = format!("{} {}", keyword, target);
+    grants(client, Acl::Type, oid, &object, script).await
+}
+
+fn create_enum(target: &str, labels: &[String]) -> String {
+    let labels: Vec<String> = labels.iter().map(|label| quote_literal(label)).collect();
+    format!(
+        "CREATE TYPE {} AS ENUM (\n    {}\n);",
+        target,
+        labels.join(",\n    ")
+    )
+}
+
+/// `CREATE TYPE ... AS` from attribute lines like `"x" integer`.
+fn create_composite(target: &str, attributes: &[String]) -> String {
+    format!(
+        "CREATE TYPE {} AS (\n    {}\n);",
+        target,
+        attributes.join(",\n    ")
+    )
+}
+
+/// A domain and its check constraints.
+struct Domain<'a> {
+    base_type: &'a str,
+    collation: Option<&'a str>,
+    default_value: Option<&'a str>,
+    not_null: bool,
+    constraints: Vec<DomainConstraint<'a>>,
+}
+
+struct DomainConstraint<'a> {
+    name: &'a str,
+    definition: &'a str,
+    validated: bool,
+}
+
+impl Domain<'_> {
+    /// `CREATE DOMAIN`, followed by the constraints added `NOT VALID`, which
+    /// can only be added afterwards.
+    fn statements(&self, target: &str) -> Vec<String> {
+        let mut statement = format!("CREATE DOMAIN {} AS {}", target, self.base_type);
+        if let Some(collation) = self.collation {
+            statement += &format!(" COLLATE {}", collation);
+        }
+        if let Some(default_value) = self.default_value {
+            statement += &format!(" DEFAULT {}", default_value);
+        }
+        if self.not_null {
+            statement += " NOT NULL";
+        }
+        let mut not_valid = Vec::new();
+        for constraint in &self.constraints {
+            let definition = format!(
+                "CONSTRAINT {} {}",
+                quote_identifier(constraint.name),
+                constraint.definition
+            );
+            if constraint.validated {
+                statement += &format!("\n    {}", definition);
+            } else {
+                not_valid.push(format!("ALTER DOMAIN {} ADD {};", target, definition));
+            }
+        }
+        statement.push(';');
+
+        let mut statements = vec![statement];
+        statements.extend(not_valid);
+        statements
+    }
+}
+
+/// Catalogs with an access control list.
+#[derive(Clone, Copy)]
+enum Acl {
+    Relation,
+    Function,
+    Type,
+}
+
+impl Acl {
+    /// Catalog, ACL column, owner column.
+    fn columns(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            Acl::Relation => ("pg_class", "relacl", "relowner"),
+            Acl::Function => ("pg_proc", "proacl", "proowner"),
+            Acl::Type => ("pg_type", "typacl", "typowner"),
+        }
+    }
+
+    /// The ACL an object of catalog row `o` has while its ACL is unset.
+    fn default_acl(self) -> &'static str {
+        match self {
+            Acl::Relation => {
+                r#"acldefault(CASE o.relkind WHEN 'S' THEN 's'::"char" ELSE 'r' END, o.relowner)"#
+            }
+            Acl::Function => "acldefault('f', o.proowner)",
+            Acl::Type => "acldefault('T', o.typowner)",
+        }
+    }
+
+    /// Whether PUBLIC holds privileges while the ACL is unset: `EXECUTE`
+    /// on functions and `USAGE` on types.
+    fn public_by_default(self) -> bool {
+        !matches!(self, Acl::Relation)
+    }
+}
+
+/// `GRANT` statements recreating an object's ACL. An unset ACL means the
+/// defaults and needs no statements. The owner's privileges are implied by
+/// ownership and only scripted when the owner gave some of them up.
+async fn grants(
+    client: &Client,
+    acl: Acl,
+    oid: u32,
+    object: &str,
+    script: &mut Vec<String>,
+) -> Result<(), AppError> {
+    let (catalog, acl_column, owner_column) = acl.columns();
+    let default_acl = acl.default_acl();
+    let owner = client
+        .query_one(
+            &format!(
+                r#"
+                SELECT
+                    o.{acl_column} IS NOT NULL AS acl_is_set,
+                    pg_get_userbyid(o.{owner_column}) AS owner,
+                    ARRAY(
+                        SELECT a.privilege_type FROM aclexplode(o.{acl_column}) a
+                        WHERE a.grantee = o.{owner_column} ORDER BY 1
+                    ) AS privileges,
+                    ARRAY(
+                        SELECT d.privilege_type FROM aclexplode({default_acl}) d
+                        WHERE d.grantee = o.{owner_column} ORDER BY 1
+                    ) AS default_privileges
+                FROM {catalog} o
+                WHERE o.oid = $1
+                "#
+            ),
+            &[&oid],
+        )
+        .await?;
+    if !owner.get::<_, bool>("acl_is_set") {
+        return Ok(());
+    }
+
+    let query = format!(
+        r#"
+        SELECT
+            CASE WHEN a.grantee <> 0 THEN pg_get_userbyid(a.grantee) END AS grantee,
+            string_agg(a.privilege_type, ', ' ORDER BY a.privilege_type) AS privileges,
+            a.is_grantable
+        FROM {catalog} o
+        CROSS JOIN LATERAL aclexplode(o.{acl_column}) a
+        WHERE o.oid = $1 AND a.grantee <> o.{owner_column}
+        GROUP BY a.grantee, a.is_grantable
+        ORDER BY a.grantee <> 0, 1, a.is_grantable
+        "#
+    );
+    let rows = client.query(&query, &[&oid]).await?;
+
+    let object_acl = ObjectAcl {
+        owner: owner.get("owner"),
+        owner_privileges: owner.get("privileges"),
+        default_privileges: owner.get("default_privileges"),
+        grants: rows.iter().map(grant).collect(),
+    };
+    script.extend(object_acl.statements(acl, object));
+    Ok(())
+}
+
+/// A set ACL, split into the owner's privileges and everyone else's.
+struct ObjectAcl {
+    owner: String,
+    owner_privileges: Vec<String>,
+    /// What the owner holds while the ACL is unset.
+    default_privileges: Vec<String>,
+    grants: Vec<Grant>,
+}
+
+impl ObjectAcl {
+    fn statements(&self, acl: Acl, object: &str) -> Vec<String> {
+        let mut statements = Vec::new();
+        if acl.public_by_default() && !self.grants.iter().any(|grant| grant.grantee.is_none()) {
+            statements.push(format!("REVOKE ALL ON {} FROM PUBLIC;", object));
+        }
+
+        if self.owner_privileges != self.default_privileges {
+            let role = quote_identifier(&self.owner);
+            statements.push(format!("REVOKE ALL ON {} FROM {};", object, role));
+            if !self.owner_privileges.is_empty() {
+                statements.push(format!(
+                    "GRANT {} ON {} TO {};",
+                    self.owner_privileges.join(", "),
+                    object,
+                    role
+                ));
+            }
+        }
+
+        statements.extend(self.grants.iter().map(|grant| grant.statement(object)));
+        statements
+    }
+}
+
+/// Privileges one role holds on an object, or on columns of it. No
+/// grantee means `PUBLIC`.
+struct Grant {
+    grantee: Option<String>,
+    privileges: String,
+    grantable: bool,
+}
+
+impl Grant {
+    fn statement(&self, object: &str) -> String {
+        let grantee = match &self.grantee {
+            Some(role) => quote_identifier(role),
+            None => "PUBLIC".to_string(),
+        };
+        format!(
+            "GRANT {} ON {} TO {}{};",
+            self.privileges,
+            object,
+            grantee,
+            if self.grantable {
+                " WITH GRANT OPTION"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+/// Reads a `grantee`, `privileges`, `is_grantable` row.
+fn grant(row: &Row) -> Grant {
+    Grant {
+        grantee: row.get("grantee"),
+        privileges: row.get("privileges"),
+        grantable: row.get("is_grantable"),
+    }
+}
+
+/// `GRANT` statements for privileges held on single columns of a table or
+/// view, e.g. `GRANT SELECT ("id"), UPDATE ("id") ON TABLE ...`.
+async fn column_grants(
+    client: &Client,
+    oid: u32,
+    target: &str,
+    script: &mut Vec<String>,
+) -> Result<(), AppError> {
+    let query = r#"
+        SELECT
+            CASE WHEN x.grantee <> 0 THEN pg_get_userbyid(x.grantee) END AS grantee,
+            string_agg(format('%s (%I)', x.privilege_type, a.attname), ', '
+                       ORDER BY x.privilege_type) AS privileges,
+            x.is_grantable
+        FROM pg_attribute a
+        CROSS JOIN LATERAL aclexplode(a.attacl) x
+        WHERE a.attrelid = $1 AND a.attnum > 0 AND NOT a.attisdropped
+        GROUP BY a.attnum, x.grantee, x.is_grantable
+        ORDER BY a.attnum, x.grantee <> 0, 1, x.is_grantable
+    "#;
+
+    let object = format!("TABLE {}", target);
+    for row in client.query(query, &[&oid]).await? {
+        script.push(grant(&row).statement(&object));
+    }
+    Ok(())
+}
+
+/// `server_version_num` of the connected server, e.g. `160002`.
+async fn server_version(client: &Client) -> Result<i32, AppError> {
+    Ok(client
+        .query_one("SELECT current_setting('server_version_num')::int", &[])
+        .await?
+        .get(0))
+}
+
+/// `OPTIONS` list from `name=value` entries as stored in the catalogs.
+fn fdw_options(options: &[String]) -> String {
+    options
+        .iter()
+        .map(|option| {
+            let (name, value) = option.split_once('=').unwrap_or((option, ""));
+            format!("{} {}", quote_identifier(name), quote_literal(value))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(keyword: &'static str) -> CreateTable {
+        CreateTable {
+            keyword,
+            target: r#""public"."t""#.to_string(),
+            unlogged: false,
+            partition_of: None,
+            body: Vec::new(),
+            parents: Vec::new(),
+            partition_key: None,
+            server: None,
+            options: Vec::new(),
+        }
+    }
+
+    fn column<'a>(
+        identity: &'a str,
+        generated: &'a str,
+        default_expr: Option<&'a str>,
+    ) -> Column<'a> {
+        Column {
+            name: "id",
+            data_type: "integer",
+            collation: None,
+            identity,
+            generated,
+            default_expr,
+            not_null: false,
+        }
+    }
+
+    #[test]
+    fn scripts_tables() {
+        let mut plain = table("TABLE");
+        plain.unlogged = true;
+        plain.body = vec![
+            r#""id" integer NOT NULL"#.to_string(),
+            r#"CONSTRAINT "t_pkey" PRIMARY KEY (id)"#.to_string(),
+        ];
+        plain.parents = vec!["base".to_string(), "other".to_string()];
+        plain.options = vec!["fillfactor=80".to_string()];
+        assert_eq!(
+            plain.statement(),
+            "CREATE UNLOGGED TABLE \"public\".\"t\" (\n    \"id\" integer NOT NULL,\n    \
+             CONSTRAINT \"t_pkey\" PRIMARY KEY (id)\n)\nINHERITS (base, other)\nWITH (fillfactor=80);"
+        );
+
+        assert_eq!(
+            table("TABLE").statement(),
+            r#"CREATE TABLE "public"."t" ();"#
+        );
+
+        let mut partitioned = table("TABLE");
+        partitioned.partition_of = Some(("parent".to_string(), "FOR VALUES IN ('a')".to_string()));
+        partitioned.partition_key = Some("RANGE (id)".to_string());
+        assert_eq!(
+            partitioned.statement(),
+            "CREATE TABLE \"public\".\"t\" PARTITION OF parent\nFOR VALUES IN ('a')\nPARTITION BY RANGE (id);"
+        );
+
+        let mut foreign = table("FOREIGN TABLE");
+        foreign.body = vec![r#""a" integer"#.to_string()];
+        foreign.server = Some((
+            "remote".to_string(),
+            vec!["schema_name=x".to_string(), "table_name=it's".to_string()],
+        ));
+        assert_eq!(
+            foreign.statement(),
+            "CREATE FOREIGN TABLE \"public\".\"t\" (\n    \"a\" integer\n)\nSERVER \"remote\"\n\
+             OPTIONS (\"schema_name\" 'x', \"table_name\" 'it''s');"
+        );
+    }
+
+    #[test]
+    fn scripts_columns() {
+        let cases = [
+            (column("", "", None), r#""id" integer"#),
+            (
+                column("a", "", None),
+                r#""id" integer GENERATED ALWAYS AS IDENTITY"#,
+            ),
+            (
+                column("d", "", None),
+                r#""id" integer GENERATED BY DEFAULT AS IDENTITY"#,
+            ),
+            (
+                column("", "", Some("nextval('s'::regclass)")),
+                r#""id" integer DEFAULT nextval('s'::regclass)"#,
+            ),
+            (
+                column("", "s", Some("(a * 2)")),
+                r#""id" integer GENERATED ALWAYS AS ((a * 2)) STORED"#,
+            ),
+            // PostgreSQL 18
+            (
+                column("", "v", Some("(a * 2)")),
+                r#""id" integer GENERATED ALWAYS AS ((a * 2)) VIRTUAL"#,
+            ),
+        ];
+        for (column, expected) in cases {
+            assert_eq!(column.definition().unwrap(), expected);
+        }
+
+        let named = Column {
+            name: "Name",
+            data_type: "text",
+            collation: Some(r#"pg_catalog."C""#),
+            not_null: true,
+            ..column("", "", Some("'x'::text"))
+        };
+        assert_eq!(
+            named.definition().unwrap(),
+            r#""Name" text COLLATE pg_catalog."C" DEFAULT 'x'::text NOT NULL"#
+        );
+
+        assert!(column("", "x", Some("1")).definition().is_err());
+    }
+
+    #[test]
+    fn finds_cloned_triggers_by_server_version() {
+        assert_eq!(cloned_trigger_filter(120017), "");
+        assert_eq!(cloned_trigger_filter(130000), "AND tgparentid = 0");
+        assert_eq!(cloned_trigger_filter(170002), "AND tgparentid = 0");
+    }
+
+    #[test]
+    fn scripts_views() {
+        assert_eq!(
+            create_view(
+                "VIEW",
+                r#""public"."v""#,
+                &["security_barrier=true".to_string()],
+                " SELECT t.id\n   FROM t;",
+                None
+            ),
+            "CREATE VIEW \"public\".\"v\" WITH (security_barrier=true) AS\nSELECT t.id\n   FROM t;"
+        );
+        assert_eq!(
+            create_view("MATERIALIZED VIEW", "mv", &[], "SELECT 1;", Some(true)),
+            "CREATE MATERIALIZED VIEW mv AS\nSELECT 1\nWITH DATA;"
+        );
+        assert_eq!(
+            create_view("MATERIALIZED VIEW", "mv", &[], "SELECT 1;", Some(false)),
+            "CREATE MATERIALIZED VIEW mv AS\nSELECT 1\nWITH NO DATA;"
+        );
+    }
+
+    #[test]
+    fn names_routines_by_kind() {
+        assert_eq!(function_keyword("f").unwrap(), "FUNCTION");
+        assert_eq!(function_keyword("w").unwrap(), "FUNCTION");
+        assert_eq!(function_keyword("p").unwrap(), "PROCEDURE");
+        assert!(function_keyword("a").is_err());
+    }
+
+    #[test]
+    fn scripts_sequences() {
+        let mut sequence = Sequence {
+            data_type: "integer",
+            start: 3,
+            increment: 2,
+            min: -5,
+            max: 50,
+            cache: 4,
+            cycle: false,
+        };
+        assert_eq!(
+            sequence.statement("s"),
+            "CREATE SEQUENCE s\n    AS integer\n    START WITH 3\n    INCREMENT BY 2\n    \
+             MINVALUE -5\n    MAXVALUE 50\n    CACHE 4;"
+        );
+        sequence.cycle = true;
+        assert!(sequence
+            .statement("s")
+            .ends_with("\n    CACHE 4\n    CYCLE;"));
+    }
+
+    #[test]
+    fn scripts_types() {
+        assert_eq!(
+            create_enum("mood", &["sad".to_string(), "it's ok".to_string()]),
+            "CREATE TYPE mood AS ENUM (\n    'sad',\n    'it''s ok'\n);"
+        );
+        assert_eq!(
+            create_composite(
+                "pair",
+                &["x integer".to_string(), r#""Y" text"#.to_string()]
+            ),
+            "CREATE TYPE pair AS (\n    x integer,\n    \"Y\" text\n);"
+        );
+
+        let domain = Domain {
+            base_type: "integer",
+            collation: None,
+            default_value: Some("1"),
+            not_null: true,
+            constraints: vec![
+                DomainConstraint {
+                    name: "positive",
+                    definition: "CHECK ((VALUE > 0))",
+                    validated: true,
+                },
+                DomainConstraint {
+                    name: "small",
+                    definition: "CHECK ((VALUE < 100)) NOT VALID",
+                    validated: false,
+                },
+            ],
+        };
+        assert_eq!(
+            domain.statements("posint"),
+            [
+                "CREATE DOMAIN posint AS integer DEFAULT 1 NOT NULL\n    \
+                 CONSTRAINT \"positive\" CHECK ((VALUE > 0));",
+                "ALTER DOMAIN posint ADD CONSTRAINT \"small\" CHECK ((VALUE < 100)) NOT VALID;",
+            ]
+        );
+
+        let collated = Domain {
+            base_type: "text",
+            collation: Some(r#"pg_catalog."C""#),
+            default_value: None,
+            not_null: false,
+            constraints: Vec::new(),
+        };
+        assert_eq!(
+            collated.statements("ctext"),
+            [r#"CREATE DOMAIN ctext AS text COLLATE pg_catalog."C";"#]
+        );
+    }
+
+    fn grant(grantee: Option<&str>, privileges: &str, grantable: bool) -> Grant {
+        Grant {
+            grantee: grantee.map(str::to_string),
+            privileges: privileges.to_string(),
+            grantable,
+        }
+    }
+
+    fn privileges(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn scripts_grants() {
+        let all = ["DELETE", "INSERT", "SELECT"];
+        let table = ObjectAcl {
+            owner: "owner".to_string(),
+            owner_privileges: privileges(&["INSERT", "SELECT"]),
+            default_privileges: privileges(&all),
+            grants: vec![
+                grant(None, "SELECT", false),
+                grant(Some("reader"), "INSERT, SELECT", true),
+            ],
+        };
+        assert_eq!(
+            table.statements(Acl::Relation, "TABLE t"),
+            [
+                r#"REVOKE ALL ON TABLE t FROM "owner";"#,
+                r#"GRANT INSERT, SELECT ON TABLE t TO "owner";"#,
+                "GRANT SELECT ON TABLE t TO PUBLIC;",
+                r#"GRANT INSERT, SELECT ON TABLE t TO "reader" WITH GRANT OPTION;"#,
+            ]
+        );
+
+        // PUBLIC may execute a function unless that was revoked
+        let function = ObjectAcl {
+            owner: "owner".to_string(),
+            owner_privileges: privileges(&["EXECUTE"]),
+            default_privileges: privileges(&["EXECUTE"]),
+            grants: vec![grant(Some("app"), "EXECUTE", false)],
+        };
+        assert_eq!(
+            function.statements(Acl::Function, "FUNCTION f()"),
+            [
+                "REVOKE ALL ON FUNCTION f() FROM PUBLIC;",
+                r#"GRANT EXECUTE ON FUNCTION f() TO "app";"#,
+            ]
+        );
+
+        let revoked_from_owner = ObjectAcl {
+            owner: "owner".to_string(),
+            owner_privileges: Vec::new(),
+            default_privileges: privileges(&["USAGE"]),
+            grants: vec![grant(None, "USAGE", false)],
+        };
+        assert_eq!(
+            revoked_from_owner.statements(Acl::Type, "TYPE mood"),
+            [
+                r#"REVOKE ALL ON TYPE mood FROM "owner";"#,
+                "GRANT USAGE ON TYPE mood TO PUBLIC;",
+            ]
+        );
+
+        // Column privileges are granted on the table
+        assert_eq!(
+            grant(None, r#"SELECT ("id"), UPDATE ("id")"#, false).statement("TABLE t"),
+            r#"GRANT SELECT ("id"), UPDATE ("id") ON TABLE t TO PUBLIC;"#
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use crate::db::sql::{qualified_name, quote_identifier, Params};
+use crate::db::types::JsonParam;
+use crate::error::AppError;
+use crate::models::{RowIdentity, RowIdentityKind};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tokio_postgres::Client;
+
+/// One change to a table row. Rows are addressed by the values of their
+/// identity columns (see `row_identity`) as they were when the row was read.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "UPPERCASE")]
+pub enum RowEdit {
+    /// Columns left out get their default.
+    Insert { data: Map<String, Value> },
+    Update {
+        key: Map<String, Value>,
+        data: Map<String, Value>,
+    },
+    Delete { key: Map<String, Value> },
+}
+
+/// Finds the columns that identify a row: the primary key, or else the
+/// narrowest unique index whose columns are all `NOT NULL`. Partial and
+/// expression indexes don't qualify.
+pub async fn row_identity(
+    client: &Client,
+    schema: &str,
+    table: &str,
+) -> Result<Option<RowIdentity>, tokio_postgres::Error> {
+    let query = r#"
+        SELECT i.indisprimary, array_agg(a.attname::text ORDER BY k.ord) AS columns
+        FROM pg_index i
+        JOIN pg_class c ON c.oid = i.indrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        CROSS JOIN LATERAL unnest(i.indkey::int2[]) WITH ORDINALITY AS k(attnum, ord)
+        JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = k.attnum
+        WHERE n.nspname = $1 AND c.relname = $2
+          AND (i.indisprimary
+               OR (i.indisunique AND i.indisvalid
+                   AND i.indpred IS NULL AND i.indexprs IS NULL))
+        GROUP BY i.indexrelid, i.indisprimary
+        HAVING bool_and(a.attnotnull)
+        ORDER BY i.indisprimary DESC, count(*), i.indexrelid
+        LIMIT 1
+    "#;
+
+    let row = client.query_opt(query, &[&schema, &table]).await?;
+    Ok(row.map(|row| RowIdentity {
+        kind: if row.get(0) {
+            RowIdentityKind::PrimaryKey
+        } else {
+            RowIdentityKind::UniqueKey
+        },
+        columns: row.get(1),
+    }))
+}
+
+/// Applies a batch of edits in one transaction: either every edit takes
+/// effect or none does. An update or delete whose row is gone, e.g. because
+/// someone else deleted it or changed its key, fails the batch. Returns the
+/// number of rows affected.
+pub async fn apply_edits(
+    client: &Client,
+    schema: &str,
+    table: &str,
+    edits: &[RowEdit],
+) -> Result<u64, AppError> {
+    let needs_identity = edits
+        .iter()
+        .any(|edit| !matches!(edit, RowEdit::Insert { .. }));
+    let identity = if needs_identity {
+        let identity = row_identity(client, schema, table).await?.ok_or_else(|| {
+            AppError::validation(
+                "Table has no primary key or unique key, so its rows can't be updated or deleted safely",
+            )
+        })?;
+        Some(identity)
+    } else {
+        None
+    };
+    let target = qualified_name(schema, table);
+
+    client.batch_execute("BEGIN").await?;
+    let mut affected = 0;
+    for (i, edit) in edits.iter().enumerate() {
+        match apply_edit(client, &target, identity.as_ref(), edit).await {
+            Ok(rows) => affected += rows,
+            Err(e) => {
+                if let Err(rollback) = client.batch_execute("ROLLBACK").await {
+                    eprintln!("failed to roll back edits: {}", rollback);
+                }
+                return Err(e.context(&format!("Edit {} failed", i + 1)));
+            }
+        }
+    }
+    client.batch_execute("COMMIT").await?;
+    Ok(affected)
+}
+
+async fn apply_edit(
+    client: &Client,
+    target: &str,
+    identity: Option<&RowIdentity>,
+    edit: &RowEdit,
+) -> Result<u64, AppError> {
+    let mut params = Params::default();
+    let sql = match edit {
+        RowEdit::Insert { data } if data.is_empty() => {
+            format!("INSERT INTO {} DEFAULT VALUES", target)
+        }
+        RowEdit::Insert { data } => {
+            let columns = data
+                .keys()
+                .map(|c| quote_identifier(c))
+                .collect::<Vec<_>>();
+            let placeholders = data
+                .values()
+                .map(|value| params.push(JsonParam(value)))
+                .collect::<Vec<_>>();
+            format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                target,
+                columns.join(", "),
+                placeholders.join(", ")
+            )
+        }
+        RowEdit::Update { key, data } => {
+            if data.is_empty() {
+                return Err(AppError::validation("Update has no columns to set"));
+            }
+            let assignments = data
+                .iter()
+                .map(|(column, value)| {
+                    format!("{} = {}", quote_identifier(column), params.push(JsonParam(value)))
+                })
+                .collect::<Vec<_>>();
+            let condition = key_condition(identity, key, &mut params)?;
+            format!(
+                "UPDATE {} SET {} WHERE {}",
+                target,
+                assignments.join(", "),
+                condition
+            )
+        }
+        RowEdit::Delete { key } => {
+            let condition = key_condition(identity, key, &mut params)?;
+            format!("DELETE FROM {} WHERE {}", target, condition)
+        }
+    };
+
+    let rows = client.execute(&sql, &params.as_refs()).await?;
+
+    if !matches!(edit, RowEdit::Insert { .. }) && rows == 0 {
+        return Err(AppError::not_found(
+            "Row not found; it may have been changed or deleted since it was read",
+        ));
+    }
+    Ok(rows)
+}
+
+/// `WHERE` condition matching the identity columns against `key`.
+fn key_condition<'a>(
+    identity: Option<&RowIdentity>,
+    key: &'a Map<String, Value>,
+    params: &mut Params<'a>,
+) -> Result<String, AppError> {
+    let identity = identity.expect("identity is looked up for updates and deletes");
+    let conditions = identity
+        .columns
+        .iter()
+        .map(|column| {
+            let value = key
+                .get(column)
+                .filter(|v| !v.is_null())
+                .ok_or_else(|| {
+                    AppError::validation(format!("Missing value for key column \"{}\"", column))
+                })?;
+            Ok(format!("{} = {}", quote_identifier(column), params.push(JsonParam(value))))
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+    Ok(conditions.join(" AND "))
+}
@@ -0,0 +1,355 @@
+use crate::db::plan_analysis::{analyze_plan, table_rows};
+use crate::db::session::transaction_status;
+use crate::db::types::decode_row;
+use crate::error::{AppError, ErrorCategory};
+use crate::models::{
+    BufferUsage, ExplainResult, PlanNode, QueryPlan, TransactionStatus, TriggerTiming, WalUsage,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use tokio_postgres::Client;
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExplainFormat {
+    Text,
+    #[default]
+    Json,
+}
+
+/// `EXPLAIN` options. ANALYZE is off unless asked for, so explaining a
+/// statement doesn't run it.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct ExplainOptions {
+    pub analyze: bool,
+    pub buffers: bool,
+    pub verbose: bool,
+    pub settings: bool,
+    /// Requires ANALYZE.
+    pub wal: bool,
+    pub format: ExplainFormat,
+}
+
+impl ExplainOptions {
+    /// The `EXPLAIN (...) ` put in front of the statement.
+    fn prefix(&self) -> Result<String, AppError> {
+        if self.wal && !self.analyze {
+            return Err(AppError::validation("WAL usage can only be reported with ANALYZE"));
+        }
+        let mut options: Vec<&str> = [
+            ("ANALYZE", self.analyze),
+            ("BUFFERS", self.buffers),
+            ("VERBOSE", self.verbose),
+            ("SETTINGS", self.settings),
+            ("WAL", self.wal),
+        ]
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(option, _)| option)
+        .collect();
+        options.push(match self.format {
+            ExplainFormat::Text => "FORMAT TEXT",
+            ExplainFormat::Json => "FORMAT JSON",
+        });
+        Ok(format!("EXPLAIN ({}) ", options.join(", ")))
+    }
+}
+
+const SAVEPOINT: &str = "pgsql_studio_explain";
+
+/// Explains `query`. ANALYZE really executes the statement, so it runs in a
+/// transaction that is rolled back afterwards, or in a savepoint when one
+/// is already open; an explained `DELETE` leaves the rows in place. Error
+/// positions point into `query`.
+pub async fn explain(
+    client: &Client,
+    query: &str,
+    options: &ExplainOptions,
+) -> Result<ExplainResult, AppError> {
+    let prefix = options.prefix()?;
+    let statement = format!("{}{}", prefix, query);
+
+    let rows = if options.analyze {
+        let (begin, undo) = match transaction_status(client).await? {
+            TransactionStatus::Idle => ("BEGIN".to_string(), "ROLLBACK".to_string()),
+            _ => (
+                format!("SAVEPOINT {}", SAVEPOINT),
+                format!("ROLLBACK TO SAVEPOINT {0}; RELEASE SAVEPOINT {0}", SAVEPOINT),
+            ),
+        };
+        client.batch_execute(&begin).await?;
+        let rows = client.query(&statement, &[]).await;
+        let undone = client.batch_execute(&undo).await;
+        let rows = rows.map_err(|e| AppError::from(e).shift_position(-(prefix.len() as i64)))?;
+        undone.map_err(|e| AppError::from(e).context("Failed to roll back the explained statement"))?;
+        rows
+    } else {
+        client
+            .query(&statement, &[])
+            .await
+            .map_err(|e| AppError::from(e).shift_position(-(prefix.len() as i64)))?
+    };
+
+    let (text, plan) = match options.format {
+        ExplainFormat::Text => {
+            let lines: Vec<String> = rows.iter().map(|row| row.get(0)).collect();
+            (Some(lines.join("\n")), None)
+        }
+        ExplainFormat::Json => {
+            let raw = rows
+                .first()
+                .and_then(|row| decode_row(row).0.into_iter().next())
+                .unwrap_or(Value::Null);
+            let mut plan = parse_plan(raw)?;
+            let table_rows = plan_table_rows(client, &plan)
+                .await
+                .map_err(|e| e.context("Failed to look up table sizes for the plan"))?;
+            analyze_plan(&mut plan, &table_rows);
+            (None, Some(plan))
+        }
+    };
+    Ok(ExplainResult {
+        text,
+        plan,
+        rolled_back: options.analyze,
+    })
+}
+
+/// Looks up the sizes of the tables `plan` scans. Inside a transaction the
+/// lookup gets a savepoint of its own, so if it fails the transaction is
+/// left as it was rather than aborted.
+async fn plan_table_rows(
+    client: &Client,
+    plan: &QueryPlan,
+) -> Result<HashMap<String, f64>, AppError> {
+    if matches!(transaction_status(client).await?, TransactionStatus::Idle) {
+        return Ok(table_rows(client, plan).await?);
+    }
+
+    client.batch_execute(&format!("SAVEPOINT {}", SAVEPOINT)).await?;
+    let rows = table_rows(client, plan).await;
+    client
+        .batch_execute(&format!(
+            "ROLLBACK TO SAVEPOINT {0}; RELEASE SAVEPOINT {0}",
+            SAVEPOINT
+        ))
+        .await?;
+    Ok(rows?)
+}
+
+/// Reads the output of `EXPLAIN (FORMAT JSON)`.
+pub fn parse_plan(raw: Value) -> Result<QueryPlan, AppError> {
+    let top = raw
+        .get(0)
+        .and_then(Value::as_object)
+        .ok_or_else(|| AppError::new(ErrorCategory::Internal, "Unexpected EXPLAIN output"))?;
+    let root = top
+        .get("Plan")
+        .and_then(Value::as_object)
+        .ok_or_else(|| AppError::new(ErrorCategory::Internal, "EXPLAIN output has no plan"))?;
+
+    let triggers = top
+        .get("Triggers")
+        .and_then(Value::as_array)
+        .map(|triggers| {
+            triggers
+                .iter()
+                .filter_map(Value::as_object)
+                .map(|trigger| {
+                    let mut trigger = Properties(trigger.clone());
+                    TriggerTiming {
+                        name: trigger.string("Trigger Name").unwrap_or_default(),
+                        relation: trigger.string("Relation"),
+                        time_ms: trigger.number("Time").unwrap_or_default(),
+                        calls: trigger.number("Calls").unwrap_or_default(),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(QueryPlan {
+        root: plan_node(root.clone(), &mut 0),
+        planning_time_ms: top.get("Planning Time").and_then(Value::as_f64),
+        execution_time_ms: top.get("Execution Time").and_then(Value::as_f64),
+        settings: top
+            .get("Settings")
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default(),
+        triggers,
+        findings: Vec::new(),
+        raw,
+    })
+}
+
+fn plan_node(node: Map<String, Value>, next_id: &mut usize) -> PlanNode {
+    let id = *next_id;
+    *next_id += 1;
+    let mut node = Properties(node);
+
+    let buffers = node.0.contains_key("Shared Hit Blocks").then(|| BufferUsage {
+        shared_hit: node.integer("Shared Hit Blocks"),
+        shared_read: node.integer("Shared Read Blocks"),
+        shared_dirtied: node.integer("Shared Dirtied Blocks"),
+        shared_written: node.integer("Shared Written Blocks"),
+        local_hit: node.integer("Local Hit Blocks"),
+        local_read: node.integer("Local Read Blocks"),
+        local_dirtied: node.integer("Local Dirtied Blocks"),
+        local_written: node.integer("Local Written Blocks"),
+        temp_read: node.integer("Temp Read Blocks"),
+        temp_written: node.integer("Temp Written Blocks"),
+    });
+    let wal = node.0.contains_key("WAL Records").then(|| WalUsage {
+        records: node.integer("WAL Records"),
+        full_page_images: node.integer("WAL FPI"),
+        bytes: node.integer("WAL Bytes"),
+    });
+    let output = match node.0.remove("Output") {
+        Some(Value::Array(columns)) => columns
+            .into_iter()
+            .filter_map(|column| column.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let children = match node.0.remove("Plans") {
+        Some(Value::Array(children)) => children
+            .into_iter()
+            .filter_map(|child| match child {
+                Value::Object(child) => Some(plan_node(child, next_id)),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    PlanNode {
+        id,
+        node_type: node.string("Node Type").unwrap_or_default(),
+        parent_relationship: node.string("Parent Relationship"),
+        relation_name: node.string("Relation Name"),
+        schema: node.string("Schema"),
+        alias: node.string("Alias"),
+        index_name: node.string("Index Name"),
+        join_type: node.string("Join Type"),
+        startup_cost: node.number("Startup Cost").unwrap_or_default(),
+        total_cost: node.number("Total Cost").unwrap_or_default(),
+        plan_rows: node.number("Plan Rows").unwrap_or_default(),
+        plan_width: node.integer("Plan Width"),
+        actual_startup_time_ms: node.number("Actual Startup Time"),
+        actual_total_time_ms: node.number("Actual Total Time"),
+        actual_rows: node.number("Actual Rows"),
+        actual_loops: node.number("Actual Loops"),
+        rows_removed_by_filter: node.number("Rows Removed by Filter"),
+        exclusive_time_ms: None,
+        estimate_factor: None,
+        filter: node.string("Filter"),
+        index_cond: node.string("Index Cond"),
+        output,
+        buffers,
+        wal,
+        details: node.0,
+        children,
+    }
+}
+
+/// Plan properties not yet moved into typed fields.
+struct Properties(Map<String, Value>);
+
+impl Properties {
+    fn string(&mut self, key: &str) -> Option<String> {
+        match self.0.remove(key) {
+            Some(Value::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn number(&mut self, key: &str) -> Option<f64> {
+        self.0.remove(key).and_then(|value| value.as_f64())
+    }
+
+    fn integer(&mut self, key: &str) -> i64 {
+        self.number(key).unwrap_or_default() as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_a_plan_tree() {
+        let raw = json!([{
+            "Plan": {
+                "Node Type": "Hash Join",
+                "Join Type": "Inner",
+                "Startup Cost": 1.5,
+                "Total Cost": 20.25,
+                "Plan Rows": 10,
+                "Plan Width": 8,
+                "Hash Cond": "(a.id = b.a_id)",
+                "Plans": [
+                    {
+                        "Node Type": "Seq Scan",
+                        "Parent Relationship": "Outer",
+                        "Relation Name": "a",
+                        "Schema": "public",
+                        "Alias": "a",
+                        "Total Cost": 5.0,
+                        "Plan Rows": 100,
+                        "Output": ["a.id"],
+                        "Shared Hit Blocks": 3,
+                        "Shared Read Blocks": 1
+                    },
+                    {
+                        "Node Type": "Hash",
+                        "Parent Relationship": "Inner",
+                        "Plans": [{ "Node Type": "Seq Scan", "Relation Name": "b" }]
+                    }
+                ]
+            },
+            "Planning Time": 0.1,
+            "Execution Time": 2.5,
+            "Triggers": [{ "Trigger Name": "t", "Relation": "b", "Time": 0.5, "Calls": 2 }]
+        }]);
+
+        let plan = parse_plan(raw.clone()).unwrap();
+        assert_eq!(plan.planning_time_ms, Some(0.1));
+        assert_eq!(plan.execution_time_ms, Some(2.5));
+        assert_eq!(plan.triggers.len(), 1);
+        assert_eq!(plan.triggers[0].calls, 2.0);
+        assert_eq!(plan.raw, raw);
+
+        let root = &plan.root;
+        assert_eq!((root.id, root.node_type.as_str()), (0, "Hash Join"));
+        assert_eq!(root.join_type.as_deref(), Some("Inner"));
+        assert_eq!(root.total_cost, 20.25);
+        assert_eq!(root.plan_width, 8);
+        assert_eq!(
+            root.details.get("Hash Cond"),
+            Some(&json!("(a.id = b.a_id)"))
+        );
+        assert!(root.actual_rows.is_none());
+
+        // Ids follow a depth-first walk
+        let scan = &root.children[0];
+        assert_eq!(scan.id, 1);
+        assert_eq!(scan.relation_name.as_deref(), Some("a"));
+        assert_eq!(scan.output, ["a.id"]);
+        let buffers = scan.buffers.as_ref().unwrap();
+        assert_eq!((buffers.shared_hit, buffers.shared_read), (3, 1));
+        assert_eq!(root.children[1].id, 2);
+        assert_eq!(root.children[1].children[0].id, 3);
+        assert!(root.children[1].buffers.is_none());
+    }
+
+    #[test]
+    fn rejects_output_without_a_plan() {
+        assert!(parse_plan(json!([{ "Planning Time": 0.1 }])).is_err());
+        assert!(parse_plan(json!({ "Plan": {} })).is_err());
+        assert!(parse_plan(Value::Null).is_err());
+    }
+}
@@ -1,2 +1,18 @@
+pub mod browse;
+pub mod cancel;
+pub mod connect;
+pub mod ddl;
+pub mod editing;
+pub mod explain;
+pub mod objects;
+pub mod plan_analysis;
+pub mod plan_diff;
 pub mod pool;
+pub mod results;
 pub mod schema;
+pub mod script;
+pub mod session;
+pub mod sql;
+pub mod tls;
+pub mod tunnel;
+pub mod types;
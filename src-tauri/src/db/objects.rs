@@ -0,0 +1,228 @@
+use crate::models::{
+    ExtensionInfo, FunctionInfo, FunctionKind, SequenceInfo, TypeAttribute, TypeInfo, TypeKind,
+    Volatility,
+};
+use anyhow::{bail, Result};
+use tokio_postgres::Client;
+
+pub async fn list_functions(client: &Client, schema: &str) -> Result<Vec<FunctionInfo>> {
+    let query = r#"
+        SELECT
+            n.nspname AS schema,
+            p.proname AS name,
+            p.prokind::text AS kind,
+            pg_get_function_arguments(p.oid) AS arguments,
+            pg_get_function_identity_arguments(p.oid) AS identity_arguments,
+            CASE WHEN p.prokind <> 'p' THEN pg_get_function_result(p.oid) END AS return_type,
+            l.lanname AS language,
+            p.provolatile::text AS volatility,
+            p.prosecdef AS is_security_definer,
+            pg_get_userbyid(p.proowner) AS owner,
+            obj_description(p.oid, 'pg_proc') AS comment,
+            x.extname AS extension,
+            CASE WHEN p.prokind IN ('f', 'p', 'w') THEN pg_get_functiondef(p.oid) END AS definition
+        FROM pg_proc p
+        JOIN pg_namespace n ON n.oid = p.pronamespace
+        JOIN pg_language l ON l.oid = p.prolang
+        LEFT JOIN pg_depend d
+            ON d.classid = 'pg_proc'::regclass AND d.objid = p.oid AND d.deptype = 'e'
+        LEFT JOIN pg_extension x ON x.oid = d.refobjid
+        WHERE n.nspname = $1
+        ORDER BY p.proname, identity_arguments
+    "#;
+
+    let rows = client.query(query, &[&schema]).await?;
+
+    rows.iter()
+        .map(|row| {
+            let kind = match row.get::<_, &str>("kind") {
+                "f" => FunctionKind::Function,
+                "p" => FunctionKind::Procedure,
+                "a" => FunctionKind::Aggregate,
+                "w" => FunctionKind::Window,
+                other => bail!("Unknown function kind {}", other),
+            };
+            let volatility = match row.get::<_, &str>("volatility") {
+                "i" => Volatility::Immutable,
+                "s" => Volatility::Stable,
+                "v" => Volatility::Volatile,
+                other => bail!("Unknown volatility {}", other),
+            };
+            Ok(FunctionInfo {
+                schema: row.get("schema"),
+                name: row.get("name"),
+                kind,
+                arguments: row.get("arguments"),
+                identity_arguments: row.get("identity_arguments"),
+                return_type: row.get("return_type"),
+                language: row.get("language"),
+                volatility,
+                is_security_definer: row.get("is_security_definer"),
+                owner: row.get("owner"),
+                comment: row.get("comment"),
+                extension: row.get("extension"),
+                definition: row.get("definition"),
+            })
+        })
+        .collect()
+}
+
+pub async fn list_sequences(client: &Client, schema: &str) -> Result<Vec<SequenceInfo>> {
+    let query = r#"
+        SELECT
+            s.schemaname::text AS schema,
+            s.sequencename::text AS name,
+            format_type(s.data_type, NULL) AS data_type,
+            s.start_value,
+            s.increment_by,
+            s.min_value,
+            s.max_value,
+            s.cache_size,
+            s.cycle,
+            s.last_value,
+            (
+                SELECT format('%s.%s', d.refobjid::regclass, a.attname)
+                FROM pg_depend d
+                JOIN pg_attribute a ON a.attrelid = d.refobjid AND a.attnum = d.refobjsubid
+                WHERE d.classid = 'pg_class'::regclass AND d.objid = c.oid
+                  AND d.refclassid = 'pg_class'::regclass AND d.deptype IN ('a', 'i')
+                LIMIT 1
+            ) AS owned_by,
+            s.sequenceowner::text AS owner,
+            obj_description(c.oid, 'pg_class') AS comment
+        FROM pg_sequences s
+        JOIN pg_namespace n ON n.nspname = s.schemaname
+        JOIN pg_class c ON c.relnamespace = n.oid AND c.relname = s.sequencename
+        WHERE s.schemaname = $1
+        ORDER BY s.sequencename
+    "#;
+
+    let rows = client.query(query, &[&schema]).await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| SequenceInfo {
+            schema: row.get("schema"),
+            name: row.get("name"),
+            data_type: row.get("data_type"),
+            start_value: row.get("start_value"),
+            increment_by: row.get("increment_by"),
+            min_value: row.get("min_value"),
+            max_value: row.get("max_value"),
+            cache_size: row.get("cache_size"),
+            cycle: row.get("cycle"),
+            last_value: row.get("last_value"),
+            owned_by: row.get("owned_by"),
+            owner: row.get("owner"),
+            comment: row.get("comment"),
+        })
+        .collect())
+}
+
+/// Enums, standalone composite types and domains. Row types of tables are
+/// left out.
+pub async fn list_types(client: &Client, schema: &str) -> Result<Vec<TypeInfo>> {
+    let query = r#"
+        SELECT
+            n.nspname AS schema,
+            t.typname AS name,
+            t.typtype::text AS kind,
+            ARRAY(
+                SELECT e.enumlabel::text FROM pg_enum e
+                WHERE e.enumtypid = t.oid
+                ORDER BY e.enumsortorder
+            ) AS labels,
+            ARRAY(
+                SELECT a.attname::text FROM pg_attribute a
+                WHERE a.attrelid = t.typrelid AND a.attnum > 0 AND NOT a.attisdropped
+                ORDER BY a.attnum
+            ) AS attribute_names,
+            ARRAY(
+                SELECT format_type(a.atttypid, a.atttypmod) FROM pg_attribute a
+                WHERE a.attrelid = t.typrelid AND a.attnum > 0 AND NOT a.attisdropped
+                ORDER BY a.attnum
+            ) AS attribute_types,
+            CASE WHEN t.typtype = 'd' THEN format_type(t.typbasetype, t.typtypmod) END AS base_type,
+            t.typnotnull AS not_null,
+            t.typdefault AS default_value,
+            ARRAY(
+                SELECT pg_get_constraintdef(con.oid, true) FROM pg_constraint con
+                WHERE con.contypid = t.oid
+                ORDER BY con.conname
+            ) AS constraints,
+            pg_get_userbyid(t.typowner) AS owner,
+            obj_description(t.oid, 'pg_type') AS comment,
+            x.extname AS extension
+        FROM pg_type t
+        JOIN pg_namespace n ON n.oid = t.typnamespace
+        LEFT JOIN pg_class c ON c.oid = t.typrelid
+        LEFT JOIN pg_depend d
+            ON d.classid = 'pg_type'::regclass AND d.objid = t.oid AND d.deptype = 'e'
+        LEFT JOIN pg_extension x ON x.oid = d.refobjid
+        WHERE n.nspname = $1
+          AND (t.typtype IN ('e', 'd') OR (t.typtype = 'c' AND c.relkind = 'c'))
+        ORDER BY t.typname
+    "#;
+
+    let rows = client.query(query, &[&schema]).await?;
+
+    rows.iter()
+        .map(|row| {
+            let kind = match row.get::<_, &str>("kind") {
+                "e" => TypeKind::Enum,
+                "c" => TypeKind::Composite,
+                "d" => TypeKind::Domain,
+                other => bail!("Unexpected type kind {}", other),
+            };
+            let names: Vec<String> = row.get("attribute_names");
+            let types: Vec<String> = row.get("attribute_types");
+            Ok(TypeInfo {
+                schema: row.get("schema"),
+                name: row.get("name"),
+                kind,
+                labels: row.get("labels"),
+                attributes: names
+                    .into_iter()
+                    .zip(types)
+                    .map(|(name, data_type)| TypeAttribute { name, data_type })
+                    .collect(),
+                base_type: row.get("base_type"),
+                not_null: row.get("not_null"),
+                default_value: row.get("default_value"),
+                constraints: row.get("constraints"),
+                owner: row.get("owner"),
+                comment: row.get("comment"),
+                extension: row.get("extension"),
+            })
+        })
+        .collect()
+}
+
+/// Installed extensions first, then the rest of what the server offers.
+pub async fn list_extensions(client: &Client) -> Result<Vec<ExtensionInfo>> {
+    let query = r#"
+        SELECT
+            coalesce(a.name::text, e.extname::text) AS name,
+            e.extversion AS installed_version,
+            a.default_version,
+            n.nspname::text AS schema,
+            coalesce(obj_description(e.oid, 'pg_extension'), a.comment) AS comment
+        FROM pg_available_extensions a
+        FULL JOIN pg_extension e ON e.extname = a.name
+        LEFT JOIN pg_namespace n ON n.oid = e.extnamespace
+        ORDER BY e.oid IS NULL, 1
+    "#;
+
+    let rows = client.query(query, &[]).await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ExtensionInfo {
+            name: row.get("name"),
+            installed_version: row.get("installed_version"),
+            default_version: row.get("default_version"),
+            schema: row.get("schema"),
+            comment: row.get("comment"),
+        })
+        .collect())
+}
@@ -0,0 +1,477 @@
+use crate::db::sql::{qualified_name, quote_identifier};
+use crate::models::{FindingKind, FindingSeverity, PlanFinding, PlanNode, QueryPlan};
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio_postgres::Client;
+
+/// Tables the planner believes hold at least this many rows are large.
+const LARGE_TABLE_ROWS: f64 = 100_000.0;
+const HUGE_TABLE_ROWS: f64 = 10_000_000.0;
+/// Estimates off by this factor in either direction are reported.
+const MISESTIMATE_FACTOR: f64 = 10.0;
+const SEVERE_MISESTIMATE_FACTOR: f64 = 1_000.0;
+/// Inner-side executions of a nested loop worth reporting.
+const MANY_LOOPS: f64 = 1_000.0;
+const VERY_MANY_LOOPS: f64 = 100_000.0;
+/// Share of the rows read that a filter has to discard to be reported.
+const FILTER_REMOVED_SHARE: f64 = 0.9;
+/// Misestimates and wasteful filters on fewer rows than this don't matter.
+const MIN_ROWS: f64 = 1_000.0;
+/// Share of execution time that makes a node a hotspot.
+const HOTSPOT_SHARE: f64 = 0.3;
+const SEVERE_HOTSPOT_SHARE: f64 = 0.6;
+/// Nodes faster than this aren't hotspots however small the query.
+const MIN_HOTSPOT_MS: f64 = 1.0;
+
+/// Planner row counts (`pg_class.reltuples`) of the tables `plan` scans
+/// sequentially. Tables that were never analyzed are left out.
+pub async fn table_rows(
+    client: &Client,
+    plan: &QueryPlan,
+) -> Result<HashMap<String, f64>, tokio_postgres::Error> {
+    let mut names = Vec::new();
+    walk(&plan.root, &mut |node| {
+        if is_seq_scan(node) {
+            names.extend(relation_key(node));
+        }
+    });
+    if names.is_empty() {
+        return Ok(HashMap::new());
+    }
+    names.sort();
+    names.dedup();
+
+    let query = r#"
+        SELECT name, c.reltuples::float8 AS reltuples
+        FROM unnest($1::text[]) AS name
+        JOIN pg_class c ON c.oid = to_regclass(name)
+        WHERE c.reltuples >= 0
+    "#;
+    let rows = client.query(query, &[&names]).await?;
+    Ok(rows
+        .iter()
+        .map(|row| (row.get("name"), row.get("reltuples")))
+        .collect())
+}
+
+/// Fills in each node's exclusive time and estimate factor, then lists what
+/// looks wrong, most severe first and, within a severity, where the most
+/// time went. Most checks need ANALYZE figures and are skipped without them.
+pub fn analyze_plan(plan: &mut QueryPlan, table_rows: &HashMap<String, f64>) {
+    annotate(&mut plan.root);
+
+    let total_ms = plan
+        .execution_time_ms
+        .or_else(|| inclusive_time_ms(&plan.root));
+    let mut findings = Vec::new();
+    inspect(&plan.root, total_ms, table_rows, &mut findings);
+
+    findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then(
+                b.time_share
+                    .unwrap_or(-1.0)
+                    .total_cmp(&a.time_share.unwrap_or(-1.0)),
+            )
+            .then(a.node_id.cmp(&b.node_id))
+    });
+    plan.findings = findings;
+}
+
+fn walk<'a>(node: &'a PlanNode, visit: &mut impl FnMut(&'a PlanNode)) {
+    visit(node);
+    for child in &node.children {
+        walk(child, visit);
+    }
+}
+
+/// Whether `node` reads its whole table. Each worker of a parallel one reads
+/// part of it, but together they still read all of it.
+fn is_seq_scan(node: &PlanNode) -> bool {
+    matches!(node.node_type.as_str(), "Seq Scan" | "Parallel Seq Scan")
+}
+
+/// How `table_rows` keys a scanned table. The schema is only in the plan
+/// with VERBOSE; without it the name resolves through the search path.
+fn relation_key(node: &PlanNode) -> Option<String> {
+    let name = node.relation_name.as_deref()?;
+    Some(match node.schema.as_deref() {
+        Some(schema) => qualified_name(schema, name),
+        None => quote_identifier(name),
+    })
+}
+
+/// Time spent in a node and its children over all loops.
+pub fn inclusive_time_ms(node: &PlanNode) -> Option<f64> {
+    Some(node.actual_total_time_ms? * node.actual_loops?)
+}
+
+fn annotate(node: &mut PlanNode) {
+    for child in &mut node.children {
+        annotate(child);
+    }
+
+    node.exclusive_time_ms = inclusive_time_ms(node).map(|inclusive| {
+        let children: f64 = node.children.iter().filter_map(inclusive_time_ms).sum();
+        // Parallel workers and CTEs scanned by several nodes can make the
+        // children add up to more than the parent
+        (inclusive - children).max(0.0)
+    });
+    node.estimate_factor = node
+        .actual_rows
+        .map(|actual| actual.max(1.0) / node.plan_rows.max(1.0));
+}
+
+fn inspect(
+    node: &PlanNode,
+    total_ms: Option<f64>,
+    table_rows: &HashMap<String, f64>,
+    findings: &mut Vec<PlanFinding>,
+) {
+    let time_share = match (node.exclusive_time_ms, total_ms) {
+        (Some(exclusive), Some(total)) if total > 0.0 => Some((exclusive / total).min(1.0)),
+        _ => None,
+    };
+    let mut report = |kind, severity, message: String| {
+        findings.push(PlanFinding {
+            node_id: node.id,
+            kind,
+            severity,
+            message,
+            time_share,
+        });
+    };
+    let label = describe(node);
+    let loops = node.actual_loops.unwrap_or(1.0);
+
+    if let (Some(share), Some(exclusive)) = (time_share, node.exclusive_time_ms) {
+        if share >= HOTSPOT_SHARE && exclusive >= MIN_HOTSPOT_MS {
+            report(
+                FindingKind::Hotspot,
+                if share >= SEVERE_HOTSPOT_SHARE {
+                    FindingSeverity::Warning
+                } else {
+                    FindingSeverity::Info
+                },
+                format!(
+                    "{} took {:.1} ms, {:.0}% of the execution time",
+                    label,
+                    exclusive,
+                    share * 100.0
+                ),
+            );
+        }
+    }
+
+    if let (Some(factor), Some(actual)) = (node.estimate_factor, node.actual_rows) {
+        let off_by = factor.max(1.0 / factor);
+        if off_by >= MISESTIMATE_FACTOR && actual.max(node.plan_rows) * loops >= MIN_ROWS {
+            let hint = match &node.relation_name {
+                Some(table) => format!("; statistics on {} may be stale, try ANALYZE", table),
+                None => "; the statistics of the tables below it may be stale".to_string(),
+            };
+            report(
+                FindingKind::Misestimate,
+                if off_by >= SEVERE_MISESTIMATE_FACTOR {
+                    FindingSeverity::Critical
+                } else {
+                    FindingSeverity::Warning
+                },
+                format!(
+                    "{} returned {} rows where the planner expected {} ({:.0}x {}){}",
+                    label,
+                    actual,
+                    node.plan_rows,
+                    off_by,
+                    if factor > 1.0 { "more" } else { "fewer" },
+                    hint
+                ),
+            );
+        }
+    }
+
+    if is_seq_scan(node) {
+        let rows = relation_key(node).and_then(|key| table_rows.get(&key).copied());
+        if let Some(rows) = rows.filter(|rows| *rows >= LARGE_TABLE_ROWS) {
+            let hint = match &node.filter {
+                Some(filter) => format!("; an index matching {} could avoid it", filter),
+                None => String::new(),
+            };
+            report(
+                FindingKind::SeqScanOnLargeTable,
+                if rows >= HUGE_TABLE_ROWS {
+                    FindingSeverity::Critical
+                } else {
+                    FindingSeverity::Warning
+                },
+                format!("{} reads all of about {:.0} rows{}", label, rows, hint),
+            );
+        }
+    }
+
+    if let Some(spill) = spill(node) {
+        report(
+            FindingKind::DiskSpill,
+            FindingSeverity::Warning,
+            format!(
+                "{} {}; a larger work_mem would keep it in memory",
+                label, spill
+            ),
+        );
+    }
+
+    if node.node_type == "Nested Loop" {
+        let inner_loops = node
+            .children
+            .get(1)
+            .and_then(|inner| inner.actual_loops)
+            .unwrap_or(0.0);
+        if inner_loops >= MANY_LOOPS {
+            report(
+                FindingKind::NestedLoopWithManyLoops,
+                if inner_loops >= VERY_MANY_LOOPS {
+                    FindingSeverity::Critical
+                } else {
+                    FindingSeverity::Warning
+                },
+                format!(
+                    "{} ran its inner side {:.0} times; a hash or merge join may be cheaper, and an underestimated outer side often causes this",
+                    label, inner_loops
+                ),
+            );
+        }
+    }
+
+    let join_filter_removed = node
+        .details
+        .get("Rows Removed by Join Filter")
+        .and_then(Value::as_f64);
+    for (condition, removed) in [
+        ("filter", node.rows_removed_by_filter),
+        ("join filter", join_filter_removed),
+    ] {
+        let (Some(removed), Some(actual)) = (removed, node.actual_rows) else {
+            continue;
+        };
+        let share = removed / (removed + actual).max(1.0);
+        if share >= FILTER_REMOVED_SHARE && removed * loops >= MIN_ROWS {
+            report(
+                FindingKind::FilterRemovesMostRows,
+                if share >= 0.99 {
+                    FindingSeverity::Warning
+                } else {
+                    FindingSeverity::Info
+                },
+                format!(
+                    "The {} of {} discarded {:.0} of {:.0} rows read ({:.1}%)",
+                    condition,
+                    label,
+                    removed * loops,
+                    (removed + actual) * loops,
+                    share * 100.0
+                ),
+            );
+        }
+    }
+
+    for child in &node.children {
+        inspect(child, total_ms, table_rows, findings);
+    }
+}
+
+/// Describes how a sort, hash or aggregate went to disk, if it did.
+fn spill(node: &PlanNode) -> Option<String> {
+    let detail = |key: &str| node.details.get(key);
+    let number = |key: &str| detail(key).and_then(Value::as_f64).unwrap_or(0.0);
+
+    if detail("Sort Space Type").and_then(Value::as_str) == Some("Disk") {
+        return Some(format!(
+            "sorted on disk using {:.0} kB",
+            number("Sort Space Used")
+        ));
+    }
+    if number("Hash Batches") > 1.0 {
+        return Some(format!(
+            "split the hash table into {:.0} batches",
+            number("Hash Batches")
+        ));
+    }
+    if number("HashAgg Batches") > 1.0 || number("Disk Usage") > 0.0 {
+        return Some(format!(
+            "spilled {:.0} kB of aggregate state to disk",
+            number("Disk Usage")
+        ));
+    }
+    // Buffer counts include the children's
+    let temp_written = |node: &PlanNode| node.buffers.as_ref().map_or(0, |b| b.temp_written);
+    let own = temp_written(node) - node.children.iter().map(temp_written).sum::<i64>();
+    (own > 0).then(|| format!("wrote {} temporary blocks", own))
+}
+
+fn describe(node: &PlanNode) -> String {
+    match &node.relation_name {
+        Some(table) => format!("{} on {}", node.node_type, table),
+        None => node.node_type.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::explain::parse_plan;
+    use serde_json::json;
+
+    fn analyzed(raw: Value, table_rows: &[(&str, f64)]) -> QueryPlan {
+        let mut plan = parse_plan(raw).unwrap();
+        let table_rows = table_rows
+            .iter()
+            .map(|(name, rows)| (name.to_string(), *rows))
+            .collect();
+        analyze_plan(&mut plan, &table_rows);
+        plan
+    }
+
+    #[test]
+    fn finds_problems_in_an_analyzed_plan() {
+        let plan = analyzed(
+            json!([{
+                "Plan": {
+                    "Node Type": "Nested Loop",
+                    "Plan Rows": 10,
+                    "Actual Total Time": 100.0,
+                    "Actual Rows": 10,
+                    "Actual Loops": 1,
+                    "Plans": [
+                        {
+                            "Node Type": "Seq Scan",
+                            "Relation Name": "orders",
+                            "Filter": "(status = 'open')",
+                            "Plan Rows": 50,
+                            "Actual Total Time": 10.0,
+                            "Actual Rows": 5000,
+                            "Actual Loops": 1,
+                            "Rows Removed by Filter": 995000
+                        },
+                        {
+                            "Node Type": "Index Scan",
+                            "Relation Name": "items",
+                            "Plan Rows": 1,
+                            "Actual Total Time": 0.018,
+                            "Actual Rows": 1,
+                            "Actual Loops": 5000
+                        }
+                    ]
+                },
+                "Execution Time": 100.0
+            }]),
+            &[("\"orders\"", 1_000_000.0)],
+        );
+
+        assert_eq!(plan.root.exclusive_time_ms, Some(0.0));
+        assert_eq!(plan.root.children[0].exclusive_time_ms, Some(10.0));
+        assert_eq!(plan.root.children[0].estimate_factor, Some(100.0));
+
+        let findings: Vec<_> = plan
+            .findings
+            .iter()
+            .map(|f| (f.node_id, f.kind, f.severity))
+            .collect();
+        assert_eq!(
+            findings,
+            [
+                (2, FindingKind::Hotspot, FindingSeverity::Warning),
+                (1, FindingKind::Misestimate, FindingSeverity::Warning),
+                (
+                    1,
+                    FindingKind::SeqScanOnLargeTable,
+                    FindingSeverity::Warning
+                ),
+                (
+                    1,
+                    FindingKind::FilterRemovesMostRows,
+                    FindingSeverity::Warning
+                ),
+                (
+                    0,
+                    FindingKind::NestedLoopWithManyLoops,
+                    FindingSeverity::Warning
+                ),
+            ]
+        );
+        assert_eq!(plan.findings[1].time_share, Some(0.1));
+    }
+
+    #[test]
+    fn only_checks_table_sizes_without_analyze() {
+        let plan = analyzed(
+            json!([{
+                "Plan": {
+                    "Node Type": "Sort",
+                    "Plan Rows": 20000000,
+                    "Plans": [{
+                        "Node Type": "Seq Scan",
+                        "Relation Name": "events",
+                        "Schema": "audit",
+                        "Plan Rows": 20000000
+                    }]
+                }
+            }]),
+            &[(r#""audit"."events""#, 20_000_000.0)],
+        );
+
+        assert!(plan.root.exclusive_time_ms.is_none());
+        assert!(plan.root.estimate_factor.is_none());
+        assert_eq!(plan.findings.len(), 1);
+        let finding = &plan.findings[0];
+        assert_eq!(finding.node_id, 1);
+        assert_eq!(finding.kind, FindingKind::SeqScanOnLargeTable);
+        assert_eq!(finding.severity, FindingSeverity::Critical);
+        assert!(finding.time_share.is_none());
+    }
+
+    #[test]
+    fn checks_parallel_scans_of_large_tables() {
+        let plan = analyzed(
+            json!([{
+                "Plan": {
+                    "Node Type": "Gather",
+                    "Plan Rows": 5000,
+                    "Workers Planned": 2,
+                    "Plans": [{
+                        "Node Type": "Parallel Seq Scan",
+                        "Relation Name": "orders",
+                        "Filter": "(status = 'open')",
+                        "Plan Rows": 2083
+                    }]
+                }
+            }]),
+            &[("\"orders\"", 1_000_000.0)],
+        );
+
+        assert_eq!(plan.findings.len(), 1);
+        let finding = &plan.findings[0];
+        assert_eq!(finding.node_id, 1);
+        assert_eq!(finding.kind, FindingKind::SeqScanOnLargeTable);
+        assert_eq!(finding.severity, FindingSeverity::Warning);
+        assert!(finding.message.contains("(status = 'open')"));
+    }
+
+    #[test]
+    fn reports_disk_sorts() {
+        let plan = analyzed(
+            json!([{
+                "Plan": {
+                    "Node Type": "Sort",
+                    "Plan Rows": 100,
+                    "Sort Space Type": "Disk",
+                    "Sort Space Used": 2048
+                }
+            }]),
+            &[],
+        );
+        assert_eq!(plan.findings.len(), 1);
+        assert_eq!(plan.findings[0].kind, FindingKind::DiskSpill);
+        assert!(plan.findings[0].message.contains("2048 kB"));
+    }
+}
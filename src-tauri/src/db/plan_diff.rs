@@ -0,0 +1,363 @@
+use crate::db::explain::parse_plan;
+use crate::db::plan_analysis::{analyze_plan, inclusive_time_ms};
+use crate::error::AppError;
+use crate::models::{
+    MatchedPlanNode, PlanComparison, PlanDelta, PlanNode, QueryPlan, UnmatchedPlanNode,
+};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Diffs two plans of the same query. Each side may be a plan returned by
+/// `explain_query`, the output of `EXPLAIN (FORMAT JSON)`, or that output
+/// as text. The roots are always matched; below them children are paired
+/// by the tables they read and then by node type.
+pub fn compare_plans(before: Value, after: Value) -> Result<PlanComparison, AppError> {
+    let before = read_plan(before, "before")?;
+    let after = read_plan(after, "after")?;
+
+    let mut diff = Diff::default();
+    diff.matched(&before.root, &after.root);
+    diff.children(&before.root, &after.root);
+
+    Ok(PlanComparison {
+        total_cost: delta(before.root.total_cost, after.root.total_cost),
+        planning_time_ms: optional_delta(before.planning_time_ms, after.planning_time_ms),
+        execution_time_ms: optional_delta(before.execution_time_ms, after.execution_time_ms),
+        matched: diff.matched,
+        only_in_before: diff.only_in_before,
+        only_in_after: diff.only_in_after,
+        before,
+        after,
+    })
+}
+
+fn read_plan(value: Value, side: &str) -> Result<QueryPlan, AppError> {
+    let invalid = || {
+        AppError::validation(format!(
+            "The {} plan is not EXPLAIN (FORMAT JSON) output",
+            side
+        ))
+    };
+    let value = match value {
+        Value::String(text) => serde_json::from_str(&text).map_err(|_| invalid())?,
+        value => value,
+    };
+    let raw = match value {
+        // A plan from `explain_query`
+        Value::Object(mut plan) if plan.contains_key("raw") => {
+            plan.remove("raw").unwrap_or_default()
+        }
+        // The single element of the EXPLAIN output, copied without its brackets
+        Value::Object(plan) => Value::Array(vec![Value::Object(plan)]),
+        value => value,
+    };
+
+    let mut plan = parse_plan(raw).map_err(|_| invalid())?;
+    analyze_plan(&mut plan, &HashMap::new());
+    Ok(plan)
+}
+
+#[derive(Default)]
+struct Diff {
+    matched: Vec<MatchedPlanNode>,
+    only_in_before: Vec<UnmatchedPlanNode>,
+    only_in_after: Vec<UnmatchedPlanNode>,
+}
+
+impl Diff {
+    fn matched(&mut self, before: &PlanNode, after: &PlanNode) {
+        self.matched.push(MatchedPlanNode {
+            before_id: before.id,
+            after_id: after.id,
+            before_node_type: before.node_type.clone(),
+            after_node_type: after.node_type.clone(),
+            node_type_changed: before.node_type != after.node_type,
+            relation_name: before
+                .relation_name
+                .clone()
+                .or_else(|| after.relation_name.clone()),
+            total_cost: delta(before.total_cost, after.total_cost),
+            plan_rows: delta(before.plan_rows, after.plan_rows),
+            actual_rows: optional_delta(before.actual_rows, after.actual_rows),
+            actual_time_ms: optional_delta(inclusive_time_ms(before), inclusive_time_ms(after)),
+            exclusive_time_ms: optional_delta(before.exclusive_time_ms, after.exclusive_time_ms),
+        });
+    }
+
+    /// Pairs the children of two matched nodes, each child with the most
+    /// similar one on the other side.
+    fn children(&mut self, before: &PlanNode, after: &PlanNode) {
+        let before_tables: Vec<_> = before.children.iter().map(tables).collect();
+        let after_tables: Vec<_> = after.children.iter().map(tables).collect();
+        let mut candidates = Vec::new();
+        for (b, before_child) in before.children.iter().enumerate() {
+            for (a, after_child) in after.children.iter().enumerate() {
+                let score = similarity(
+                    before_child,
+                    after_child,
+                    &before_tables[b],
+                    &after_tables[a],
+                );
+                if score > 0 {
+                    candidates.push((score, b.abs_diff(a), b, a));
+                }
+            }
+        }
+        // Best first; between equals, keep children where they were
+        candidates.sort_by(|x, y| y.0.cmp(&x.0).then(x.1.cmp(&y.1)).then(x.2.cmp(&y.2)));
+
+        let mut partner = vec![None; before.children.len()];
+        let mut taken = vec![false; after.children.len()];
+        for (_, _, b, a) in candidates {
+            if partner[b].is_none() && !taken[a] {
+                partner[b] = Some(a);
+                taken[a] = true;
+            }
+        }
+
+        for (b, before_child) in before.children.iter().enumerate() {
+            match partner[b] {
+                Some(a) => self.pair(before_child, &after.children[a], before.id, after.id),
+                None => unmatched(before_child, Some(before.id), &mut self.only_in_before),
+            }
+        }
+        for (a, after_child) in after.children.iter().enumerate() {
+            if !taken[a] {
+                unmatched(after_child, Some(after.id), &mut self.only_in_after);
+            }
+        }
+    }
+
+    /// Matches two paired nodes. A node with a single child facing one
+    /// that has none or several is taken to have been added or removed
+    /// around the rest of the plan, like a Sort an index made unnecessary
+    /// or the Gather of a plan that went parallel, when its child is at
+    /// least as similar to the other node as it is.
+    fn pair(
+        &mut self,
+        before: &PlanNode,
+        after: &PlanNode,
+        before_parent: usize,
+        after_parent: usize,
+    ) {
+        if before.node_type != after.node_type {
+            if let Some(child) = wrapped(before, after) {
+                self.only_in_before
+                    .push(unmatched_node(before, Some(before_parent)));
+                return self.pair(child, after, before.id, after_parent);
+            }
+            if let Some(child) = wrapped(after, before) {
+                self.only_in_after
+                    .push(unmatched_node(after, Some(after_parent)));
+                return self.pair(before, child, before_parent, after.id);
+            }
+        }
+        self.matched(before, after);
+        self.children(before, after);
+    }
+}
+
+/// The only child of `node` if it, rather than `node`, should face `other`.
+fn wrapped<'a>(node: &'a PlanNode, other: &PlanNode) -> Option<&'a PlanNode> {
+    let [child] = node.children.as_slice() else {
+        return None;
+    };
+    if other.children.len() == 1 {
+        return None;
+    }
+    let other_tables = tables(other);
+    let as_is = similarity(node, other, &tables(node), &other_tables);
+    (similarity(child, other, &tables(child), &other_tables) >= as_is).then_some(child)
+}
+
+fn unmatched_node(node: &PlanNode, parent_id: Option<usize>) -> UnmatchedPlanNode {
+    UnmatchedPlanNode {
+        id: node.id,
+        parent_id,
+        node_type: node.node_type.clone(),
+        relation_name: node.relation_name.clone(),
+        total_cost: node.total_cost,
+        plan_rows: node.plan_rows,
+        actual_time_ms: inclusive_time_ms(node),
+    }
+}
+
+/// Adds `node` and everything below it.
+fn unmatched(node: &PlanNode, parent_id: Option<usize>, nodes: &mut Vec<UnmatchedPlanNode>) {
+    nodes.push(unmatched_node(node, parent_id));
+    for child in &node.children {
+        unmatched(child, Some(node.id), nodes);
+    }
+}
+
+/// How alike two sibling nodes are; 0 means they shouldn't be paired.
+/// Nodes reading different tables never are, so a Seq Scan that became an
+/// Index Scan on the same table pairs up, but two Seq Scans on different
+/// tables don't.
+fn similarity(
+    before: &PlanNode,
+    after: &PlanNode,
+    before_tables: &HashSet<&str>,
+    after_tables: &HashSet<&str>,
+) -> u32 {
+    let mut score = 0;
+    if !before_tables.is_empty() || !after_tables.is_empty() {
+        if before_tables.is_disjoint(after_tables) {
+            return 0;
+        }
+        score += if scanned(before).is_some() && scanned(before) == scanned(after) {
+            4
+        } else {
+            3
+        };
+    }
+    if before.node_type == after.node_type {
+        score += 2;
+    }
+    if before.parent_relationship == after.parent_relationship {
+        score += 1;
+    }
+    score
+}
+
+/// The table a node scans, by alias so that a self-join's sides differ.
+fn scanned(node: &PlanNode) -> Option<&str> {
+    node.alias.as_deref().or(node.relation_name.as_deref())
+}
+
+/// The tables scanned in a node's subtree.
+fn tables(node: &PlanNode) -> HashSet<&str> {
+    let mut tables = HashSet::new();
+    collect_tables(node, &mut tables);
+    tables
+}
+
+fn collect_tables<'a>(node: &'a PlanNode, tables: &mut HashSet<&'a str>) {
+    tables.extend(scanned(node));
+    for child in &node.children {
+        collect_tables(child, tables);
+    }
+}
+
+fn delta(before: f64, after: f64) -> PlanDelta {
+    PlanDelta {
+        before,
+        after,
+        change: after - before,
+        ratio: (before != 0.0).then(|| after / before),
+    }
+}
+
+fn optional_delta(before: Option<f64>, after: Option<f64>) -> Option<PlanDelta> {
+    Some(delta(before?, after?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn scan(node_type: &str, table: &str, relationship: &str, cost: f64) -> Value {
+        json!({
+            "Node Type": node_type,
+            "Relation Name": table,
+            "Alias": table,
+            "Parent Relationship": relationship,
+            "Total Cost": cost
+        })
+    }
+
+    fn hash_join() -> Value {
+        json!([{
+            "Plan": {
+                "Node Type": "Hash Join",
+                "Total Cost": 100.0,
+                "Plans": [
+                    scan("Seq Scan", "orders", "Outer", 40.0),
+                    {
+                        "Node Type": "Hash",
+                        "Parent Relationship": "Inner",
+                        "Total Cost": 50.0,
+                        "Plans": [scan("Seq Scan", "customers", "Outer", 50.0)]
+                    }
+                ]
+            },
+            "Planning Time": 0.2
+        }])
+    }
+
+    fn nested_loop() -> Value {
+        json!([{
+            "Plan": {
+                "Node Type": "Nested Loop",
+                "Total Cost": 50.0,
+                "Plans": [
+                    scan("Seq Scan", "orders", "Outer", 40.0),
+                    scan("Index Scan", "customers", "Inner", 0.5)
+                ]
+            },
+            "Planning Time": 0.1
+        }])
+    }
+
+    #[test]
+    fn pairs_nodes_by_table_and_skips_removed_wrappers() {
+        let comparison = compare_plans(hash_join(), nested_loop()).unwrap();
+
+        assert_eq!(comparison.total_cost.change, -50.0);
+        assert_eq!(comparison.total_cost.ratio, Some(0.5));
+        assert!(comparison.execution_time_ms.is_none());
+        assert!(comparison.planning_time_ms.is_some());
+
+        let matched: Vec<_> = comparison
+            .matched
+            .iter()
+            .map(|m| (m.before_id, m.after_id, m.node_type_changed))
+            .collect();
+        assert_eq!(matched, [(0, 0, true), (1, 1, false), (3, 2, true)]);
+        assert_eq!(
+            comparison.matched[2].relation_name.as_deref(),
+            Some("customers")
+        );
+
+        // The Hash went away with the hash join
+        assert_eq!(comparison.only_in_before.len(), 1);
+        assert_eq!(comparison.only_in_before[0].node_type, "Hash");
+        assert_eq!(comparison.only_in_before[0].parent_id, Some(0));
+        assert!(comparison.only_in_after.is_empty());
+    }
+
+    #[test]
+    fn never_pairs_scans_of_different_tables() {
+        let before = json!([{ "Plan": {
+            "Node Type": "Append",
+            "Plans": [scan("Seq Scan", "a", "Member", 1.0)]
+        }}]);
+        let after = json!([{ "Plan": {
+            "Node Type": "Append",
+            "Plans": [scan("Seq Scan", "b", "Member", 1.0)]
+        }}]);
+        let comparison = compare_plans(before, after).unwrap();
+        assert_eq!(comparison.matched.len(), 1);
+        assert_eq!(
+            comparison.only_in_before[0].relation_name.as_deref(),
+            Some("a")
+        );
+        assert_eq!(
+            comparison.only_in_after[0].relation_name.as_deref(),
+            Some("b")
+        );
+    }
+
+    #[test]
+    fn reads_every_plan_form() {
+        let explained = serde_json::to_value(read_plan(hash_join(), "before").unwrap()).unwrap();
+        let text = Value::String(nested_loop().to_string());
+        let unwrapped = nested_loop()[0].clone();
+
+        assert!(compare_plans(explained, text).is_ok());
+        assert!(compare_plans(unwrapped, hash_join()).is_ok());
+        assert!(compare_plans(json!("not json"), hash_join()).is_err());
+        assert!(compare_plans(hash_join(), json!({ "Plans": [] })).is_err());
+    }
+}
@@ -1,70 +1,432 @@
-use anyhow::Result;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
-use tokio_postgres::{Client, NoTls};
+use crate::db::connect::connect;
+use crate::db::tunnel::{open_tunnel, SshTunnel};
+use crate::models::ConnectionConfig;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, RwLock, Semaphore};
+use tokio_postgres::Client;
 
-type ConnectionPool = Arc<RwLock<HashMap<String, Arc<Client>>>>;
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
-pub struct PoolManager {
-    pools: ConnectionPool,
+/// Sizing and lifetime limits applied to every connection pool.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PoolSettings {
+    /// Idle clients kept open even when unused.
+    pub min_size: usize,
+    /// Upper bound on clients open at once, idle or in use.
+    pub max_size: usize,
+    /// Idle clients above `min_size` are closed after this long.
+    pub idle_timeout_secs: u64,
+    /// Clients are recycled after this long regardless of use.
+    pub max_lifetime_secs: u64,
+    /// How often idle clients are pinged in the background.
+    pub health_check_interval_secs: u64,
+    /// How long a command waits for a free client before giving up.
+    pub acquire_timeout_secs: u64,
 }
 
-impl PoolManager {
-    pub fn new() -> Self {
+impl Default for PoolSettings {
+    fn default() -> Self {
         Self {
-            pools: Arc::new(RwLock::new(HashMap::new())),
+            min_size: 0,
+            max_size: 5,
+            idle_timeout_secs: 300,
+            max_lifetime_secs: 1800,
+            health_check_interval_secs: 30,
+            acquire_timeout_secs: 30,
         }
     }
+}
 
-    pub async fn get_client(
-        &self,
+#[derive(Debug, Serialize)]
+pub struct PoolStats {
+    pub connection_id: String,
+    pub size: usize,
+    pub idle: usize,
+    pub in_use: usize,
+    pub waiting: usize,
+    pub min_size: usize,
+    pub max_size: usize,
+    pub total_created: u64,
+    pub total_acquired: u64,
+    pub total_timeouts: u64,
+}
+
+struct Connection {
+    client: Client,
+    created_at: Instant,
+}
+
+struct IdleConnection {
+    conn: Connection,
+    idle_since: Instant,
+}
+
+/// Pool of clients for one saved connection. Idle clients are reused LIFO; a
+/// background task pings idle clients, retires expired ones and keeps
+/// `min_size` warm, so checkout itself never touches the network unless it
+/// has to open a new client.
+pub struct ConnectionPool {
+    connection_id: String,
+    config: ConnectionConfig,
+    password: String,
+    settings: PoolSettings,
+    // Kept alive for as long as the pool; dropping it closes the tunnel.
+    tunnel: Option<SshTunnel>,
+    idle: Mutex<VecDeque<IdleConnection>>,
+    permits: Arc<Semaphore>,
+    size: AtomicUsize,
+    waiting: AtomicUsize,
+    total_created: AtomicU64,
+    total_acquired: AtomicU64,
+    total_timeouts: AtomicU64,
+    closed: AtomicBool,
+}
+
+impl ConnectionPool {
+    fn new(
         connection_id: &str,
-        host: &str,
-        port: u16,
-        database: &str,
-        username: &str,
+        config: &ConnectionConfig,
         password: &str,
-    ) -> Result<Arc<Client>> {
-        let mut pools = self.pools.write().await;
+        settings: PoolSettings,
+        tunnel: Option<SshTunnel>,
+    ) -> Arc<Self> {
+        let max_size = settings.max_size.max(1);
+        let pool = Arc::new(Self {
+            connection_id: connection_id.to_string(),
+            config: config.clone(),
+            password: password.to_string(),
+            settings,
+            tunnel,
+            idle: Mutex::new(VecDeque::new()),
+            permits: Arc::new(Semaphore::new(max_size)),
+            size: AtomicUsize::new(0),
+            waiting: AtomicUsize::new(0),
+            total_created: AtomicU64::new(0),
+            total_acquired: AtomicU64::new(0),
+            total_timeouts: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+        });
 
-        if let Some(client) = pools.get(connection_id) {
-            // Test if connection is still alive
-            match client.simple_query("SELECT 1").await {
-                Ok(_) => return Ok(Arc::clone(client)),
-                Err(_) => {
-                    // Connection is dead, remove it
-                    pools.remove(connection_id);
-                }
+        tokio::spawn(maintain(Arc::downgrade(&pool)));
+        pool
+    }
+
+    /// Checks out a client, reusing an idle one when possible. Waits up to
+    /// `acquire_timeout_secs` when `max_size` clients are already in use.
+    pub async fn get(self: &Arc<Self>) -> Result<PooledClient> {
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        let permit = tokio::time::timeout(
+            Duration::from_secs(self.settings.acquire_timeout_secs),
+            Arc::clone(&self.permits).acquire_owned(),
+        )
+        .await;
+        self.waiting.fetch_sub(1, Ordering::SeqCst);
+
+        let permit = match permit {
+            Ok(permit) => permit?,
+            Err(_) => {
+                self.total_timeouts.fetch_add(1, Ordering::Relaxed);
+                return Err(anyhow!(
+                    "Timed out waiting for a free connection ({} in use)",
+                    self.settings.max_size
+                ));
             }
+        };
+
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(anyhow!("Connection was closed"));
         }
 
-        // Create new connection
-        let (client, connection) = tokio_postgres::connect(
-            &format!(
-                "host={} port={} dbname={} user={} password={}",
-                host, port, database, username, password
-            ),
-            NoTls,
-        )
-        .await?;
+        let conn = match self.take_idle() {
+            Some(conn) => conn,
+            None => self.open().await?,
+        };
 
-        // Spawn connection task
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                eprintln!("connection error: {}", e);
+        self.total_acquired.fetch_add(1, Ordering::Relaxed);
+        Ok(PooledClient {
+            conn: Some(conn),
+            pool: Arc::clone(self),
+            permit: Some(permit),
+        })
+    }
+
+    /// Opens a client of its own for a session to pin while a tab keeps it.
+    /// It doesn't count towards `max_size`, so open tabs can't starve
+    /// everything else, and it is closed rather than returned when dropped.
+    pub async fn dedicated(self: &Arc<Self>) -> Result<PooledClient> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(anyhow!("Connection was closed"));
+        }
+        let client = connect(&self.config, &self.password, self.tunnel.as_ref()).await?;
+        Ok(PooledClient {
+            conn: Some(Connection {
+                client,
+                created_at: Instant::now(),
+            }),
+            pool: Arc::clone(self),
+            permit: None,
+        })
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let size = self.size.load(Ordering::SeqCst);
+        let idle = self.idle.lock().unwrap().len();
+        PoolStats {
+            connection_id: self.connection_id.clone(),
+            size,
+            idle,
+            in_use: size.saturating_sub(idle),
+            waiting: self.waiting.load(Ordering::SeqCst),
+            min_size: self.settings.min_size,
+            max_size: self.settings.max_size,
+            total_created: self.total_created.load(Ordering::Relaxed),
+            total_acquired: self.total_acquired.load(Ordering::Relaxed),
+            total_timeouts: self.total_timeouts.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Closes idle clients and the tunnel. Checked-out clients are discarded
+    /// instead of returned when they are dropped.
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        let drained: Vec<_> = self.idle.lock().unwrap().drain(..).collect();
+        self.size.fetch_sub(drained.len(), Ordering::SeqCst);
+        if let Some(tunnel) = &self.tunnel {
+            tunnel.close();
+        }
+    }
+
+    async fn open(&self) -> Result<Connection> {
+        let client = connect(&self.config, &self.password, self.tunnel.as_ref()).await?;
+        self.size.fetch_add(1, Ordering::SeqCst);
+        self.total_created.fetch_add(1, Ordering::Relaxed);
+        Ok(Connection {
+            client,
+            created_at: Instant::now(),
+        })
+    }
+
+    /// Pops the most recently used idle client that is still usable.
+    fn take_idle(&self) -> Option<Connection> {
+        let mut idle = self.idle.lock().unwrap();
+        while let Some(entry) = idle.pop_back() {
+            if entry.conn.client.is_closed() || self.is_expired(&entry.conn) {
+                self.size.fetch_sub(1, Ordering::SeqCst);
+                continue;
             }
+            return Some(entry.conn);
+        }
+        None
+    }
+
+    fn release(&self, conn: Connection) {
+        if self.closed.load(Ordering::SeqCst)
+            || conn.client.is_closed()
+            || self.is_expired(&conn)
+        {
+            self.size.fetch_sub(1, Ordering::SeqCst);
+            return;
+        }
+        self.idle.lock().unwrap().push_back(IdleConnection {
+            conn,
+            idle_since: Instant::now(),
         });
+    }
+
+    fn is_expired(&self, conn: &Connection) -> bool {
+        conn.created_at.elapsed() >= Duration::from_secs(self.settings.max_lifetime_secs)
+    }
+
+    /// One maintenance pass: drop dead, expired and surplus idle clients,
+    /// then open clients until `min_size` is met. Clients being checked hold
+    /// a permit each, so checkout can't open replacements for them past
+    /// `max_size`; those that find no free permit wait for the next pass.
+    async fn run_health_check(&self) {
+        let idle_timeout = Duration::from_secs(self.settings.idle_timeout_secs);
+        let (candidates, permits) = {
+            let mut idle = self.idle.lock().unwrap();
+            let count = idle.len().min(self.permits.available_permits());
+            match self.permits.try_acquire_many(count as u32) {
+                Ok(permits) => (idle.drain(..count).collect::<Vec<_>>(), Some(permits)),
+                Err(_) => (Vec::new(), None),
+            }
+        };
+        let mut keep = Vec::with_capacity(candidates.len());
+        let mut open = self.size.load(Ordering::SeqCst);
+
+        for entry in candidates {
+            let surplus = open > self.settings.min_size && entry.idle_since.elapsed() >= idle_timeout;
+            let alive = !surplus
+                && !self.is_expired(&entry.conn)
+                && matches!(
+                    tokio::time::timeout(
+                        HEALTH_CHECK_TIMEOUT,
+                        entry.conn.client.simple_query("SELECT 1")
+                    )
+                    .await,
+                    Ok(Ok(_))
+                );
+
+            if alive {
+                keep.push(entry);
+            } else {
+                open -= 1;
+                self.size.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        // Put survivors back behind anything returned during the check, so
+        // recently used clients stay at the hot end.
+        {
+            let mut idle = self.idle.lock().unwrap();
+            for entry in keep.into_iter().rev() {
+                idle.push_front(entry);
+            }
+        }
+        drop(permits);
+
+        while !self.closed.load(Ordering::SeqCst)
+            && self.size.load(Ordering::SeqCst) < self.settings.min_size
+        {
+            let Ok(_permit) = self.permits.try_acquire() else {
+                break;
+            };
+            match self.open().await {
+                Ok(conn) => self.release(conn),
+                Err(e) => {
+                    eprintln!("pool warm-up for {} failed: {}", self.connection_id, e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn maintain(pool: Weak<ConnectionPool>) {
+    loop {
+        let interval = match pool.upgrade() {
+            Some(pool) if !pool.closed.load(Ordering::SeqCst) => {
+                pool.run_health_check().await;
+                Duration::from_secs(pool.settings.health_check_interval_secs.max(1))
+            }
+            _ => return,
+        };
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// A checked-out client. Derefs to `tokio_postgres::Client` and goes back to
+/// its pool when dropped.
+pub struct PooledClient {
+    conn: Option<Connection>,
+    pool: Arc<ConnectionPool>,
+    // None for a dedicated client, which the pool doesn't track
+    permit: Option<OwnedSemaphorePermit>,
+}
+
+impl PooledClient {
+    /// Settings the client was opened with, e.g. to open a side connection
+    /// for a cancel request.
+    pub fn connection_config(&self) -> &ConnectionConfig {
+        &self.pool.config
+    }
+}
 
-        let client_arc = Arc::new(client);
-        pools.insert(connection_id.to_string(), Arc::clone(&client_arc));
+impl Deref for PooledClient {
+    type Target = Client;
 
-        Ok(client_arc)
+    fn deref(&self) -> &Client {
+        &self.conn.as_ref().expect("client already released").client
     }
+}
 
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            if self.permit.is_some() {
+                self.pool.release(conn);
+            }
+        }
+    }
+}
+
+pub struct PoolManager {
+    pools: RwLock<HashMap<String, Arc<ConnectionPool>>>,
+}
+
+impl PoolManager {
+    pub fn new() -> Self {
+        Self {
+            pools: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The pool for a connection, set up on first use.
+    pub async fn pool(
+        &self,
+        connection_id: &str,
+        config: &ConnectionConfig,
+        password: &str,
+        ssh_secret: Option<&str>,
+        settings: &PoolSettings,
+    ) -> Result<Arc<ConnectionPool>> {
+        let existing = self.pools.read().await.get(connection_id).cloned();
+        let pool = match existing {
+            Some(pool) => pool,
+            None => {
+                // Bring up the tunnel first so its errors are reported as such.
+                // It is opened without the lock held, so a slow bastion doesn't
+                // stall every other connection.
+                let tunnel = match &config.ssh_tunnel {
+                    Some(ssh) => {
+                        Some(open_tunnel(ssh, ssh_secret, &config.host, config.port).await?)
+                    }
+                    None => None,
+                };
+
+                let mut pools = self.pools.write().await;
+                match pools.get(connection_id) {
+                    // Another caller got there first; dropping our tunnel closes it
+                    Some(pool) => Arc::clone(pool),
+                    None => {
+                        let pool = ConnectionPool::new(
+                            connection_id,
+                            config,
+                            password,
+                            settings.clone(),
+                            tunnel,
+                        );
+                        pools.insert(connection_id.to_string(), Arc::clone(&pool));
+                        pool
+                    }
+                }
+            }
+        };
+
+        Ok(pool)
+    }
+
+    /// Closes the pool for a connection; this also tears down its SSH tunnel,
+    /// if any.
     pub async fn remove_connection(&self, connection_id: &str) {
         let mut pools = self.pools.write().await;
-        pools.remove(connection_id);
+        if let Some(pool) = pools.remove(connection_id) {
+            pool.close();
+        }
+    }
+
+    pub async fn stats(&self) -> Vec<PoolStats> {
+        let pools = self.pools.read().await;
+        let mut stats: Vec<_> = pools.values().map(|pool| pool.stats()).collect();
+        stats.sort_by(|a, b| a.connection_id.cmp(&b.connection_id));
+        stats
     }
 }
 
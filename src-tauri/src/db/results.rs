@@ -0,0 +1,243 @@
+use crate::db::cancel::cancel_running;
+use crate::db::pool::PooledClient;
+use crate::db::types::decode_row;
+use crate::models::ResultPage;
+use anyhow::{anyhow, Context, Result};
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Client, Row, RowStream, Statement};
+
+/// Open results nobody has fetched from for this long are closed, so a
+/// forgotten handle doesn't hold a pooled client forever.
+const RESULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How often `sweep_idle` looks for such results.
+pub const IDLE_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Reads a statement's rows incrementally instead of buffering the whole
+/// result. The server only sends as fast as pages are pulled, and no more
+/// than `max_rows` rows are ever read.
+pub struct RowReader {
+    stream: Pin<Box<RowStream>>,
+    // One row read ahead, so a page knows whether another one follows.
+    peeked: Option<Row>,
+    fetched: usize,
+    max_rows: usize,
+    exhausted: bool,
+    /// Whether `close` may cancel the statement instead of reading it to
+    /// the end.
+    cancelable: bool,
+}
+
+impl RowReader {
+    /// Starts `statement`. `cancelable` should only be set for a read-only
+    /// statement outside a transaction block; see `close`.
+    pub async fn start(
+        client: &Client,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+        max_rows: usize,
+        cancelable: bool,
+    ) -> Result<Self, tokio_postgres::Error> {
+        let stream = client.query_raw(statement, params.iter().copied()).await?;
+        Ok(Self {
+            stream: Box::pin(stream),
+            peeked: None,
+            fetched: 0,
+            max_rows,
+            exhausted: false,
+            cancelable,
+        })
+    }
+
+    /// Reads up to `limit` more rows.
+    pub async fn next_page(&mut self, limit: usize) -> Result<ResultPage, tokio_postgres::Error> {
+        let limit = limit.min(self.max_rows - self.fetched);
+        let mut rows = Vec::new();
+        let mut undecodable_cells = Vec::new();
+
+        while rows.len() < limit {
+            let Some(row) = self.next_row().await? else {
+                break;
+            };
+            let (values, undecodable) = decode_row(&row);
+            undecodable_cells.extend(undecodable.into_iter().map(|col| [rows.len(), col]));
+            rows.push(values);
+        }
+        self.fetched += rows.len();
+
+        if !self.exhausted && self.peeked.is_none() {
+            self.peeked = self.next_row().await?;
+        }
+        let more_available = self.peeked.is_some();
+        let truncated = more_available && self.fetched >= self.max_rows;
+
+        Ok(ResultPage {
+            row_count: rows.len(),
+            rows,
+            undecodable_cells,
+            has_more: more_available && !truncated,
+            truncated,
+        })
+    }
+
+    /// Rows inserted, updated or deleted; only known once every row has
+    /// been read.
+    pub fn rows_affected(&self) -> Option<u64> {
+        self.stream.rows_affected()
+    }
+
+    async fn next_row(&mut self) -> Result<Option<Row>, tokio_postgres::Error> {
+        if let Some(row) = self.peeked.take() {
+            return Ok(Some(row));
+        }
+        match self.stream.as_mut().next().await {
+            Some(row) => row.map(Some),
+            None => {
+                self.exhausted = true;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Finishes the statement if it is still producing rows, discarding the
+    /// rest of them; dropping an unfinished stream would leave them in the
+    /// way of the client's next statement. A cancelable statement is
+    /// canceled instead. Anything else runs to completion: canceling it
+    /// would roll back its changes, or abort the transaction it is part of.
+    /// Errors from finishing the statement are returned, since they mean it
+    /// failed after all.
+    pub async fn close(&mut self, client: &PooledClient) -> Result<()> {
+        self.peeked = None;
+        if self.exhausted {
+            return Ok(());
+        }
+        self.exhausted = true;
+
+        // The cancel request has reached the server once this returns, so
+        // it can't hit a later statement: a backend ignores cancels that
+        // arrive while it waits for the next one
+        let canceled = match self.cancelable {
+            true => cancel_running(client).await,
+            false => Ok(()),
+        };
+        while let Some(row) = self.stream.as_mut().next().await {
+            match row {
+                Ok(_) => {}
+                Err(e) if self.cancelable && e.code() == Some(&SqlState::QUERY_CANCELED) => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        canceled.context("Failed to cancel the rest of the result")
+    }
+}
+
+struct OpenResult {
+    client: PooledClient,
+    reader: RowReader,
+    last_used: Instant,
+}
+
+/// Results with more rows to fetch, keyed by result id. Each one keeps its
+/// pooled client checked out until it is exhausted or closed.
+#[derive(Default)]
+pub struct OpenResults {
+    results: Mutex<HashMap<String, Arc<tokio::sync::Mutex<OpenResult>>>>,
+}
+
+impl OpenResults {
+    /// Keeps a partially read result open and returns its id.
+    pub fn insert(&self, client: PooledClient, reader: RowReader) -> String {
+        self.close_idle();
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let result = OpenResult {
+            client,
+            reader,
+            last_used: Instant::now(),
+        };
+        self.results
+            .lock()
+            .unwrap()
+            .insert(id.clone(), Arc::new(tokio::sync::Mutex::new(result)));
+        id
+    }
+
+    /// Reads the next page of an open result. The result is closed once it
+    /// has no more rows, hits the row cap or fails.
+    pub async fn fetch(&self, result_id: &str, limit: usize) -> Result<ResultPage> {
+        let entry = self
+            .results
+            .lock()
+            .unwrap()
+            .get(result_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Result is no longer open"))?;
+
+        let mut result = entry.lock().await;
+        result.last_used = Instant::now();
+        let page = result.reader.next_page(limit).await;
+        drop(result);
+        drop(entry);
+
+        match page {
+            Ok(page) if page.has_more => Ok(page),
+            Ok(page) => {
+                self.close(result_id).await?;
+                Ok(page)
+            }
+            Err(e) => {
+                self.results.lock().unwrap().remove(result_id);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Closes an open result and returns its client to the pool. Returns
+    /// false if it was already closed.
+    pub async fn close(&self, result_id: &str) -> Result<bool> {
+        let Some(entry) = self.results.lock().unwrap().remove(result_id) else {
+            return Ok(false);
+        };
+        close_entry(entry).await?;
+        Ok(true)
+    }
+
+    /// Closes results left unread for `RESULT_IDLE_TIMEOUT`. Runs every
+    /// `IDLE_SWEEP_INTERVAL` and whenever a result is kept open.
+    pub fn close_idle(&self) {
+        let mut results = self.results.lock().unwrap();
+        let idle: Vec<String> = results
+            .iter()
+            .filter(|(_, entry)| {
+                // A locked entry is being fetched from right now
+                entry
+                    .try_lock()
+                    .is_ok_and(|r| r.last_used.elapsed() >= RESULT_IDLE_TIMEOUT)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for id in idle {
+            if let Some(entry) = results.remove(&id) {
+                tokio::spawn(async move {
+                    if let Err(e) = close_entry(entry).await {
+                        eprintln!("failed to close idle result: {:#}", e);
+                    }
+                });
+            }
+        }
+    }
+}
+
+async fn close_entry(entry: Arc<tokio::sync::Mutex<OpenResult>>) -> Result<()> {
+    // Locking waits out a fetch that is still in flight
+    let mut result = entry.lock().await;
+    let OpenResult { client, reader, .. } = &mut *result;
+    reader.close(client).await
+}
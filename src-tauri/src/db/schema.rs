@@ -1,17 +1,106 @@
-use crate::models::{ColumnInfo, TableSchema};
-use anyhow::Result;
+use crate::db::editing::row_identity;
+use crate::models::{
+    ColumnInfo, ConstraintInfo, ConstraintKind, IdentityKind, IndexInfo, RelationKind,
+    TableDetails, TableInfo, TableSchema, TriggerInfo,
+};
+use anyhow::{bail, Result};
 use tokio_postgres::Client;
 
+/// Lists relations of the given kinds, in one schema or in all user schemas.
+pub async fn list_relations(
+    client: &Client,
+    schema: Option<&str>,
+    kinds: &[RelationKind],
+) -> Result<Vec<TableInfo>> {
+    let query = r#"
+        SELECT
+            c.relname AS name,
+            n.nspname AS schema,
+            c.relkind::text AS relkind,
+            pg_get_userbyid(c.relowner) AS owner,
+            CASE WHEN c.relkind IN ('r', 'p', 'm', 'f') AND c.reltuples >= 0
+                THEN c.reltuples::bigint
+            END AS estimated_rows,
+            obj_description(c.oid, 'pg_class') AS comment,
+            c.relispartition AS is_partition
+        FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE c.relkind::text = ANY($2)
+          AND ($1::text IS NULL OR n.nspname = $1)
+          AND n.nspname NOT IN ('information_schema', 'pg_catalog', 'pg_toast')
+          AND n.nspname NOT LIKE 'pg_temp_%'
+          AND n.nspname NOT LIKE 'pg_toast_temp_%'
+        ORDER BY n.nspname, c.relname
+    "#;
+
+    let relkinds: Vec<&str> = kinds.iter().map(|kind| kind.relkind()).collect();
+    let rows = client.query(query, &[&schema, &relkinds]).await?;
+
+    rows.iter()
+        .map(|row| {
+            let relkind: &str = row.get("relkind");
+            let kind = match RelationKind::from_relkind(relkind) {
+                Some(kind) => kind,
+                None => bail!("Unexpected relation kind {}", relkind),
+            };
+            Ok(TableInfo {
+                name: row.get("name"),
+                schema: row.get("schema"),
+                kind,
+                owner: row.get("owner"),
+                estimated_rows: row.get("estimated_rows"),
+                comment: row.get("comment"),
+                is_partition: row.get("is_partition"),
+            })
+        })
+        .collect()
+}
+
 pub async fn get_table_schema(client: &Client, schema: &str, table: &str) -> Result<TableSchema> {
+    // Read from the catalogs rather than information_schema.columns, which
+    // leaves out materialized views and columns the user has no privileges on
     let query = r#"
-        SELECT 
-            column_name,
-            data_type,
-            is_nullable,
-            column_default
-        FROM information_schema.columns
-        WHERE table_schema = $1 AND table_name = $2
-        ORDER BY ordinal_position
+        SELECT
+            a.attname AS column_name,
+            CASE
+                WHEN bt.typelem <> 0 AND bt.typlen = -1 THEN 'ARRAY'
+                WHEN bt.typnamespace = 'pg_catalog'::regnamespace THEN format_type(bt.oid, NULL)
+                ELSE 'USER-DEFINED'
+            END AS data_type,
+            format_type(a.atttypid, a.atttypmod) AS formatted_type,
+            a.atttypid AS type_oid,
+            information_schema._pg_char_max_length(bt.oid, information_schema._pg_truetypmod(a, t))::int AS max_length,
+            information_schema._pg_numeric_precision(bt.oid, information_schema._pg_truetypmod(a, t))::int AS numeric_precision,
+            information_schema._pg_numeric_scale(bt.oid, information_schema._pg_truetypmod(a, t))::int AS numeric_scale,
+            NOT (a.attnotnull OR (t.typtype = 'd' AND t.typnotnull)) AS is_nullable,
+            CASE WHEN a.attgenerated = '' THEN pg_get_expr(d.adbin, d.adrelid) END AS column_default,
+            CASE a.attidentity WHEN 'a' THEN 'ALWAYS' WHEN 'd' THEN 'BY DEFAULT' END AS identity_generation,
+            CASE WHEN a.attgenerated <> '' THEN pg_get_expr(d.adbin, d.adrelid) END AS generation_expression,
+            CASE WHEN a.attcollation <> t.typcollation THEN co.collname::text END AS collation,
+            col_description(a.attrelid, a.attnum) AS comment,
+            EXISTS (
+                SELECT 1 FROM pg_constraint k
+                WHERE k.conrelid = a.attrelid AND k.contype = 'p' AND a.attnum = ANY(k.conkey)
+            ) AS is_primary_key,
+            EXISTS (
+                SELECT 1 FROM pg_constraint k
+                WHERE k.conrelid = a.attrelid AND k.contype = 'u' AND a.attnum = ANY(k.conkey)
+            ) AS is_unique,
+            EXISTS (
+                SELECT 1 FROM pg_constraint k
+                WHERE k.conrelid = a.attrelid AND k.contype = 'f' AND a.attnum = ANY(k.conkey)
+            ) AS is_foreign_key
+        FROM pg_attribute a
+        JOIN pg_class r ON r.oid = a.attrelid
+        JOIN pg_namespace n ON n.oid = r.relnamespace
+        JOIN pg_type t ON t.oid = a.atttypid
+        JOIN pg_type bt ON bt.oid = information_schema._pg_truetypid(a, t)
+        LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+        LEFT JOIN pg_collation co ON co.oid = a.attcollation
+        WHERE n.nspname = $1 AND r.relname = $2
+          AND r.relkind IN ('r', 'v', 'm', 'f', 'p')
+          AND a.attnum > 0 AND NOT a.attisdropped
+        ORDER BY a.attnum
     "#;
 
     let rows = client.query(query, &[&schema, &table]).await?;
@@ -21,13 +110,174 @@ pub async fn get_table_schema(client: &Client, schema: &str, table: &str) -> Res
         .map(|row| ColumnInfo {
             name: row.get("column_name"),
             data_type: row.get("data_type"),
-            is_nullable: row.get::<_, String>("is_nullable") == "YES",
+            formatted_type: row.get("formatted_type"),
+            type_oid: row.get("type_oid"),
+            max_length: row.get("max_length"),
+            numeric_precision: row.get("numeric_precision"),
+            numeric_scale: row.get("numeric_scale"),
+            is_nullable: row.get("is_nullable"),
             default_value: row.get("column_default"),
+            identity: match row.get::<_, Option<&str>>("identity_generation") {
+                Some("ALWAYS") => Some(IdentityKind::Always),
+                Some("BY DEFAULT") => Some(IdentityKind::ByDefault),
+                _ => None,
+            },
+            generation_expression: row.get("generation_expression"),
+            collation: row.get("collation"),
+            comment: row.get("comment"),
+            is_primary_key: row.get("is_primary_key"),
+            is_unique: row.get("is_unique"),
+            is_foreign_key: row.get("is_foreign_key"),
         })
         .collect();
 
     Ok(TableSchema {
         name: format!("{}.{}", schema, table),
         columns,
+        row_identity: row_identity(client, schema, table).await?,
+    })
+}
+
+pub async fn get_table_details(client: &Client, schema: &str, table: &str) -> Result<TableDetails> {
+    Ok(TableDetails {
+        schema: get_table_schema(client, schema, table).await?,
+        indexes: get_indexes(client, schema, table).await?,
+        constraints: get_constraints(client, schema, table).await?,
+        triggers: get_triggers(client, schema, table).await?,
     })
 }
+
+async fn get_indexes(client: &Client, schema: &str, table: &str) -> Result<Vec<IndexInfo>> {
+    let query = r#"
+        SELECT
+            ic.relname AS name,
+            am.amname AS method,
+            ARRAY(
+                SELECT pg_get_indexdef(i.indexrelid, k, true)
+                FROM generate_series(1, i.indnkeyatts) AS k
+                ORDER BY k
+            ) AS columns,
+            i.indisprimary AS is_primary,
+            i.indisunique AS is_unique,
+            i.indpred IS NOT NULL AS is_partial,
+            i.indisvalid AS is_valid,
+            con.conname AS constraint_name,
+            pg_get_indexdef(i.indexrelid) AS definition,
+            pg_relation_size(i.indexrelid) AS size_bytes
+        FROM pg_index i
+        JOIN pg_class c ON c.oid = i.indrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_class ic ON ic.oid = i.indexrelid
+        JOIN pg_am am ON am.oid = ic.relam
+        LEFT JOIN pg_constraint con ON con.conindid = i.indexrelid AND con.conrelid = i.indrelid
+        WHERE n.nspname = $1 AND c.relname = $2
+        ORDER BY i.indisprimary DESC, ic.relname
+    "#;
+
+    let rows = client.query(query, &[&schema, &table]).await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| IndexInfo {
+            name: row.get("name"),
+            method: row.get("method"),
+            columns: row.get("columns"),
+            is_primary: row.get("is_primary"),
+            is_unique: row.get("is_unique"),
+            is_partial: row.get("is_partial"),
+            is_valid: row.get("is_valid"),
+            constraint: row.get("constraint_name"),
+            definition: row.get("definition"),
+            size_bytes: row.get("size_bytes"),
+        })
+        .collect())
+}
+
+async fn get_constraints(
+    client: &Client,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<ConstraintInfo>> {
+    let query = r#"
+        SELECT
+            con.conname AS name,
+            con.contype::text AS kind,
+            ARRAY(
+                SELECT a.attname::text
+                FROM unnest(con.conkey) WITH ORDINALITY AS k(attnum, ord)
+                JOIN pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum
+                ORDER BY k.ord
+            ) AS columns,
+            pg_get_constraintdef(con.oid, true) AS definition,
+            con.convalidated AS is_validated,
+            con.condeferrable AS is_deferrable,
+            con.condeferred AS is_initially_deferred,
+            CASE WHEN con.contype = 'f' THEN con.confrelid::regclass::text END AS referenced_table,
+            CASE WHEN con.contype IN ('p', 'u', 'x') AND con.conindid <> 0
+                THEN pg_relation_size(con.conindid)
+            END AS size_bytes
+        FROM pg_constraint con
+        JOIN pg_class c ON c.oid = con.conrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = $1 AND c.relname = $2
+          AND con.contype IN ('p', 'u', 'f', 'c', 'x', 't')
+        ORDER BY array_position(ARRAY['p', 'u', 'f', 'c', 'x', 't'], con.contype::text), con.conname
+    "#;
+
+    let rows = client.query(query, &[&schema, &table]).await?;
+
+    rows.iter()
+        .map(|row| {
+            let kind = match row.get::<_, &str>("kind") {
+                "p" => ConstraintKind::PrimaryKey,
+                "u" => ConstraintKind::Unique,
+                "f" => ConstraintKind::ForeignKey,
+                "c" => ConstraintKind::Check,
+                "x" => ConstraintKind::Exclusion,
+                "t" => ConstraintKind::Trigger,
+                other => bail!("Unknown constraint type {}", other),
+            };
+            Ok(ConstraintInfo {
+                name: row.get("name"),
+                kind,
+                columns: row.get("columns"),
+                definition: row.get("definition"),
+                is_validated: row.get("is_validated"),
+                is_deferrable: row.get("is_deferrable"),
+                is_initially_deferred: row.get("is_initially_deferred"),
+                referenced_table: row.get("referenced_table"),
+                size_bytes: row.get("size_bytes"),
+            })
+        })
+        .collect()
+}
+
+/// User-defined triggers; the internal ones backing foreign keys are left out.
+async fn get_triggers(client: &Client, schema: &str, table: &str) -> Result<Vec<TriggerInfo>> {
+    let query = r#"
+        SELECT
+            t.tgname AS name,
+            t.tgfoid::regprocedure::text AS function,
+            t.tgenabled <> 'D' AS is_enabled,
+            t.tgconstraint <> 0 AS is_constraint,
+            pg_get_triggerdef(t.oid, true) AS definition
+        FROM pg_trigger t
+        JOIN pg_class c ON c.oid = t.tgrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = $1 AND c.relname = $2 AND NOT t.tgisinternal
+        ORDER BY t.tgname
+    "#;
+
+    let rows = client.query(query, &[&schema, &table]).await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| TriggerInfo {
+            name: row.get("name"),
+            function: row.get("function"),
+            is_enabled: row.get("is_enabled"),
+            is_constraint: row.get("is_constraint"),
+            definition: row.get("definition"),
+        })
+        .collect())
+}
@@ -0,0 +1,659 @@
+use crate::db::session::transaction_status;
+use crate::models::TransactionStatus;
+use tokio_postgres::Client;
+
+/// One statement of a script.
+#[derive(Debug, Clone)]
+pub struct ScriptStatement {
+    pub sql: String,
+    /// 1-based line the statement starts on, for pointing at it in the editor.
+    pub line: usize,
+    /// Characters before the statement in the script, to map server-reported
+    /// error positions back onto the script.
+    pub offset: usize,
+}
+
+/// Splits a script into statements on top-level semicolons. Semicolons inside
+/// string literals, quoted identifiers, dollar-quoted bodies, comments,
+/// parentheses and SQL-standard `BEGIN ATOMIC ... END` function bodies don't
+/// end a statement. Leading comments are dropped and empty statements
+/// skipped.
+pub fn split_statements(script: &str) -> Vec<ScriptStatement> {
+    let mut statements = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut end = 0;
+    let mut parens = 0i32;
+    let mut atomic = AtomicBodyTracker::default();
+
+    for (pos, token_end, token) in Lexer::new(script) {
+        match token {
+            Token::Semicolon if parens <= 0 && atomic.depth == 0 => {
+                if let Some(start) = start.take() {
+                    statements.push(statement(script, start, end));
+                }
+                parens = 0;
+                atomic = AtomicBodyTracker::default();
+                continue;
+            }
+            Token::Open => parens += 1,
+            Token::Close => parens -= 1,
+            Token::Word(word) => atomic.word(word),
+            Token::Semicolon | Token::Other => {}
+        }
+        start.get_or_insert(pos);
+        end = token_end;
+    }
+
+    if let Some(start) = start {
+        statements.push(statement(script, start, end));
+    }
+    statements
+}
+
+fn statement(script: &str, start: usize, end: usize) -> ScriptStatement {
+    ScriptStatement {
+        sql: script[start..end].to_string(),
+        line: script[..start].matches('\n').count() + 1,
+        offset: script[..start].chars().count(),
+    }
+}
+
+/// A statement whose `:name` placeholders were replaced by `$1`, `$2`, ...
+#[derive(Debug)]
+pub struct NamedStatement {
+    pub sql: String,
+    /// Parameter names in `$n` order; a name used twice is one parameter.
+    pub names: Vec<String>,
+    /// Character offset in `sql`, new length and old length of each
+    /// replacement, in order.
+    replacements: Vec<(usize, usize, usize)>,
+}
+
+impl NamedStatement {
+    /// Maps a 1-based character position in `sql` back onto the statement
+    /// as written. Positions inside a placeholder map to its start.
+    pub fn original_position(&self, position: u32) -> u32 {
+        let offset = position as usize - 1;
+        let mut shift = 0i64;
+        for &(start, new_len, old_len) in &self.replacements {
+            if offset < start {
+                break;
+            }
+            if offset < start + new_len {
+                return (start as i64 - shift) as u32 + 1;
+            }
+            shift += new_len as i64 - old_len as i64;
+        }
+        (position as i64 - shift) as u32
+    }
+}
+
+/// Rewrites psql-style `:name` placeholders outside literals, quoted
+/// identifiers and comments into numbered parameters. `::` casts and array
+/// slices such as `a[lo:hi]` or `a[:hi]` are left alone; the first colon
+/// directly inside a subscript is always taken as the slice's, so write
+/// `a[(:i)]` to subscript with a parameter. `ARRAY[:a, :b]` is a
+/// constructor, not a subscript, and binds both.
+pub fn named_parameters(sql: &str) -> NamedStatement {
+    let bytes = sql.as_bytes();
+    let mut rewritten = String::with_capacity(sql.len());
+    let mut names: Vec<String> = Vec::new();
+    let mut replacements = Vec::new();
+    let mut copied = 0;
+    let mut chars = 0;
+    // Open brackets and parentheses, innermost last; for a subscript, whether
+    // its slice colon has been seen
+    let mut nesting: Vec<Option<bool>> = Vec::new();
+    // Whether the previous token ends an expression, making a `[` after it
+    // a subscript rather than part of an array constructor
+    let mut after_expression = false;
+
+    let mut tokens = Lexer::new(sql).peekable();
+    while let Some((pos, end, token)) = tokens.next() {
+        match (token, bytes[pos]) {
+            (Token::Open, _) => nesting.push(None),
+            (Token::Other, b'[') => nesting.push(after_expression.then_some(false)),
+            (Token::Close, _) | (Token::Other, b']') => {
+                nesting.pop();
+            }
+            _ => {}
+        }
+        after_expression = match token {
+            Token::Word(word) => !word.eq_ignore_ascii_case("ARRAY"),
+            Token::Close => true,
+            Token::Other => matches!(bytes[end - 1], b']' | b'"') || is_ident_byte(bytes[end - 1]),
+            Token::Open | Token::Semicolon => false,
+        };
+        let is_colon = bytes[pos] == b':' && end == pos + 1;
+        if is_colon {
+            if let Some(slice_colon @ Some(false)) = nesting.last_mut() {
+                *slice_colon = Some(true);
+                continue;
+            }
+        }
+        let is_placeholder =
+            is_colon && (pos == 0 || !(bytes[pos - 1] == b':' || is_ident_byte(bytes[pos - 1])));
+        let Some(&(name_start, name_end, Token::Word(name))) = tokens.peek() else {
+            continue;
+        };
+        if !is_placeholder || name_start != end {
+            continue;
+        }
+        tokens.next();
+
+        let index = match names.iter().position(|known| known == name) {
+            Some(index) => index,
+            None => {
+                names.push(name.to_string());
+                names.len() - 1
+            }
+        };
+        let before = &sql[copied..pos];
+        rewritten.push_str(before);
+        chars += before.chars().count();
+        let parameter = format!("${}", index + 1);
+        replacements.push((chars, parameter.len(), sql[pos..name_end].chars().count()));
+        chars += parameter.len();
+        rewritten.push_str(&parameter);
+        copied = name_end;
+    }
+    rewritten.push_str(&sql[copied..]);
+
+    NamedStatement {
+        sql: rewritten,
+        names,
+        replacements,
+    }
+}
+
+/// Keywords outside parentheses, literals and comments, uppercased.
+fn top_level_words(sql: &str) -> Vec<String> {
+    let mut depth = 0i32;
+    let mut words = Vec::new();
+    for (_, _, token) in Lexer::new(sql) {
+        match token {
+            Token::Open => depth += 1,
+            Token::Close => depth -= 1,
+            Token::Word(word) if depth == 0 => words.push(word.to_ascii_uppercase()),
+            _ => {}
+        }
+    }
+    words
+}
+
+/// Command tag in PostgreSQL's format, e.g. `INSERT 0 3`, `UPDATE 2` or
+/// `CREATE TABLE`. The driver only reports affected-row counts, so the
+/// command is derived from the statement's leading keywords.
+pub fn command_tag(sql: &str, rows_affected: Option<u64>) -> String {
+    let words = top_level_words(sql);
+    let Some(first) = words.first() else {
+        return String::new();
+    };
+    let count = |tag: &str| match rows_affected {
+        Some(n) => format!("{} {}", tag, n),
+        None => tag.to_string(),
+    };
+
+    match first.as_str() {
+        "WITH" => {
+            let main = words[1..].iter().find(|w| {
+                matches!(
+                    w.as_str(),
+                    "SELECT" | "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "VALUES" | "TABLE"
+                )
+            });
+            match main.map(String::as_str) {
+                Some("INSERT") => count("INSERT 0"),
+                Some(verb @ ("UPDATE" | "DELETE" | "MERGE")) => count(verb),
+                _ => count("SELECT"),
+            }
+        }
+        "SELECT" | "VALUES" | "TABLE" => count("SELECT"),
+        "INSERT" => count("INSERT 0"),
+        "UPDATE" | "DELETE" | "MERGE" | "FETCH" | "MOVE" | "COPY" => count(first),
+        "CREATE" | "DROP" | "ALTER" => format!("{} {}", first, object_kind(&words[1..])),
+        "START" => "START TRANSACTION".to_string(),
+        "END" => "COMMIT".to_string(),
+        "ABORT" => "ROLLBACK".to_string(),
+        "TRUNCATE" => "TRUNCATE TABLE".to_string(),
+        "LOCK" => "LOCK TABLE".to_string(),
+        "PREPARE" if words.get(1).is_some_and(|w| w == "TRANSACTION") => {
+            "PREPARE TRANSACTION".to_string()
+        }
+        _ => first.clone(),
+    }
+}
+
+/// The object kind of a `CREATE`/`DROP`/`ALTER`, e.g. `TABLE` or
+/// `MATERIALIZED VIEW`.
+fn object_kind(words: &[String]) -> String {
+    const MODIFIERS: &[&str] = &[
+        "OR", "REPLACE", "TEMP", "TEMPORARY", "UNLOGGED", "GLOBAL", "LOCAL", "UNIQUE", "TRUSTED",
+        "PROCEDURAL", "RECURSIVE", "CONSTRAINT", "DEFAULT",
+    ];
+    let mut words = words
+        .iter()
+        .map(String::as_str)
+        .skip_while(|w| MODIFIERS.contains(w));
+
+    let Some(kind) = words.next() else {
+        return String::new();
+    };
+    let extra = match kind {
+        "MATERIALIZED" | "EVENT" | "OPERATOR" | "USER" | "ACCESS" => 1,
+        "FOREIGN" => match words.clone().next() {
+            Some("DATA") => 2,
+            _ => 1,
+        },
+        "TEXT" => 2,
+        _ => 0,
+    };
+    std::iter::once(kind)
+        .chain(words.take(extra))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Whether a statement starts, ends or otherwise controls a transaction.
+pub fn is_transaction_control(sql: &str) -> bool {
+    let words = top_level_words(sql);
+    match words.first().map(String::as_str) {
+        Some("BEGIN" | "START" | "COMMIT" | "END" | "ROLLBACK" | "ABORT" | "SAVEPOINT" | "RELEASE") => {
+            true
+        }
+        Some("PREPARE") => words.get(1).is_some_and(|w| w == "TRANSACTION"),
+        _ => false,
+    }
+}
+
+/// Whether a statement only reads, judging by its keywords: a query or
+/// `SHOW` that mentions no `INSERT`, `UPDATE`, `DELETE`, `MERGE` or `INTO`
+/// anywhere, so data-modifying `WITH` parts and `SELECT INTO` don't count.
+/// Functions it calls may still write.
+pub fn is_read_only(sql: &str) -> bool {
+    let mut words = Lexer::new(sql).filter_map(|(_, _, token)| match token {
+        Token::Word(word) => Some(word.to_ascii_uppercase()),
+        _ => None,
+    });
+    matches!(
+        words.next().as_deref(),
+        Some("SELECT" | "VALUES" | "TABLE" | "WITH" | "SHOW")
+    ) && !words.any(|word| matches!(word.as_str(), "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "INTO"))
+}
+
+/// Rolls back a transaction a script left open, so the client doesn't go
+/// back to the pool mid-transaction. Returns whether one was open.
+pub async fn rollback_open_transaction(client: &Client) -> Result<bool, tokio_postgres::Error> {
+    let open = !matches!(transaction_status(client).await, Ok(TransactionStatus::Idle));
+    if open {
+        client.batch_execute("ROLLBACK").await?;
+    }
+    Ok(open)
+}
+
+/// Tracks SQL-standard function bodies (`CREATE FUNCTION ... BEGIN ATOMIC
+/// ... END`), whose statements end in semicolons of their own. Follows the
+/// same rule as psql: inside `CREATE FUNCTION`/`CREATE PROCEDURE`, `BEGIN`
+/// and `CASE` open a block and `END` closes one.
+#[derive(Default)]
+struct AtomicBodyTracker {
+    words: usize,
+    create: bool,
+    routine: bool,
+    depth: usize,
+}
+
+impl AtomicBodyTracker {
+    fn word(&mut self, word: &str) {
+        self.words += 1;
+        if self.words == 1 {
+            self.create = word.eq_ignore_ascii_case("create");
+            return;
+        }
+        if !self.create {
+            return;
+        }
+        if word.eq_ignore_ascii_case("function") || word.eq_ignore_ascii_case("procedure") {
+            self.routine = true;
+        } else if (self.routine && word.eq_ignore_ascii_case("begin"))
+            || (self.depth > 0 && word.eq_ignore_ascii_case("case"))
+        {
+            self.depth += 1;
+        } else if self.depth > 0 && word.eq_ignore_ascii_case("end") {
+            self.depth -= 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Word(&'a str),
+    Semicolon,
+    Open,
+    Close,
+    /// Literals, quoted identifiers, operators and anything else that
+    /// doesn't affect statement boundaries.
+    Other,
+}
+
+/// Minimal SQL lexer: just enough to know where literals, comments and
+/// quoted bodies start and end. Comments are skipped entirely. All delimiters
+/// are ASCII, so scanning bytes is safe for UTF-8 input.
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn byte(&self, pos: usize) -> Option<u8> {
+        self.src.as_bytes().get(pos).copied()
+    }
+
+    /// End of the literal, quoted identifier or single character starting at
+    /// `pos`.
+    fn skip_token(&self, pos: usize) -> usize {
+        let bytes = self.src.as_bytes();
+        match bytes[pos] {
+            b'\'' => {
+                // E'...' strings allow backslash escapes
+                let escapes = pos > 0
+                    && matches!(bytes[pos - 1], b'e' | b'E')
+                    && (pos < 2 || !is_ident_byte(bytes[pos - 2]));
+                skip_quoted(bytes, pos, b'\'', escapes)
+            }
+            b'"' => skip_quoted(bytes, pos, b'"', false),
+            b'$' => match dollar_tag(bytes, pos) {
+                Some(tag) => {
+                    let body = pos + tag.len();
+                    match self.src[body..].find(tag) {
+                        Some(close) => body + close + tag.len(),
+                        None => bytes.len(),
+                    }
+                }
+                None => pos + 1,
+            },
+            _ => pos + self.src[pos..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    /// Start and end offsets of each token.
+    type Item = (usize, usize, Token<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.src.as_bytes();
+        loop {
+            let pos = self.pos;
+            let b = self.byte(pos)?;
+            let token = match b {
+                b if b.is_ascii_whitespace() => {
+                    self.pos += 1;
+                    continue;
+                }
+                b'-' if self.byte(pos + 1) == Some(b'-') => {
+                    self.pos = self.src[pos..].find('\n').map_or(bytes.len(), |n| pos + n + 1);
+                    continue;
+                }
+                b'/' if self.byte(pos + 1) == Some(b'*') => {
+                    self.pos = skip_block_comment(bytes, pos);
+                    continue;
+                }
+                b';' => {
+                    self.pos += 1;
+                    Token::Semicolon
+                }
+                b'(' => {
+                    self.pos += 1;
+                    Token::Open
+                }
+                b')' => {
+                    self.pos += 1;
+                    Token::Close
+                }
+                b if is_ident_start(b) => {
+                    let end = bytes[pos..]
+                        .iter()
+                        .position(|&b| !is_ident_byte(b))
+                        .map_or(bytes.len(), |n| pos + n);
+                    self.pos = end;
+                    Token::Word(&self.src[pos..end])
+                }
+                _ => {
+                    self.pos = self.skip_token(pos);
+                    Token::Other
+                }
+            };
+            return Some((pos, self.pos, token));
+        }
+    }
+}
+
+fn is_ident_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b >= 0x80
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    is_ident_start(b) || b.is_ascii_digit() || b == b'$'
+}
+
+/// Returns the end of a quoted string starting at `pos`. A doubled quote
+/// stands for itself; an unterminated string runs to the end of input.
+fn skip_quoted(bytes: &[u8], pos: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut i = pos + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if backslash_escapes => i += 2,
+            b if b == quote => {
+                if bytes.get(i + 1) == Some(&quote) {
+                    i += 2;
+                } else {
+                    return i + 1;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Block comments nest in PostgreSQL.
+fn skip_block_comment(bytes: &[u8], pos: usize) -> usize {
+    let mut depth = 0;
+    let mut i = pos;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// The `$tag$` opening a dollar-quoted string at `pos`, if there is one.
+/// `$1` and friends are parameters, not quotes.
+fn dollar_tag(bytes: &[u8], pos: usize) -> Option<&str> {
+    let rest = &bytes[pos + 1..];
+    let len = rest.iter().position(|&b| b == b'$')?;
+    let tag = &rest[..len];
+    let valid = tag.first().is_none_or(|&b| is_ident_start(b))
+        && tag.iter().all(|&b| is_ident_start(b) || b.is_ascii_digit());
+    if !valid {
+        return None;
+    }
+    std::str::from_utf8(&bytes[pos..pos + len + 2]).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sql(script: &str) -> Vec<String> {
+        split_statements(script)
+            .into_iter()
+            .map(|s| s.sql)
+            .collect()
+    }
+
+    #[test]
+    fn splits_on_top_level_semicolons() {
+        let statements = split_statements("SELECT 1;\n\n  SELECT 2;;\nSELECT 3");
+        let found: Vec<_> = statements
+            .iter()
+            .map(|s| (s.sql.as_str(), s.line, s.offset))
+            .collect();
+        assert_eq!(
+            found,
+            [("SELECT 1", 1, 0), ("SELECT 2", 3, 13), ("SELECT 3", 4, 24)]
+        );
+    }
+
+    #[test]
+    fn keeps_dollar_quoted_bodies_together() {
+        let script = "CREATE FUNCTION f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql;\n\
+                      DO $body$ BEGIN PERFORM 1; $x$ ; $x$; END $body$;";
+        assert_eq!(
+            sql(script),
+            [
+                "CREATE FUNCTION f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql",
+                "DO $body$ BEGIN PERFORM 1; $x$ ; $x$; END $body$",
+            ]
+        );
+    }
+
+    #[test]
+    fn positional_parameters_are_not_dollar_quotes() {
+        assert_eq!(sql("SELECT $1; SELECT $2"), ["SELECT $1", "SELECT $2"]);
+    }
+
+    #[test]
+    fn skips_nested_block_comments() {
+        assert_eq!(
+            sql("/* one /* two; */ still; */ SELECT 1; -- trailing; comment\nSELECT 2"),
+            ["SELECT 1", "SELECT 2"]
+        );
+    }
+
+    #[test]
+    fn handles_escape_strings_and_quoted_identifiers() {
+        assert_eq!(
+            sql(r#"SELECT E'it\'s; fine'; SELECT 'a''b;'; SELECT "x;y" FROM t"#),
+            [
+                r"SELECT E'it\'s; fine'",
+                "SELECT 'a''b;'",
+                r#"SELECT "x;y" FROM t"#
+            ]
+        );
+        // Outside E'' a backslash is an ordinary character
+        assert_eq!(sql(r"SELECT 'a\'; SELECT 2"), [r"SELECT 'a\'", "SELECT 2"]);
+    }
+
+    #[test]
+    fn keeps_atomic_function_bodies_together() {
+        let script = "CREATE FUNCTION f() RETURNS int LANGUAGE sql BEGIN ATOMIC SELECT 1; SELECT 2; END; SELECT 3";
+        assert_eq!(
+            sql(script),
+            [
+                "CREATE FUNCTION f() RETURNS int LANGUAGE sql BEGIN ATOMIC SELECT 1; SELECT 2; END",
+                "SELECT 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn derives_command_tags() {
+        assert_eq!(command_tag("select 1", Some(1)), "SELECT 1");
+        assert_eq!(
+            command_tag("INSERT INTO t VALUES (1), (2)", Some(2)),
+            "INSERT 0 2"
+        );
+        assert_eq!(
+            command_tag("WITH d AS (SELECT 1) UPDATE t SET a = 1", Some(4)),
+            "UPDATE 4"
+        );
+        assert_eq!(
+            command_tag("create or replace view v as select 1", None),
+            "CREATE VIEW"
+        );
+        assert_eq!(
+            command_tag("DROP MATERIALIZED VIEW m", None),
+            "DROP MATERIALIZED VIEW"
+        );
+        assert_eq!(command_tag("end", None), "COMMIT");
+        assert_eq!(command_tag("-- comment\nVACUUM", None), "VACUUM");
+        assert_eq!(command_tag("", None), "");
+    }
+
+    #[test]
+    fn detects_transaction_control() {
+        assert!(is_transaction_control("begin"));
+        assert!(is_transaction_control("PREPARE TRANSACTION 'x'"));
+        assert!(!is_transaction_control("PREPARE q AS SELECT 1"));
+        assert!(!is_transaction_control("SELECT 'BEGIN'"));
+    }
+
+    #[test]
+    fn detects_read_only_statements() {
+        assert!(is_read_only("select * from t where note = 'delete me'"));
+        assert!(is_read_only("WITH d AS (SELECT 1) SELECT * FROM d"));
+        assert!(is_read_only("SHOW search_path"));
+        assert!(!is_read_only("WITH d AS (DELETE FROM t RETURNING *) SELECT * FROM d"));
+        assert!(!is_read_only("SELECT * INTO copy FROM t"));
+        assert!(!is_read_only("INSERT INTO t VALUES (1) RETURNING *"));
+        assert!(!is_read_only("-- SELECT\nUPDATE t SET a = 1 RETURNING a"));
+    }
+
+    #[test]
+    fn rewrites_named_parameters() {
+        let named = named_parameters("SELECT :a, :b, :a WHERE x = ':c' AND \"y:d\" = 1 -- :e");
+        assert_eq!(
+            named.sql,
+            "SELECT $1, $2, $1 WHERE x = ':c' AND \"y:d\" = 1 -- :e"
+        );
+        assert_eq!(named.names, ["a", "b"]);
+    }
+
+    #[test]
+    fn leaves_casts_and_array_slices_alone() {
+        let named = named_parameters("SELECT :v::int, x::text, a[1:2], a[lo:hi], a[:n], a[(:i)]");
+        assert_eq!(
+            named.sql,
+            "SELECT $1::int, x::text, a[1:2], a[lo:hi], a[:n], a[($2)]"
+        );
+        assert_eq!(named.names, ["v", "i"]);
+    }
+
+    #[test]
+    fn binds_parameters_in_array_constructors() {
+        let named = named_parameters("SELECT ARRAY[:a, :b], array[:c][:d], (ARRAY[:e])[:f]");
+        assert_eq!(named.sql, "SELECT ARRAY[$1, $2], array[$3][:d], (ARRAY[$4])[:f]");
+        assert_eq!(named.names, ["a", "b", "c", "e"]);
+    }
+
+    #[test]
+    fn maps_positions_back_to_the_original() {
+        let named = named_parameters("SELECT :long_name + :b FROM t");
+        assert_eq!(named.sql, "SELECT $1 + $2 FROM t");
+        // Before the first placeholder
+        assert_eq!(named.original_position(1), 1);
+        // Inside a placeholder: its start
+        assert_eq!(named.original_position(9), 8);
+        // Between placeholders and after both
+        assert_eq!(named.original_position(11), 19);
+        assert_eq!(named.original_position(16), 24);
+    }
+}
@@ -0,0 +1,290 @@
+use crate::db::pool::PooledClient;
+use crate::db::script::is_transaction_control;
+use crate::db::sql::quote_identifier;
+use crate::error::AppError;
+use crate::models::{SessionInfo, TransactionStatus};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OwnedMutexGuard;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::{Client, SimpleQueryMessage};
+
+/// A client pinned to one editor tab, so a transaction can span several
+/// `execute_query` calls. With auto-commit off, a transaction is opened
+/// before the first statement and stays open until committed or rolled back.
+pub struct Session {
+    connection_id: String,
+    client: PooledClient,
+    status: TransactionStatus,
+    auto_commit: bool,
+    // Savepoints set through `savepoint`, oldest first
+    savepoints: Vec<String>,
+}
+
+impl Session {
+    pub fn connection_id(&self) -> &str {
+        &self.connection_id
+    }
+
+    pub fn client(&self) -> &PooledClient {
+        &self.client
+    }
+
+    pub fn info(&self, session_id: &str) -> SessionInfo {
+        SessionInfo {
+            session_id: session_id.to_string(),
+            connection_id: self.connection_id.clone(),
+            status: self.status,
+            auto_commit: self.auto_commit,
+            savepoints: self.savepoints.clone(),
+        }
+    }
+
+    /// Opens the implicit transaction when auto-commit is off and none is
+    /// open. Statements that manage the transaction themselves are left alone.
+    pub async fn before_statement(&mut self, sql: &str) -> Result<(), AppError> {
+        if !self.auto_commit
+            && self.status == TransactionStatus::Idle
+            && !is_transaction_control(sql)
+        {
+            self.client.batch_execute("BEGIN").await?;
+            self.status = TransactionStatus::InTransaction;
+        }
+        Ok(())
+    }
+
+    /// Updates the status after a statement ran. Only failures and
+    /// transaction control statements can change it.
+    pub async fn after_statement(&mut self, sql: &str, succeeded: bool) {
+        if is_transaction_control(sql) {
+            self.refresh_status().await;
+        } else if !succeeded && self.status == TransactionStatus::InTransaction {
+            self.status = TransactionStatus::Failed;
+        }
+    }
+
+    pub async fn begin(&mut self) -> Result<(), AppError> {
+        if self.status != TransactionStatus::Idle {
+            return Err(AppError::validation("A transaction is already open"));
+        }
+        self.client.batch_execute("BEGIN").await?;
+        self.status = TransactionStatus::InTransaction;
+        Ok(())
+    }
+
+    /// Commits the open transaction. The server rolls a failed transaction
+    /// back instead.
+    pub async fn commit(&mut self) -> Result<(), AppError> {
+        self.end("COMMIT").await
+    }
+
+    pub async fn rollback(&mut self) -> Result<(), AppError> {
+        self.end("ROLLBACK").await
+    }
+
+    async fn end(&mut self, command: &str) -> Result<(), AppError> {
+        if self.status == TransactionStatus::Idle {
+            return Err(AppError::validation("No transaction is open"));
+        }
+        let result = self.client.batch_execute(command).await;
+        self.refresh_status().await;
+        result.map_err(AppError::from)
+    }
+
+    pub async fn savepoint(&mut self, name: &str) -> Result<(), AppError> {
+        match self.status {
+            TransactionStatus::InTransaction => {}
+            TransactionStatus::Idle if !self.auto_commit => {
+                self.client.batch_execute("BEGIN").await?;
+                self.status = TransactionStatus::InTransaction;
+            }
+            TransactionStatus::Idle => {
+                return Err(AppError::validation("Savepoints need an open transaction"))
+            }
+            TransactionStatus::Failed => {
+                return Err(AppError::validation(
+                    "The transaction has failed; roll back first",
+                ))
+            }
+        }
+        self.client
+            .batch_execute(&format!("SAVEPOINT {}", quote_identifier(name)))
+            .await?;
+        // Reusing a name shadows the older savepoint until it is released
+        self.savepoints.push(name.to_string());
+        Ok(())
+    }
+
+    /// Undoes everything since the savepoint was set, which also recovers a
+    /// failed transaction. The savepoint itself stays.
+    pub async fn rollback_to(&mut self, name: &str) -> Result<(), AppError> {
+        let index = self.savepoint_index(name)?;
+        self.client
+            .batch_execute(&format!("ROLLBACK TO SAVEPOINT {}", quote_identifier(name)))
+            .await?;
+        self.savepoints.truncate(index + 1);
+        self.status = TransactionStatus::InTransaction;
+        Ok(())
+    }
+
+    /// Forgets a savepoint and every one set after it, keeping their changes.
+    pub async fn release(&mut self, name: &str) -> Result<(), AppError> {
+        let index = self.savepoint_index(name)?;
+        self.client
+            .batch_execute(&format!("RELEASE SAVEPOINT {}", quote_identifier(name)))
+            .await?;
+        self.savepoints.truncate(index);
+        Ok(())
+    }
+
+    fn savepoint_index(&self, name: &str) -> Result<usize, AppError> {
+        self.savepoints
+            .iter()
+            .rposition(|s| s == name)
+            .ok_or_else(|| AppError::not_found(format!("Savepoint \"{}\" not found", name)))
+    }
+
+    /// Switching auto-commit on with a transaction open would leave it
+    /// dangling, so that has to be resolved first.
+    pub fn set_auto_commit(&mut self, enabled: bool) -> Result<(), AppError> {
+        if enabled && self.status != TransactionStatus::Idle {
+            return Err(AppError::validation(
+                "Commit or roll back the open transaction first",
+            ));
+        }
+        self.auto_commit = enabled;
+        Ok(())
+    }
+
+    async fn refresh_status(&mut self) {
+        self.status = match transaction_status(&self.client).await {
+            Ok(status) => status,
+            // The connection is gone, and the transaction with it
+            Err(_) if self.client.is_closed() => TransactionStatus::Idle,
+            Err(_) => TransactionStatus::Failed,
+        };
+        if self.status == TransactionStatus::Idle {
+            self.savepoints.clear();
+        }
+    }
+
+    /// Rolls back whatever is still open before the client is closed.
+    async fn close(&mut self) {
+        if self.status != TransactionStatus::Idle {
+            if let Err(e) = self.client.batch_execute("ROLLBACK").await {
+                eprintln!("failed to roll back session transaction: {}", e);
+            }
+            self.status = TransactionStatus::Idle;
+        }
+    }
+}
+
+/// Asks the server whether a transaction block is open on `client`.
+pub async fn transaction_status(client: &Client) -> Result<TransactionStatus, tokio_postgres::Error> {
+    // Outside a transaction block every statement runs in its own
+    // transaction, so the two timestamps only differ inside one. This has to
+    // be a simple query: the extended protocol's separate parse and execute
+    // steps can straddle the timestamps. A failed transaction rejects the
+    // check itself.
+    match client
+        .simple_query("SELECT now() <> statement_timestamp()")
+        .await
+    {
+        Ok(messages) => {
+            let open = messages.iter().any(|message| {
+                matches!(message, SimpleQueryMessage::Row(row) if row.get(0) == Some("t"))
+            });
+            Ok(if open {
+                TransactionStatus::InTransaction
+            } else {
+                TransactionStatus::Idle
+            })
+        }
+        Err(e) if e.code() == Some(&SqlState::IN_FAILED_SQL_TRANSACTION) => {
+            Ok(TransactionStatus::Failed)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+struct SessionEntry {
+    connection_id: String,
+    session: Arc<tokio::sync::Mutex<Session>>,
+}
+
+/// Open sessions, keyed by the caller-chosen session id (one per editor tab).
+/// Each one keeps its dedicated client open until it is closed.
+#[derive(Default)]
+pub struct Sessions {
+    sessions: Mutex<HashMap<String, SessionEntry>>,
+}
+
+impl Sessions {
+    /// Pins `client` to a new session. A session already open under the same
+    /// id is closed first.
+    pub async fn open(
+        &self,
+        session_id: &str,
+        connection_id: &str,
+        client: PooledClient,
+        auto_commit: bool,
+    ) -> SessionInfo {
+        self.close(session_id).await;
+
+        let session = Session {
+            connection_id: connection_id.to_string(),
+            client,
+            status: TransactionStatus::Idle,
+            auto_commit,
+            savepoints: Vec::new(),
+        };
+        let info = session.info(session_id);
+        self.sessions.lock().unwrap().insert(
+            session_id.to_string(),
+            SessionEntry {
+                connection_id: connection_id.to_string(),
+                session: Arc::new(tokio::sync::Mutex::new(session)),
+            },
+        );
+        info
+    }
+
+    /// Locks a session for the duration of a command. Statements on the same
+    /// session queue up behind each other.
+    pub async fn lock(&self, session_id: &str) -> Result<OwnedMutexGuard<Session>, AppError> {
+        let session = self
+            .sessions
+            .lock()
+            .unwrap()
+            .get(session_id)
+            .map(|entry| Arc::clone(&entry.session))
+            .ok_or_else(|| AppError::not_found("Session not found"))?;
+        Ok(session.lock_owned().await)
+    }
+
+    /// Rolls back any open transaction and closes the client. Returns false if the session was already closed.
+    pub async fn close(&self, session_id: &str) -> bool {
+        let Some(entry) = self.sessions.lock().unwrap().remove(session_id) else {
+            return false;
+        };
+        // Locking waits out a statement that is still running
+        entry.session.lock().await.close().await;
+        true
+    }
+
+    /// Closes every session on a connection, e.g. before it is disconnected.
+    pub async fn close_for_connection(&self, connection_id: &str) {
+        let entries: Vec<SessionEntry> = {
+            let mut sessions = self.sessions.lock().unwrap();
+            let ids: Vec<String> = sessions
+                .iter()
+                .filter(|(_, entry)| entry.connection_id == connection_id)
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.iter().filter_map(|id| sessions.remove(id)).collect()
+        };
+        for entry in entries {
+            entry.session.lock().await.close().await;
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use tokio_postgres::types::ToSql;
+
+/// Quotes an identifier, e.g. `Order "Items"` becomes `"Order ""Items"""`.
+/// Always quoting keeps mixed-case names and reserved words intact.
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// `schema.name` with both parts quoted.
+pub fn qualified_name(schema: &str, name: &str) -> String {
+    format!("{}.{}", quote_identifier(schema), quote_identifier(name))
+}
+
+/// Quotes a string literal for scripts that are shown rather than run with
+/// parameters, e.g. `it's` becomes `'it''s'`. Backslashes get an `E''`
+/// literal so the text means the same whatever `standard_conforming_strings`
+/// is set to.
+pub fn quote_literal(value: &str) -> String {
+    let quoted = value.replace('\'', "''");
+    if value.contains('\\') {
+        format!("E'{}'", quoted.replace('\\', "\\\\"))
+    } else {
+        format!("'{}'", quoted)
+    }
+}
+
+/// Bind parameters collected while building a statement, so values never
+/// end up in the SQL text.
+#[derive(Default)]
+pub struct Params<'a> {
+    values: Vec<Box<dyn ToSql + Sync + 'a>>,
+}
+
+impl<'a> Params<'a> {
+    /// Adds a parameter and returns its placeholder, e.g. `$3`.
+    pub fn push(&mut self, value: impl ToSql + Sync + 'a) -> String {
+        self.values.push(Box::new(value));
+        format!("${}", self.values.len())
+    }
+
+    /// The parameters in the form `Client::query` and friends take.
+    pub fn as_refs(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.values
+            .iter()
+            .map(|value| value.as_ref() as &(dyn ToSql + Sync))
+            .collect()
+    }
+}
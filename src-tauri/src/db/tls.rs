@@ -0,0 +1,74 @@
+use crate::models::{ConnectionConfig, SslMode};
+use anyhow::{Context, Result};
+use native_tls::{Certificate, Identity, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use std::error::Error as _;
+use std::fs;
+
+/// Builds the TLS connector for a connection according to its `ssl_mode`.
+///
+/// As in libpq, `prefer` and `require` encrypt without verifying the server,
+/// unless `require` is given a root certificate, in which case it behaves like
+/// `verify-ca`. `verify-ca` checks the chain but not the host name, and
+/// `verify-full` checks both. Without `ssl_root_cert` the system trust store
+/// is used.
+pub fn make_connector(config: &ConnectionConfig) -> Result<MakeTlsConnector> {
+    let mut builder = TlsConnector::builder();
+
+    if let Some(path) = non_empty(&config.ssl_root_cert) {
+        let pem = fs::read(path).with_context(|| format!("Failed to read root certificate {}", path))?;
+        let cert = Certificate::from_pem(&pem)
+            .with_context(|| format!("Invalid root certificate {}", path))?;
+        builder.add_root_certificate(cert);
+    }
+
+    match (non_empty(&config.ssl_client_cert), non_empty(&config.ssl_client_key)) {
+        (Some(cert_path), Some(key_path)) => {
+            let cert = fs::read(cert_path)
+                .with_context(|| format!("Failed to read client certificate {}", cert_path))?;
+            let key = fs::read(key_path)
+                .with_context(|| format!("Failed to read client key {}", key_path))?;
+            let identity = Identity::from_pkcs8(&cert, &key)
+                .context("Invalid client certificate or key (the key must be PKCS#8 PEM)")?;
+            builder.identity(identity);
+        }
+        (None, None) => {}
+        _ => anyhow::bail!("Client certificate and client key must be provided together"),
+    }
+
+    let verify_chain = match config.ssl_mode {
+        SslMode::Disable | SslMode::Prefer => false,
+        SslMode::Require => non_empty(&config.ssl_root_cert).is_some(),
+        SslMode::VerifyCa | SslMode::VerifyFull => true,
+    };
+    builder.danger_accept_invalid_certs(!verify_chain);
+    builder.danger_accept_invalid_hostnames(config.ssl_mode != SslMode::VerifyFull);
+
+    let connector = builder.build().context("Failed to build TLS connector")?;
+    Ok(MakeTlsConnector::new(connector))
+}
+
+/// Maps our `sslmode` onto the negotiation modes tokio-postgres understands;
+/// the verify modes are enforced by the connector itself.
+pub fn pg_ssl_mode(mode: SslMode) -> tokio_postgres::config::SslMode {
+    match mode {
+        SslMode::Disable => tokio_postgres::config::SslMode::Disable,
+        SslMode::Prefer => tokio_postgres::config::SslMode::Prefer,
+        SslMode::Require | SslMode::VerifyCa | SslMode::VerifyFull => {
+            tokio_postgres::config::SslMode::Require
+        }
+    }
+}
+
+/// Returns true if a connect error came from TLS negotiation rather than from
+/// the network or authentication.
+pub fn is_tls_error(error: &tokio_postgres::Error) -> bool {
+    error.to_string().starts_with("error performing TLS handshake")
+        || error
+            .source()
+            .is_some_and(|source| source.downcast_ref::<native_tls::Error>().is_some())
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|v| !v.trim().is_empty())
+}
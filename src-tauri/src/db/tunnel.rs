@@ -0,0 +1,332 @@
+use crate::models::{SshAuthMethod, SshTunnelConfig};
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::Engine;
+use ssh2::{CheckResult, ErrorCode, HashType, KnownHostFileKind, Session};
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LIBSSH2_ERROR_EAGAIN: i32 = -37;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const KEEPALIVE_INTERVAL_SECS: u32 = 30;
+const IDLE_POLL: Duration = Duration::from_millis(2);
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+
+/// Failure while setting up or running an SSH tunnel. Kept separate from
+/// Postgres errors so the UI can tell a bad bastion login from a bad database
+/// login.
+#[derive(Debug)]
+pub struct TunnelError(String);
+
+impl fmt::Display for TunnelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SSH tunnel failed: {}", self.0)
+    }
+}
+
+impl std::error::Error for TunnelError {}
+
+impl TunnelError {
+    fn new(context: &str, error: impl fmt::Display) -> Self {
+        Self(format!("{}: {}", context, error))
+    }
+}
+
+/// A local TCP port forwarded through a bastion host to the database server.
+/// Closing (or dropping) the tunnel stops accepting connections and tears
+/// down every forwarded channel.
+pub struct SshTunnel {
+    local_addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl SshTunnel {
+    /// Connects and authenticates to the bastion, then starts forwarding a
+    /// loopback port to `target_host:target_port` as seen from the bastion.
+    ///
+    /// This blocks on network I/O, so call it from `spawn_blocking`.
+    pub fn open(
+        config: &SshTunnelConfig,
+        secret: Option<&str>,
+        target_host: &str,
+        target_port: u16,
+    ) -> Result<Self, TunnelError> {
+        let addr = (config.host.as_str(), config.port)
+            .to_socket_addrs()
+            .map_err(|e| TunnelError::new("could not resolve bastion host", e))?
+            .next()
+            .ok_or_else(|| TunnelError(format!("no address found for {}", config.host)))?;
+        let tcp = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+            .map_err(|e| TunnelError::new("could not reach bastion host", e))?;
+
+        let mut session = Session::new().map_err(|e| TunnelError::new("session setup", e))?;
+        session.set_tcp_stream(tcp);
+        session.set_timeout(CONNECT_TIMEOUT.as_millis() as u32);
+        session
+            .handshake()
+            .map_err(|e| TunnelError::new("SSH handshake failed", e))?;
+
+        verify_host_key(&session, config)?;
+        authenticate(&session, config, secret)?;
+
+        let listener = TcpListener::bind(("127.0.0.1", 0))
+            .map_err(|e| TunnelError::new("could not bind local port", e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| TunnelError::new("could not bind local port", e))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| TunnelError::new("could not bind local port", e))?;
+
+        // All channels share one session, so everything after authentication
+        // runs non-blocking and polls, letting forwarders interleave.
+        session.set_keepalive(false, KEEPALIVE_INTERVAL_SECS);
+        session.set_blocking(false);
+
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let target = (target_host.to_string(), target_port);
+        {
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || accept_loop(listener, session, target, shutdown));
+        }
+
+        Ok(Self {
+            local_addr,
+            shutdown,
+        })
+    }
+
+    pub fn local_port(&self) -> u16 {
+        self.local_addr.port()
+    }
+
+    pub fn close(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Async wrapper around `SshTunnel::open` for use from commands and the pool.
+pub async fn open_tunnel(
+    config: &SshTunnelConfig,
+    secret: Option<&str>,
+    target_host: &str,
+    target_port: u16,
+) -> Result<SshTunnel, TunnelError> {
+    let config = config.clone();
+    let secret = secret.map(str::to_string);
+    let target_host = target_host.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        SshTunnel::open(&config, secret.as_deref(), &target_host, target_port)
+    })
+    .await
+    .map_err(|e| TunnelError::new("tunnel task failed", e))?
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// Accepts the bastion's host key if it matches the fingerprint pinned in the
+/// config or, with nothing pinned, an entry in `~/.ssh/known_hosts`. An
+/// unknown key is rejected with its fingerprint so the user can check it and
+/// pin it.
+fn verify_host_key(session: &Session, config: &SshTunnelConfig) -> Result<(), TunnelError> {
+    let hash = session.host_key_hash(HashType::Sha256);
+    let (Some((key, _)), Some(hash)) = (session.host_key(), hash) else {
+        return Err(TunnelError("bastion did not present a host key".to_string()));
+    };
+    let fingerprint = format!("SHA256:{}", STANDARD_NO_PAD.encode(hash));
+
+    if let Some(pinned) = config
+        .host_key_fingerprint
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    {
+        if pinned == fingerprint {
+            return Ok(());
+        }
+        return Err(TunnelError(format!(
+            "host key for {} is {}, not the confirmed {}",
+            config.host, fingerprint, pinned
+        )));
+    }
+
+    let known_hosts_path = dirs::home_dir().map(|home| home.join(".ssh").join("known_hosts"));
+    let mut known_hosts = session
+        .known_hosts()
+        .map_err(|e| TunnelError::new("could not read known_hosts", e))?;
+    let result = match &known_hosts_path {
+        Some(path) if path.exists() => {
+            known_hosts
+                .read_file(path, KnownHostFileKind::OpenSSH)
+                .map_err(|e| TunnelError::new(&format!("could not read {}", path.display()), e))?;
+            known_hosts.check_port(&config.host, config.port, key)
+        }
+        _ => CheckResult::NotFound,
+    };
+
+    match result {
+        CheckResult::Match => Ok(()),
+        CheckResult::Mismatch => Err(TunnelError(format!(
+            "host key for {} ({}) does not match known_hosts",
+            config.host, fingerprint
+        ))),
+        CheckResult::NotFound | CheckResult::Failure => Err(TunnelError(format!(
+            "host key for {} is not known; if {} is the bastion's key, add it to known_hosts or confirm it in the connection's SSH settings",
+            config.host, fingerprint
+        ))),
+    }
+}
+
+fn authenticate(
+    session: &Session,
+    config: &SshTunnelConfig,
+    secret: Option<&str>,
+) -> Result<(), TunnelError> {
+    let secret = secret.filter(|s| !s.is_empty());
+
+    let result = match config.auth {
+        SshAuthMethod::Password => {
+            session.userauth_password(&config.username, secret.unwrap_or_default())
+        }
+        SshAuthMethod::PrivateKey => {
+            let key_path = config
+                .private_key_path
+                .as_deref()
+                .filter(|p| !p.trim().is_empty())
+                .ok_or_else(|| TunnelError("no private key file configured".to_string()))?;
+            session.userauth_pubkey_file(&config.username, None, &expand_home(key_path), secret)
+        }
+    };
+
+    result.map_err(|e| TunnelError::new("authentication to bastion failed", e))?;
+    if !session.authenticated() {
+        return Err(TunnelError("authentication to bastion failed".to_string()));
+    }
+    Ok(())
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn accept_loop(
+    listener: TcpListener,
+    session: Session,
+    target: (String, u16),
+    shutdown: Arc<AtomicBool>,
+) {
+    let mut last_keepalive = Instant::now();
+
+    while !shutdown.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let session = session.clone();
+                let target = target.clone();
+                let shutdown = Arc::clone(&shutdown);
+                thread::spawn(move || {
+                    if let Err(e) = forward(&session, stream, &target, &shutdown) {
+                        eprintln!("ssh tunnel channel error: {}", e);
+                    }
+                });
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+            Err(e) => {
+                eprintln!("ssh tunnel accept error: {}", e);
+                break;
+            }
+        }
+
+        if last_keepalive.elapsed() >= Duration::from_secs(KEEPALIVE_INTERVAL_SECS.into()) {
+            let _ = session.keepalive_send();
+            last_keepalive = Instant::now();
+        }
+    }
+
+    let _ = session.disconnect(None, "tunnel closed", None);
+}
+
+/// Pumps bytes between one local socket and a `direct-tcpip` channel until
+/// either side closes or the tunnel shuts down.
+fn forward(
+    session: &Session,
+    mut local: TcpStream,
+    (host, port): &(String, u16),
+    shutdown: &AtomicBool,
+) -> io::Result<()> {
+    let mut channel = retry(|| session.channel_direct_tcpip(host, *port, None))?;
+    local.set_nonblocking(true)?;
+    local.set_nodelay(true)?;
+
+    let mut buf = [0u8; 16 * 1024];
+    while !shutdown.load(Ordering::SeqCst) {
+        let mut idle = true;
+
+        match local.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                write_fully(&mut channel, &buf[..n], shutdown)?;
+                idle = false;
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e),
+        }
+
+        match channel.read(&mut buf) {
+            Ok(0) if channel.eof() => break,
+            Ok(0) => {}
+            Ok(n) => {
+                write_fully(&mut local, &buf[..n], shutdown)?;
+                idle = false;
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(e) => return Err(e),
+        }
+
+        if idle {
+            thread::sleep(IDLE_POLL);
+        }
+    }
+
+    let _ = retry(|| channel.close());
+    Ok(())
+}
+
+fn write_fully(writer: &mut impl Write, mut data: &[u8], shutdown: &AtomicBool) -> io::Result<()> {
+    while !data.is_empty() {
+        if shutdown.load(Ordering::SeqCst) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "tunnel closed"));
+        }
+        match writer.write(data) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(n) => data = &data[n..],
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(IDLE_POLL),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Repeats a libssh2 call while it reports `EAGAIN` on the non-blocking session.
+fn retry<T>(mut op: impl FnMut() -> Result<T, ssh2::Error>) -> io::Result<T> {
+    loop {
+        match op() {
+            Err(e) if e.code() == ErrorCode::Session(LIBSSH2_ERROR_EAGAIN) => {
+                thread::sleep(IDLE_POLL)
+            }
+            result => return result.map_err(io::Error::from),
+        }
+    }
+}
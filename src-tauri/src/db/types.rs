@@ -0,0 +1,1143 @@
+use bytes::{BufMut, BytesMut};
+use chrono::NaiveTime;
+use fallible_iterator::FallibleIterator;
+use postgres_protocol::types as wire;
+use serde_json::{Map, Number, Value};
+use std::error::Error;
+use std::fmt::Write as _;
+use tokio_postgres::types::{to_sql_checked, Format, FromSql, IsNull, Kind, ToSql, Type};
+use tokio_postgres::Row;
+
+type DecodeResult<T = Value> = Result<T, Box<dyn Error + Sync + Send>>;
+
+/// Largest integer a JavaScript number holds exactly; `int8` values beyond it
+/// are sent as strings.
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// A column value in binary wire format. Accepts every type so decoding can
+/// dispatch on the column type instead of guessing Rust types.
+struct RawValue<'a>(Option<&'a [u8]>);
+
+impl<'a> FromSql<'a> for RawValue<'a> {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> DecodeResult<Self> {
+        Ok(RawValue(Some(raw)))
+    }
+
+    fn from_sql_null(_: &Type) -> DecodeResult<Self> {
+        Ok(RawValue(None))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// Converts one row to JSON. SQL NULL becomes `null`; a value whose type has
+/// no decoder is sent as its raw bytes in `\x` hex form and its column index
+/// is returned in the second list, so the UI never mistakes it for NULL.
+pub fn decode_row(row: &Row) -> (Vec<Value>, Vec<usize>) {
+    let mut undecodable = Vec::new();
+    let values = row
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let raw = match row.try_get::<_, RawValue>(i) {
+                Ok(RawValue(Some(raw))) => raw,
+                _ => return Value::Null,
+            };
+            decode(column.type_(), raw).unwrap_or_else(|_| {
+                undecodable.push(i);
+                Value::String(hex(raw))
+            })
+        })
+        .collect();
+    (values, undecodable)
+}
+
+/// Decodes a non-NULL value of type `ty`.
+///
+/// Numbers, booleans and JSON map to their JSON counterparts; arrays become
+/// nested JSON arrays and composites JSON objects. Everything else, including
+/// `numeric` (to keep its precision) and date/time types, becomes a string.
+pub fn decode(ty: &Type, raw: &[u8]) -> DecodeResult {
+    match ty.kind() {
+        Kind::Enum(_) => text(raw),
+        Kind::Domain(base) => decode(base, raw),
+        Kind::Array(member) => array(member, raw),
+        Kind::Range(member) => range(member, raw).map(Value::String),
+        Kind::Multirange(member) => multirange(member, raw),
+        Kind::Composite(fields) => {
+            let types = fields.iter().map(|f| (f.name(), Some(f.type_().clone())));
+            composite(types, raw)
+        }
+        _ if *ty == Type::RECORD => record(raw),
+        _ => simple(ty, raw),
+    }
+}
+
+fn simple(ty: &Type, raw: &[u8]) -> DecodeResult {
+    let value = match *ty {
+        Type::BOOL => Value::Bool(wire::bool_from_sql(raw)?),
+        Type::INT2 => wire::int2_from_sql(raw)?.into(),
+        Type::INT4 => wire::int4_from_sql(raw)?.into(),
+        Type::INT8 => int8(wire::int8_from_sql(raw)?),
+        Type::OID
+        | Type::XID
+        | Type::CID
+        | Type::REGPROC
+        | Type::REGPROCEDURE
+        | Type::REGOPER
+        | Type::REGOPERATOR
+        | Type::REGCLASS
+        | Type::REGTYPE
+        | Type::REGCONFIG
+        | Type::REGDICTIONARY
+        | Type::REGNAMESPACE
+        | Type::REGROLE
+        | Type::REGCOLLATION => wire::oid_from_sql(raw)?.into(),
+        Type::XID8 => {
+            let v = Reader::new(raw).u64()?;
+            if v > MAX_SAFE_INTEGER as u64 {
+                Value::String(v.to_string())
+            } else {
+                v.into()
+            }
+        }
+        // Going through the shortest decimal form keeps 0.1::real as 0.1
+        Type::FLOAT4 => float(wire::float4_from_sql(raw)?.to_string().parse()?),
+        Type::FLOAT8 => float(wire::float8_from_sql(raw)?),
+        Type::NUMERIC => Value::String(numeric(raw)?),
+        Type::MONEY => Value::String(money(wire::int8_from_sql(raw)?)),
+        Type::TEXT
+        | Type::VARCHAR
+        | Type::BPCHAR
+        | Type::NAME
+        | Type::UNKNOWN
+        | Type::XML
+        | Type::REFCURSOR
+        | Type::CSTRING => text(raw)?,
+        Type::CHAR => Value::String((wire::char_from_sql(raw)? as u8 as char).to_string()),
+        Type::BYTEA => Value::String(hex(raw)),
+        Type::UUID => Value::String(uuid::Uuid::from_bytes(wire::uuid_from_sql(raw)?).to_string()),
+        Type::JSON => serde_json::from_slice(raw)?,
+        Type::JSONB => serde_json::from_slice(versioned(raw)?)?,
+        Type::JSONPATH => text(versioned(raw)?)?,
+        Type::DATE => Value::String(date(wire::date_from_sql(raw)?)),
+        Type::TIME => Value::String(time(wire::time_from_sql(raw)?)?),
+        Type::TIMETZ => Value::String(timetz(raw)?),
+        Type::TIMESTAMP => Value::String(timestamp(wire::timestamp_from_sql(raw)?, false)?),
+        Type::TIMESTAMPTZ => Value::String(timestamp(wire::timestamp_from_sql(raw)?, true)?),
+        Type::INTERVAL => Value::String(interval(raw)?),
+        Type::INET | Type::CIDR => {
+            let inet = wire::inet_from_sql(raw)?;
+            let full = if inet.addr().is_ipv4() { 32 } else { 128 };
+            if *ty == Type::INET && inet.netmask() == full {
+                Value::String(inet.addr().to_string())
+            } else {
+                Value::String(format!("{}/{}", inet.addr(), inet.netmask()))
+            }
+        }
+        Type::MACADDR => Value::String(mac(&wire::macaddr_from_sql(raw)?)),
+        Type::MACADDR8 => Value::String(mac(Reader::new(raw).bytes(8)?)),
+        Type::BIT | Type::VARBIT => {
+            let bits = wire::varbit_from_sql(raw)?;
+            let bytes = bits.bytes();
+            Value::String(
+                (0..bits.len())
+                    .map(|i| if bytes[i / 8] & (0x80 >> (i % 8)) != 0 { '1' } else { '0' })
+                    .collect(),
+            )
+        }
+        Type::POINT => {
+            let p = wire::point_from_sql(raw)?;
+            Value::String(point(p.x(), p.y()))
+        }
+        Type::LSEG => {
+            let mut r = Reader::new(raw);
+            let (a, b) = (r.point()?, r.point()?);
+            Value::String(format!("[{},{}]", a, b))
+        }
+        Type::BOX => {
+            let b = wire::box_from_sql(raw)?;
+            let (ur, ll) = (b.upper_right(), b.lower_left());
+            Value::String(format!("{},{}", point(ur.x(), ur.y()), point(ll.x(), ll.y())))
+        }
+        Type::PATH => {
+            let path = wire::path_from_sql(raw)?;
+            let points: Vec<String> = path.points().map(|p| Ok(point(p.x(), p.y()))).collect()?;
+            let (open, close) = if path.closed() { ('(', ')') } else { ('[', ']') };
+            Value::String(format!("{}{}{}", open, points.join(","), close))
+        }
+        Type::POLYGON => {
+            let mut r = Reader::new(raw);
+            let count = r.i32()?;
+            let points = (0..count).map(|_| r.point()).collect::<DecodeResult<Vec<_>>>()?;
+            Value::String(format!("({})", points.join(",")))
+        }
+        Type::LINE => {
+            let mut r = Reader::new(raw);
+            Value::String(format!("{{{},{},{}}}", r.f64()?, r.f64()?, r.f64()?))
+        }
+        Type::CIRCLE => {
+            let mut r = Reader::new(raw);
+            let center = r.point()?;
+            Value::String(format!("<{},{}>", center, r.f64()?))
+        }
+        Type::PG_LSN => {
+            let lsn = wire::lsn_from_sql(raw)?;
+            Value::String(format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF))
+        }
+        Type::TID => {
+            let mut r = Reader::new(raw);
+            Value::String(format!("({},{})", r.u32()?, r.u16()?))
+        }
+        Type::TS_VECTOR => Value::String(tsvector(raw)?),
+        Type::TSQUERY => Value::String(tsquery(raw)?),
+        Type::VOID => Value::String(String::new()),
+        // Extension types have no fixed OID, so go by name
+        _ => match ty.name() {
+            "citext" => text(raw)?,
+            "ltree" => Value::String(wire::ltree_from_sql(raw)?.to_string()),
+            "lquery" => Value::String(wire::lquery_from_sql(raw)?.to_string()),
+            "ltxtquery" => Value::String(wire::ltxtquery_from_sql(raw)?.to_string()),
+            "hstore" => {
+                let mut map = Map::new();
+                let mut entries = wire::hstore_from_sql(raw)?;
+                while let Some((key, value)) = entries.next()? {
+                    map.insert(key.to_string(), value.map_or(Value::Null, Value::from));
+                }
+                Value::Object(map)
+            }
+            name => return Err(format!("no decoder for type {}", name).into()),
+        },
+    };
+    Ok(value)
+}
+
+fn text(raw: &[u8]) -> DecodeResult {
+    Ok(Value::String(wire::text_from_sql(raw)?.to_string()))
+}
+
+fn hex(raw: &[u8]) -> String {
+    let mut out = String::with_capacity(2 + raw.len() * 2);
+    out.push_str("\\x");
+    for byte in raw {
+        let _ = write!(out, "{:02x}", byte);
+    }
+    out
+}
+
+fn int8(v: i64) -> Value {
+    if (-MAX_SAFE_INTEGER..=MAX_SAFE_INTEGER).contains(&v) {
+        v.into()
+    } else {
+        Value::String(v.to_string())
+    }
+}
+
+/// JSON has no NaN or infinities, so those are sent in PostgreSQL's spelling.
+fn float(v: f64) -> Value {
+    match Number::from_f64(v) {
+        Some(n) => Value::Number(n),
+        None if v.is_nan() => Value::String("NaN".to_string()),
+        None if v > 0.0 => Value::String("Infinity".to_string()),
+        None => Value::String("-Infinity".to_string()),
+    }
+}
+
+/// Strips the format version byte that `jsonb` and `jsonpath` prefix.
+fn versioned(raw: &[u8]) -> DecodeResult<&[u8]> {
+    match raw.split_first() {
+        Some((1, rest)) => Ok(rest),
+        _ => Err("unsupported binary format version".into()),
+    }
+}
+
+fn numeric(raw: &[u8]) -> DecodeResult<String> {
+    const NEGATIVE: u16 = 0x4000;
+    const NAN: u16 = 0xC000;
+    const POSITIVE_INFINITY: u16 = 0xD000;
+    const NEGATIVE_INFINITY: u16 = 0xF000;
+
+    let mut r = Reader::new(raw);
+    let ndigits = r.u16()? as usize;
+    let weight = r.i16()? as i32;
+    let sign = r.u16()?;
+    let scale = r.u16()? as usize;
+    let digits = (0..ndigits).map(|_| r.u16()).collect::<DecodeResult<Vec<_>>>()?;
+
+    match sign {
+        NAN => return Ok("NaN".to_string()),
+        POSITIVE_INFINITY => return Ok("Infinity".to_string()),
+        NEGATIVE_INFINITY => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+
+    // Digits are base 10000; digit `i` is worth 10000^(weight - i)
+    let digit = |i: i32| -> u16 {
+        usize::try_from(i)
+            .ok()
+            .and_then(|i| digits.get(i).copied())
+            .unwrap_or(0)
+    };
+
+    let mut out = String::new();
+    if sign == NEGATIVE {
+        out.push('-');
+    }
+    if weight < 0 {
+        out.push('0');
+    } else {
+        let _ = write!(out, "{}", digit(0));
+        for i in 1..=weight {
+            let _ = write!(out, "{:04}", digit(i));
+        }
+    }
+
+    if scale > 0 {
+        let mut fraction = String::new();
+        let mut i = weight + 1;
+        while fraction.len() < scale {
+            let _ = write!(fraction, "{:04}", digit(i));
+            i += 1;
+        }
+        fraction.truncate(scale);
+        out.push('.');
+        out.push_str(&fraction);
+    }
+    Ok(out)
+}
+
+fn money(cents: i64) -> String {
+    let cents = i128::from(cents);
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+fn date(days: i32) -> String {
+    match days {
+        i32::MAX => "infinity".to_string(),
+        i32::MIN => "-infinity".to_string(),
+        _ => {
+            let (date, era) = format_date(days.into());
+            date + era
+        }
+    }
+}
+
+/// Formats a day count from 2000-01-01 the way PostgreSQL's ISO style does,
+/// e.g. `2024-02-29`, or `0044-03-15` with the era ` BC` for years before 1.
+/// The era comes back on its own since PostgreSQL puts it after the time of
+/// a timestamp. Worked out by hand: chrono stops at the year 262143 and
+/// spells years past 9999 with a sign.
+fn format_date(days: i64) -> (String, &'static str) {
+    let (year, month, day) = civil_from_days(days);
+    // There is no year 0; 1 BC comes right before 1 AD
+    let (year, era) = if year > 0 { (year, "") } else { (1 - year, " BC") };
+    (format!("{:04}-{:02}-{:02}", year, month, day), era)
+}
+
+/// Year, month and day of a day count from 2000-01-01 in the proleptic
+/// Gregorian calendar, after Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Shift to days since 0000-03-01, so leap days fall at the end of a year
+    let z = days + 730_425;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 } as u32;
+    (era * 400 + year_of_era + i64::from(month <= 2), month, day)
+}
+
+fn time(micros: i64) -> DecodeResult<String> {
+    // 24:00:00 is a valid time in PostgreSQL but not in chrono
+    if micros == 86_400_000_000 {
+        return Ok("24:00:00".to_string());
+    }
+    let secs = u32::try_from(micros.div_euclid(1_000_000))?;
+    let nanos = u32::try_from(micros.rem_euclid(1_000_000) * 1000)?;
+    let time = NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos)
+        .ok_or("time out of range")?;
+    Ok(time.format("%H:%M:%S%.f").to_string())
+}
+
+fn timetz(raw: &[u8]) -> DecodeResult<String> {
+    let mut r = Reader::new(raw);
+    let time = time(r.i64()?)?;
+    // Stored as seconds west of UTC
+    let offset = -r.i32()?;
+    let sign = if offset < 0 { '-' } else { '+' };
+    let offset = offset.abs();
+    let mut out = format!("{}{}{:02}:{:02}", time, sign, offset / 3600, offset / 60 % 60);
+    if offset % 60 != 0 {
+        let _ = write!(out, ":{:02}", offset % 60);
+    }
+    Ok(out)
+}
+
+fn timestamp(micros: i64, with_tz: bool) -> DecodeResult<String> {
+    match micros {
+        i64::MAX => return Ok("infinity".to_string()),
+        i64::MIN => return Ok("-infinity".to_string()),
+        _ => {}
+    }
+    const DAY: i64 = 86_400_000_000;
+    let (date, era) = format_date(micros.div_euclid(DAY));
+    let mut out = format!("{}T{}", date, time(micros.rem_euclid(DAY))?);
+    if with_tz {
+        out.push('Z');
+    }
+    out.push_str(era);
+    Ok(out)
+}
+
+/// Formats an interval the way PostgreSQL's default `postgres` style does,
+/// e.g. `1 year 2 mons 3 days 04:05:06.5`.
+fn interval(raw: &[u8]) -> DecodeResult<String> {
+    let mut r = Reader::new(raw);
+    let micros = r.i64()?;
+    let days = r.i32()?;
+    let months = r.i32()?;
+
+    // A positive field right after a negative one gets an explicit sign
+    let mut after_negative = false;
+    let mut sign = |negative: bool| {
+        let sign = match (negative, after_negative) {
+            (true, _) => "-",
+            (false, true) => "+",
+            (false, false) => "",
+        };
+        after_negative = negative;
+        sign
+    };
+    let mut parts = Vec::new();
+    for (n, singular, plural) in [
+        (months / 12, "year", "years"),
+        (months % 12, "mon", "mons"),
+        (days, "day", "days"),
+    ] {
+        if n != 0 {
+            let unit = if n == 1 { singular } else { plural };
+            parts.push(format!("{}{} {}", sign(n < 0), n.unsigned_abs(), unit));
+        }
+    }
+    if micros != 0 || parts.is_empty() {
+        let sign = sign(micros < 0);
+        let abs = i128::from(micros).abs();
+        let secs = abs / 1_000_000;
+        let mut time = format!(
+            "{}{:02}:{:02}:{:02}",
+            sign,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        );
+        let fraction = abs % 1_000_000;
+        if fraction != 0 {
+            let digits = format!("{:06}", fraction);
+            let _ = write!(time, ".{}", digits.trim_end_matches('0'));
+        }
+        parts.push(time);
+    }
+    Ok(parts.join(" "))
+}
+
+fn mac(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn point(x: f64, y: f64) -> String {
+    format!("({},{})", x, y)
+}
+
+fn array(member: &Type, raw: &[u8]) -> DecodeResult {
+    let array = wire::array_from_sql(raw)?;
+    let dims: Vec<usize> = array
+        .dimensions()
+        .map(|d| Ok(usize::try_from(d.len)?))
+        .collect()?;
+    let values: Vec<Value> = array
+        .values()
+        .map(|v| match v {
+            Some(raw) => decode(member, raw),
+            None => Ok(Value::Null),
+        })
+        .collect()?;
+
+    if dims.is_empty() {
+        return Ok(Value::Array(Vec::new()));
+    }
+    Ok(nest(&dims, &mut values.into_iter()))
+}
+
+/// Rebuilds a multi-dimensional array from its row-major element list.
+fn nest(dims: &[usize], values: &mut impl Iterator<Item = Value>) -> Value {
+    match dims {
+        [len] => Value::Array(values.take(*len).collect()),
+        [len, rest @ ..] => Value::Array((0..*len).map(|_| nest(rest, values)).collect()),
+        [] => Value::Array(Vec::new()),
+    }
+}
+
+/// Ranges are sent in PostgreSQL's literal form, e.g. `[1,10)`.
+fn range(member: &Type, raw: &[u8]) -> DecodeResult<String> {
+    let (lower, upper) = match wire::range_from_sql(raw)? {
+        wire::Range::Empty => return Ok("empty".to_string()),
+        wire::Range::Nonempty(lower, upper) => (lower, upper),
+    };
+
+    let bound = |bound: &wire::RangeBound<Option<&[u8]>>| -> DecodeResult<(bool, String)> {
+        Ok(match bound {
+            wire::RangeBound::Inclusive(v) => (true, range_bound(member, *v)?),
+            wire::RangeBound::Exclusive(v) => (false, range_bound(member, *v)?),
+            wire::RangeBound::Unbounded => (false, String::new()),
+        })
+    };
+    let (lower_inclusive, lower) = bound(&lower)?;
+    let (upper_inclusive, upper) = bound(&upper)?;
+    Ok(format!(
+        "{}{},{}{}",
+        if lower_inclusive { '[' } else { '(' },
+        lower,
+        upper,
+        if upper_inclusive { ']' } else { ')' }
+    ))
+}
+
+fn range_bound(member: &Type, raw: Option<&[u8]>) -> DecodeResult<String> {
+    let Some(raw) = raw else {
+        return Ok(String::new());
+    };
+    Ok(match decode(member, raw)? {
+        Value::String(s) if s.contains([',', ' ', '"']) => {
+            format!("\"{}\"", s.replace('"', "\\\""))
+        }
+        Value::String(s) => s,
+        other => other.to_string(),
+    })
+}
+
+fn multirange(member: &Type, raw: &[u8]) -> DecodeResult {
+    let mut r = Reader::new(raw);
+    let count = r.i32()?;
+    let ranges = (0..count)
+        .map(|_| {
+            let len = usize::try_from(r.i32()?)?;
+            range(member, r.bytes(len)?)
+        })
+        .collect::<DecodeResult<Vec<_>>>()?;
+    Ok(Value::String(format!("{{{}}}", ranges.join(","))))
+}
+
+/// Decodes a composite value into a JSON object keyed by attribute name.
+fn composite<'a>(
+    fields: impl Iterator<Item = (&'a str, Option<Type>)>,
+    raw: &[u8],
+) -> DecodeResult {
+    let mut r = Reader::new(raw);
+    let count = r.i32()?;
+    let mut map = Map::new();
+    for (name, ty) in fields.take(usize::try_from(count)?) {
+        let (ty, value) = composite_field(&mut r, ty)?;
+        map.insert(name.to_string(), value.map_or(Ok(Value::Null), |v| decode(&ty, v))?);
+    }
+    Ok(Value::Object(map))
+}
+
+/// Anonymous `record` values (e.g. `SELECT (1, 'a')`) carry each field's type
+/// OID inline; they decode to a JSON array since their fields have no names.
+fn record(raw: &[u8]) -> DecodeResult {
+    let mut r = Reader::new(raw);
+    let count = r.i32()?;
+    let mut values = Vec::new();
+    for _ in 0..count {
+        let (ty, value) = composite_field(&mut r, None)?;
+        values.push(match value {
+            None => Value::Null,
+            Some(v) => decode(&ty, v).unwrap_or_else(|_| Value::String(hex(v))),
+        });
+    }
+    Ok(Value::Array(values))
+}
+
+fn composite_field<'a>(
+    r: &mut Reader<'a>,
+    known: Option<Type>,
+) -> DecodeResult<(Type, Option<&'a [u8]>)> {
+    let oid = r.u32()?;
+    let ty = known
+        .or_else(|| Type::from_oid(oid))
+        .ok_or_else(|| format!("unknown field type {}", oid))?;
+    let len = r.i32()?;
+    let value = match usize::try_from(len) {
+        Ok(len) => Some(r.bytes(len)?),
+        Err(_) => None,
+    };
+    Ok((ty, value))
+}
+
+fn tsvector(raw: &[u8]) -> DecodeResult<String> {
+    let mut r = Reader::new(raw);
+    let count = r.i32()?;
+    let mut lexemes = Vec::new();
+    for _ in 0..count {
+        let mut lexeme = ts_quote(r.cstr()?);
+        let positions = (0..r.u16()?)
+            .map(|_| {
+                let pos = r.u16()?;
+                let weight = match pos >> 14 {
+                    3 => "A",
+                    2 => "B",
+                    1 => "C",
+                    _ => "",
+                };
+                Ok(format!("{}{}", pos & 0x3FFF, weight))
+            })
+            .collect::<DecodeResult<Vec<_>>>()?;
+        if !positions.is_empty() {
+            let _ = write!(lexeme, ":{}", positions.join(","));
+        }
+        lexemes.push(lexeme);
+    }
+    Ok(lexemes.join(" "))
+}
+
+/// Items arrive in prefix order with each binary operator's right operand
+/// before its left, as PostgreSQL stores them.
+fn tsquery(raw: &[u8]) -> DecodeResult<String> {
+    // Returns the rendered operand and its operator precedence; operands
+    // binding looser than their parent get parenthesized.
+    fn item(r: &mut Reader) -> DecodeResult<(String, u8)> {
+        match r.u8()? {
+            1 => {
+                let weight = r.u8()?;
+                let prefix = r.u8()? != 0;
+                let mut out = ts_quote(r.cstr()?);
+                if weight != 0 || prefix {
+                    out.push(':');
+                }
+                if prefix {
+                    out.push('*');
+                }
+                for (bit, name) in [(8, 'A'), (4, 'B'), (2, 'C'), (1, 'D')] {
+                    if weight & bit != 0 {
+                        out.push(name);
+                    }
+                }
+                Ok((out, u8::MAX))
+            }
+            2 => {
+                let (op, precedence) = match r.u8()? {
+                    1 => {
+                        let operand = operand(r, 4)?;
+                        return Ok((format!("!{}", operand), 4));
+                    }
+                    2 => ("&".to_string(), 2),
+                    3 => ("|".to_string(), 1),
+                    4 => match r.i16()? {
+                        1 => ("<->".to_string(), 3),
+                        distance => (format!("<{}>", distance), 3),
+                    },
+                    other => return Err(format!("unknown tsquery operator {}", other).into()),
+                };
+                let right = operand(r, precedence)?;
+                let left = operand(r, precedence)?;
+                Ok((format!("{} {} {}", left, op, right), precedence))
+            }
+            other => Err(format!("unknown tsquery item {}", other).into()),
+        }
+    }
+
+    fn operand(r: &mut Reader, parent: u8) -> DecodeResult<String> {
+        let (out, precedence) = item(r)?;
+        Ok(if precedence < parent { format!("( {} )", out) } else { out })
+    }
+
+    let mut r = Reader::new(raw);
+    if r.i32()? == 0 {
+        return Ok(String::new());
+    }
+    Ok(item(&mut r)?.0)
+}
+
+fn ts_quote(lexeme: &str) -> String {
+    format!("'{}'", lexeme.replace('\'', "''").replace('\\', "\\\\"))
+}
+
+/// A JSON value bound as a statement parameter. It is sent in text format,
+/// so the server parses it as whatever type the parameter turns out to have,
+/// e.g. the type of the column being set. Accepts the shapes `decode`
+/// produces: nested arrays for arrays, objects for composites and strings
+/// for everything without a JSON counterpart.
+#[derive(Debug)]
+pub struct JsonParam<'a>(pub &'a Value);
+
+impl ToSql for JsonParam<'_> {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> DecodeResult<IsNull> {
+        if self.0.is_null() {
+            return Ok(IsNull::Yes);
+        }
+        out.put_slice(text_literal(ty, self.0).as_bytes());
+        Ok(IsNull::No)
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    fn encode_format(&self, _: &Type) -> Format {
+        Format::Text
+    }
+
+    to_sql_checked!();
+}
+
+/// Spells a non-NULL value in the input syntax of `ty`.
+fn text_literal(ty: &Type, value: &Value) -> String {
+    let json = matches!(*ty, Type::JSON | Type::JSONB);
+    match (ty.kind(), value) {
+        (Kind::Domain(base), _) => text_literal(base, value),
+        (Kind::Array(member), Value::Array(items)) => array_literal(member, items),
+        (Kind::Composite(fields), Value::Object(map)) => {
+            let values = fields
+                .iter()
+                .map(|f| match map.get(f.name()) {
+                    None | Some(Value::Null) => String::new(),
+                    Some(v) => quote_element(&text_literal(f.type_(), v)),
+                })
+                .collect::<Vec<_>>();
+            format!("({})", values.join(","))
+        }
+        (_, Value::String(s)) if !json => s.clone(),
+        (_, Value::Bool(b)) if !json => if *b { "t" } else { "f" }.to_string(),
+        (_, Value::Number(n)) if !json => n.to_string(),
+        _ => value.to_string(),
+    }
+}
+
+fn array_literal(member: &Type, items: &[Value]) -> String {
+    let json = matches!(*member, Type::JSON | Type::JSONB);
+    let elements = items
+        .iter()
+        .map(|item| match item {
+            Value::Null => "NULL".to_string(),
+            // A nested array is another dimension, unless it is a JSON value
+            Value::Array(inner) if !json => array_literal(member, inner),
+            _ => quote_element(&text_literal(member, item)),
+        })
+        .collect::<Vec<_>>();
+    format!("{{{}}}", elements.join(","))
+}
+
+/// Quotes an array element or composite field.
+fn quote_element(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Big-endian cursor for wire formats `postgres-protocol` doesn't cover.
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn bytes(&mut self, len: usize) -> DecodeResult<&'a [u8]> {
+        if self.buf.len() < len {
+            return Err("unexpected end of value".into());
+        }
+        let (head, tail) = self.buf.split_at(len);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> DecodeResult<[u8; N]> {
+        Ok(self.bytes(N)?.try_into()?)
+    }
+
+    fn u8(&mut self) -> DecodeResult<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> DecodeResult<u16> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    fn i16(&mut self) -> DecodeResult<i16> {
+        Ok(i16::from_be_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> DecodeResult<u32> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> DecodeResult<i32> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> DecodeResult<u64> {
+        Ok(u64::from_be_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> DecodeResult<i64> {
+        Ok(i64::from_be_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> DecodeResult<f64> {
+        Ok(f64::from_be_bytes(self.array()?))
+    }
+
+    fn point(&mut self) -> DecodeResult<String> {
+        Ok(point(self.f64()?, self.f64()?))
+    }
+
+    fn cstr(&mut self) -> DecodeResult<&'a str> {
+        let end = self
+            .buf
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or("unterminated string")?;
+        let s = std::str::from_utf8(&self.buf[..end])?;
+        self.buf = &self.buf[end + 1..];
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tokio_postgres::types::Field;
+
+    fn numeric_bytes(weight: i16, sign: u16, scale: u16, digits: &[u16]) -> Vec<u8> {
+        let mut raw = Vec::new();
+        raw.extend((digits.len() as u16).to_be_bytes());
+        raw.extend(weight.to_be_bytes());
+        raw.extend(sign.to_be_bytes());
+        raw.extend(scale.to_be_bytes());
+        for digit in digits {
+            raw.extend(digit.to_be_bytes());
+        }
+        raw
+    }
+
+    fn interval_bytes(micros: i64, days: i32, months: i32) -> Vec<u8> {
+        let mut raw = micros.to_be_bytes().to_vec();
+        raw.extend(days.to_be_bytes());
+        raw.extend(months.to_be_bytes());
+        raw
+    }
+
+    /// A range of `int4` or `date` bounds; `flags` as in PostgreSQL's
+    /// `rangetypes.h`.
+    fn range_bytes(flags: u8, bounds: &[i32]) -> Vec<u8> {
+        let mut raw = vec![flags];
+        for bound in bounds {
+            raw.extend(4i32.to_be_bytes());
+            raw.extend(bound.to_be_bytes());
+        }
+        raw
+    }
+
+    fn cstr(raw: &mut Vec<u8>, s: &str) {
+        raw.extend(s.as_bytes());
+        raw.push(0);
+    }
+
+    fn tsquery_operand(raw: &mut Vec<u8>, lexeme: &str, weight: u8, prefix: bool) {
+        raw.extend([1, weight, prefix as u8]);
+        cstr(raw, lexeme);
+    }
+
+    /// Decodes `raw` as `ty` and checks it comes out as `expected`, and that
+    /// binding the result as a parameter spells it the same way.
+    fn round_trip(ty: &Type, raw: &[u8], expected: &str) {
+        let value = decode(ty, raw).unwrap();
+        assert_eq!(value, json!(expected), "decoding {}", expected);
+        assert_eq!(text_literal(ty, &value), expected);
+    }
+
+    #[test]
+    fn decodes_numerics() {
+        const NEGATIVE: u16 = 0x4000;
+        let cases = [
+            (numeric_bytes(0, 0, 0, &[]), "0"),
+            (numeric_bytes(0, 0, 2, &[]), "0.00"),
+            (numeric_bytes(-1, NEGATIVE, 5, &[12]), "-0.00120"),
+            (numeric_bytes(1, 0, 1, &[1234, 5678, 9000]), "12345678.9"),
+            (numeric_bytes(2, 0, 0, &[1]), "100000000"),
+            (numeric_bytes(-2, 0, 5, &[1000]), "0.00001"),
+            (
+                numeric_bytes(-5, NEGATIVE, 20, &[1]),
+                "-0.00000000000000000001",
+            ),
+            (numeric_bytes(0, 0xC000, 0, &[]), "NaN"),
+            (numeric_bytes(0, 0xD000, 0, &[]), "Infinity"),
+            (numeric_bytes(0, 0xF000, 0, &[]), "-Infinity"),
+        ];
+        for (raw, expected) in cases {
+            round_trip(&Type::NUMERIC, &raw, expected);
+        }
+        assert!(decode(&Type::NUMERIC, &[0, 1]).is_err());
+    }
+
+    #[test]
+    fn decodes_intervals() {
+        const HOUR: i64 = 3_600_000_000;
+        let cases = [
+            (
+                interval_bytes(4 * HOUR + 306_500_000, 3, 14),
+                "1 year 2 mons 3 days 04:05:06.5",
+            ),
+            (interval_bytes(0, 0, 0), "00:00:00"),
+            (interval_bytes(0, 0, 1), "1 mon"),
+            (interval_bytes(25 * HOUR, 0, 0), "25:00:00"),
+            (interval_bytes(0, 0, -14), "-1 years -2 mons"),
+            (interval_bytes(-1_250_000, -3, 0), "-3 days -00:00:01.25"),
+            (interval_bytes(-1, 0, 0), "-00:00:00.000001"),
+            (interval_bytes(-HOUR, 1, 0), "1 day -01:00:00"),
+            (interval_bytes(0, 1, -1), "-1 mons +1 day"),
+            (interval_bytes(HOUR, -1, 0), "-1 days +01:00:00"),
+            (
+                interval_bytes(3 * HOUR, 2, -12),
+                "-1 years +2 days 03:00:00",
+            ),
+            (interval_bytes(HOUR, -2, 12), "1 year -2 days +01:00:00"),
+        ];
+        for (raw, expected) in cases {
+            round_trip(&Type::INTERVAL, &raw, expected);
+        }
+    }
+
+    #[test]
+    fn converts_day_counts_to_dates() {
+        let cases = [
+            (0, (2000, 1, 1)),
+            (-1, (1999, 12, 31)),
+            (59, (2000, 2, 29)),
+            (-36_465, (1900, 3, 1)),
+            (-730_119, (1, 1, 1)),
+            // The year before 1 AD is year 0, i.e. 1 BC
+            (-730_120, (0, 12, 31)),
+            (-2_451_545, (-4713, 11, 24)),
+            (2_145_031_948, (5_874_897, 12, 31)),
+        ];
+        for (days, expected) in cases {
+            assert_eq!(civil_from_days(days), expected, "day {}", days);
+        }
+    }
+
+    #[test]
+    fn formats_dates_like_postgres() {
+        let cases = [
+            (0, "2000-01-01"),
+            (-730_119, "0001-01-01"),
+            (-730_120, "0001-12-31 BC"),
+            (-2_451_545, "4714-11-24 BC"),
+            (2_921_940, "10000-01-01"),
+            // Past chrono's range, which used to panic
+            (2_145_031_948, "5874897-12-31"),
+            (i32::MAX, "infinity"),
+            (i32::MIN, "-infinity"),
+        ];
+        for (days, expected) in cases {
+            round_trip(&Type::DATE, &days.to_be_bytes(), expected);
+        }
+    }
+
+    #[test]
+    fn formats_timestamps_like_postgres() {
+        const DAY: i64 = 86_400_000_000;
+        let cases = [
+            (Type::TIMESTAMP, 0, "2000-01-01T00:00:00"),
+            (Type::TIMESTAMP, -1, "1999-12-31T23:59:59.999999"),
+            (Type::TIMESTAMPTZ, DAY / 2, "2000-01-01T12:00:00Z"),
+            (Type::TIMESTAMP, -2_451_545 * DAY, "4714-11-24T00:00:00 BC"),
+            (
+                Type::TIMESTAMPTZ,
+                -730_120 * DAY + DAY / 2,
+                "0001-12-31T12:00:00Z BC",
+            ),
+            // Past chrono's range, which used to panic
+            (
+                Type::TIMESTAMP,
+                106_751_983 * DAY - 1,
+                "294276-12-31T23:59:59.999999",
+            ),
+            (Type::TIMESTAMPTZ, i64::MAX, "infinity"),
+            (Type::TIMESTAMP, i64::MIN, "-infinity"),
+        ];
+        for (ty, micros, expected) in cases {
+            round_trip(&ty, &micros.to_be_bytes(), expected);
+        }
+    }
+
+    #[test]
+    fn decodes_text_search_values() {
+        let mut vector = 2i32.to_be_bytes().to_vec();
+        cstr(&mut vector, "cat");
+        vector.extend(2u16.to_be_bytes());
+        vector.extend((3u16 << 14 | 1).to_be_bytes());
+        vector.extend(3u16.to_be_bytes());
+        cstr(&mut vector, "it's");
+        vector.extend(0u16.to_be_bytes());
+        round_trip(&Type::TS_VECTOR, &vector, "'cat':1A,3 'it''s'");
+
+        // 'fat' & ( 'rat' | !'cat' ), with right operands first
+        let mut query = 6i32.to_be_bytes().to_vec();
+        query.extend([2, 2, 2, 3, 2, 1]);
+        tsquery_operand(&mut query, "cat", 0, false);
+        tsquery_operand(&mut query, "rat", 0, false);
+        tsquery_operand(&mut query, "fat", 0, false);
+        round_trip(&Type::TSQUERY, &query, "'fat' & ( 'rat' | !'cat' )");
+
+        // 'sup':*AB <2> ( 'a' & 'b' )
+        let mut phrase = 5i32.to_be_bytes().to_vec();
+        phrase.extend([2, 4]);
+        phrase.extend(2i16.to_be_bytes());
+        phrase.extend([2, 2]);
+        tsquery_operand(&mut phrase, "b", 0, false);
+        tsquery_operand(&mut phrase, "a", 0, false);
+        tsquery_operand(&mut phrase, "sup", 8 | 4, true);
+        round_trip(&Type::TSQUERY, &phrase, "'sup':*AB <2> ( 'a' & 'b' )");
+
+        round_trip(&Type::TS_VECTOR, &0i32.to_be_bytes(), "");
+        round_trip(&Type::TSQUERY, &0i32.to_be_bytes(), "");
+    }
+
+    #[test]
+    fn decodes_ranges() {
+        const EMPTY: u8 = 0x01;
+        const LOWER_INCLUSIVE: u8 = 0x02;
+        const UPPER_INCLUSIVE: u8 = 0x04;
+        const LOWER_INFINITE: u8 = 0x08;
+        const UPPER_INFINITE: u8 = 0x10;
+        let cases = [
+            (
+                Type::INT4_RANGE,
+                range_bytes(LOWER_INCLUSIVE, &[1, 10]),
+                "[1,10)",
+            ),
+            (Type::INT4_RANGE, range_bytes(EMPTY, &[]), "empty"),
+            (Type::INT4_RANGE, range_bytes(LOWER_INFINITE, &[4]), "(,4)"),
+            (
+                Type::INT4_RANGE,
+                range_bytes(LOWER_INFINITE | UPPER_INFINITE, &[]),
+                "(,)",
+            ),
+            (
+                Type::DATE_RANGE,
+                range_bytes(LOWER_INCLUSIVE | UPPER_INCLUSIVE, &[-2_451_545, 0]),
+                "[\"4714-11-24 BC\",2000-01-01]",
+            ),
+        ];
+        for (ty, raw, expected) in cases {
+            round_trip(&ty, &raw, expected);
+        }
+
+        let mut multirange = 2i32.to_be_bytes().to_vec();
+        for range in [
+            range_bytes(LOWER_INCLUSIVE, &[1, 3]),
+            range_bytes(EMPTY, &[]),
+        ] {
+            multirange.extend((range.len() as i32).to_be_bytes());
+            multirange.extend(range);
+        }
+        round_trip(&Type::INT4MULTI_RANGE, &multirange, "{[1,3),empty}");
+    }
+
+    #[test]
+    fn decodes_composites() {
+        let pair = Type::new(
+            "pair".to_string(),
+            0,
+            Kind::Composite(vec![
+                Field::new("a".to_string(), Type::INT4),
+                Field::new("b".to_string(), Type::TEXT),
+                Field::new("c".to_string(), Type::TEXT),
+            ]),
+            "public".to_string(),
+        );
+        let mut raw = 3i32.to_be_bytes().to_vec();
+        raw.extend(Type::INT4.oid().to_be_bytes());
+        raw.extend(4i32.to_be_bytes());
+        raw.extend(1i32.to_be_bytes());
+        raw.extend(Type::TEXT.oid().to_be_bytes());
+        raw.extend(7i32.to_be_bytes());
+        raw.extend(br#"x "q" \"#);
+        raw.extend(Type::TEXT.oid().to_be_bytes());
+        raw.extend((-1i32).to_be_bytes());
+
+        let value = decode(&pair, &raw).unwrap();
+        assert_eq!(value, json!({ "a": 1, "b": r#"x "q" \"#, "c": null }));
+        assert_eq!(text_literal(&pair, &value), r#"("1","x \"q\" \\",)"#);
+
+        // Anonymous records carry their field types and come out as arrays
+        assert_eq!(
+            decode(&Type::RECORD, &raw).unwrap(),
+            json!([1, r#"x "q" \"#, null])
+        );
+    }
+
+    #[test]
+    fn binds_json_as_text() {
+        let text = |ty: &Type, value: Value| {
+            let mut out = BytesMut::new();
+            match JsonParam(&value).to_sql(ty, &mut out).unwrap() {
+                IsNull::Yes => None,
+                IsNull::No => Some(String::from_utf8(out.to_vec()).unwrap()),
+            }
+        };
+        let cases = [
+            (Type::INT4, json!(null), None),
+            (Type::INT4, json!(42), Some("42")),
+            (Type::BOOL, json!(true), Some("t")),
+            (Type::TEXT, json!("it's"), Some("it's")),
+            (Type::NUMERIC, json!("NaN"), Some("NaN")),
+            (Type::JSON, json!("str"), Some(r#""str""#)),
+            (
+                Type::JSONB,
+                json!({ "a": [1, true] }),
+                Some(r#"{"a":[1,true]}"#),
+            ),
+            (
+                Type::INT4_ARRAY,
+                json!([[1, 2], [3, null]]),
+                Some(r#"{{"1","2"},{"3",NULL}}"#),
+            ),
+            (
+                Type::TEXT_ARRAY,
+                json!(["a,b", r#"c"d"#, "", null]),
+                Some(r#"{"a,b","c\"d","",NULL}"#),
+            ),
+            // JSON arrays inside a jsonb[] are values, not dimensions
+            (
+                Type::JSONB_ARRAY,
+                json!([{ "a": 1 }, [1, 2]]),
+                Some(r#"{"{\"a\":1}","[1,2]"}"#),
+            ),
+        ];
+        for (ty, value, expected) in cases {
+            assert_eq!(text(&ty, value).as_deref(), expected);
+        }
+
+        let domain = Type::new(
+            "positive".to_string(),
+            0,
+            Kind::Domain(Type::INT4_ARRAY),
+            "public".to_string(),
+        );
+        assert_eq!(text(&domain, json!([1])).as_deref(), Some(r#"{"1"}"#));
+    }
+}
@@ -0,0 +1,237 @@
+use crate::db::tls;
+use crate::db::tunnel::TunnelError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tokio_postgres::error::{DbError, ErrorPosition, SqlState};
+
+/// Broad cause of a failure, so the UI can react without parsing messages,
+/// e.g. offer to re-enter a password on `auth` or to reconnect on
+/// `connection`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCategory {
+    /// The server rejected a statement.
+    Database,
+    /// The server could not be reached or dropped the connection.
+    Connection,
+    /// The server rejected the credentials.
+    Auth,
+    Tls,
+    Tunnel,
+    /// The system keyring could not store or return a secret.
+    Keyring,
+    /// The request itself is invalid.
+    Validation,
+    NotFound,
+    Canceled,
+    /// Reading or writing the app's own files failed.
+    Storage,
+    Internal,
+}
+
+/// Error returned by every command. Serialized as-is to the frontend, where
+/// `invoke` rejects with it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AppError {
+    pub category: ErrorCategory,
+    pub message: String,
+    /// Fields reported by the server, when the error came from PostgreSQL.
+    pub db: Option<Box<DbErrorDetails>>,
+}
+
+/// The fields of a PostgreSQL `ErrorResponse`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DbErrorDetails {
+    /// SQLSTATE, e.g. `42P01`.
+    pub code: String,
+    pub severity: String,
+    pub message: String,
+    pub detail: Option<String>,
+    pub hint: Option<String>,
+    /// 1-based character offset of the error in the submitted SQL.
+    pub position: Option<u32>,
+    /// Offset into `internal_query`, for errors inside internally generated
+    /// SQL such as a PL/pgSQL statement.
+    pub internal_position: Option<u32>,
+    pub internal_query: Option<String>,
+    /// Call stack context, e.g. the PL/pgSQL function and line.
+    #[serde(rename = "where")]
+    pub where_: Option<String>,
+    pub schema: Option<String>,
+    pub table: Option<String>,
+    pub column: Option<String>,
+    pub datatype: Option<String>,
+    pub constraint: Option<String>,
+}
+
+impl AppError {
+    pub fn new(category: ErrorCategory, message: impl Into<String>) -> Self {
+        Self {
+            category,
+            message: message.into(),
+            db: None,
+        }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCategory::Validation, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCategory::NotFound, message)
+    }
+
+    pub fn canceled() -> Self {
+        Self::new(ErrorCategory::Canceled, "Query canceled")
+    }
+
+    /// Converts an error from the lower layers, using `fallback` when nothing
+    /// in its cause chain says more precisely what went wrong.
+    pub fn from_anyhow(error: anyhow::Error, fallback: ErrorCategory) -> Self {
+        for cause in error.chain() {
+            if let Some(pg_error) = cause.downcast_ref::<tokio_postgres::Error>() {
+                return Self::from_postgres(pg_error);
+            }
+            if let Some(tunnel_error) = cause.downcast_ref::<TunnelError>() {
+                return Self::new(ErrorCategory::Tunnel, tunnel_error.to_string());
+            }
+            if cause.downcast_ref::<keyring::Error>().is_some() {
+                return Self::new(ErrorCategory::Keyring, format!("{:#}", error));
+            }
+            if cause.downcast_ref::<native_tls::Error>().is_some() {
+                return Self::new(ErrorCategory::Tls, format!("{:#}", error));
+            }
+        }
+        Self::new(fallback, format!("{:#}", error))
+    }
+
+    fn from_postgres(error: &tokio_postgres::Error) -> Self {
+        if let Some(db) = error.as_db_error() {
+            return Self {
+                category: category_for(db.code()),
+                message: db.message().to_string(),
+                db: Some(Box::new(DbErrorDetails::from(db))),
+            };
+        }
+
+        let category = if tls::is_tls_error(error) {
+            ErrorCategory::Tls
+        } else if error.to_string().starts_with("error deserializing")
+            || error.to_string().starts_with("error serializing")
+        {
+            ErrorCategory::Internal
+        } else {
+            // Everything else the driver reports without a server error is
+            // I/O, protocol or connection setup trouble
+            ErrorCategory::Connection
+        };
+        let message = match std::error::Error::source(error) {
+            Some(source) if !error.to_string().contains(&source.to_string()) => {
+                format!("{}: {}", error, source)
+            }
+            _ => error.to_string(),
+        };
+        Self::new(category, message)
+    }
+
+    /// Prefixes the message with what was being attempted, e.g. "Failed to
+    /// query tables". Server-reported fields are left untouched.
+    pub fn context(mut self, context: &str) -> Self {
+        self.message = format!("{}: {}", context, self.message);
+        self
+    }
+
+    /// Moves the reported position by `delta` characters, e.g. to map it
+    /// from a generated `EXPLAIN ...` statement back onto the user's text, or
+    /// from one statement onto the whole script. Positions that fall outside
+    /// the user's text are dropped.
+    pub fn shift_position(mut self, delta: i64) -> Self {
+        if let Some(db) = &mut self.db {
+            db.position = db
+                .position
+                .and_then(|p| u32::try_from(i64::from(p) + delta).ok())
+                .filter(|p| *p > 0);
+        }
+        self
+    }
+
+    /// Like `shift_position`, for SQL that was rewritten in more than one
+    /// place before it was sent.
+    pub fn map_position(mut self, map: impl Fn(u32) -> u32) -> Self {
+        if let Some(db) = &mut self.db {
+            db.position = db.position.map(map);
+        }
+        self
+    }
+}
+
+impl From<tokio_postgres::Error> for AppError {
+    fn from(error: tokio_postgres::Error) -> Self {
+        Self::from_postgres(&error)
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::from_anyhow(error, ErrorCategory::Internal)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<&DbError> for DbErrorDetails {
+    fn from(db: &DbError) -> Self {
+        let (position, internal_position, internal_query) = match db.position() {
+            Some(ErrorPosition::Original(position)) => (Some(*position), None, None),
+            Some(ErrorPosition::Internal { position, query }) => {
+                (None, Some(*position), Some(query.clone()))
+            }
+            None => (None, None, None),
+        };
+        Self {
+            code: db.code().code().to_string(),
+            severity: db.severity().to_string(),
+            message: db.message().to_string(),
+            detail: db.detail().map(str::to_string),
+            hint: db.hint().map(str::to_string),
+            position,
+            internal_position,
+            internal_query,
+            where_: db.where_().map(str::to_string),
+            schema: db.schema().map(str::to_string),
+            table: db.table().map(str::to_string),
+            column: db.column().map(str::to_string),
+            datatype: db.datatype().map(str::to_string),
+            constraint: db.constraint().map(str::to_string),
+        }
+    }
+}
+
+fn category_for(code: &SqlState) -> ErrorCategory {
+    let code = code.code();
+    match code {
+        "28000" | "28P01" => ErrorCategory::Auth,
+        "57014" => ErrorCategory::Canceled,
+        // Unknown database, too many connections, server shutting down
+        "3D000" | "53300" | "57P01" | "57P02" | "57P03" => ErrorCategory::Connection,
+        _ if code.starts_with("08") => ErrorCategory::Connection,
+        _ => ErrorCategory::Database,
+    }
+}
+
+/// `anyhow`-style context for results headed back to the frontend.
+pub trait ErrorContext<T> {
+    fn context(self, context: &str) -> Result<T, AppError>;
+}
+
+impl<T, E: Into<AppError>> ErrorContext<T> for Result<T, E> {
+    fn context(self, context: &str) -> Result<T, AppError> {
+        self.map_err(|e| e.into().context(context))
+    }
+}
@@ -3,27 +3,78 @@
 
 mod commands;
 mod db;
+mod error;
 mod models;
 mod security;
+mod state;
+mod storage;
 
+use state::AppState;
+use tauri::Manager;
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .setup(|app| {
+            let config_dir = app.path().app_config_dir()?;
+            app.manage(AppState::load(&config_dir)?);
+
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    tokio::time::sleep(db::results::IDLE_SWEEP_INTERVAL).await;
+                    handle.state::<AppState>().open_results.close_idle();
+                }
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::connection::test_connection,
             commands::connection::save_connection,
             commands::connection::get_connections,
             commands::connection::get_connection_password,
             commands::connection::delete_connection,
+            commands::connection::disconnect_connection,
+            commands::connection::get_pool_stats,
             commands::query::execute_query,
+            commands::query::execute_script,
             commands::query::explain_query,
+            commands::query::compare_plans,
+            commands::query::cancel_query,
+            commands::query::fetch_more,
+            commands::query::close_result,
+            commands::session::open_session,
+            commands::session::get_session,
+            commands::session::close_session,
+            commands::session::begin_transaction,
+            commands::session::commit_transaction,
+            commands::session::rollback_transaction,
+            commands::session::create_savepoint,
+            commands::session::rollback_to_savepoint,
+            commands::session::release_savepoint,
+            commands::session::set_auto_commit,
             commands::table::get_table_schema_cmd,
+            commands::table::get_table_details_cmd,
             commands::table::get_table_data,
             commands::table::update_table_data,
             commands::table::get_schemas,
             commands::table::get_tables,
+            commands::objects::get_functions,
+            commands::objects::get_sequences,
+            commands::objects::get_types,
+            commands::objects::get_extensions,
+            commands::objects::get_ddl,
             commands::erd::get_erd_data,
+            commands::settings::get_settings,
+            commands::settings::update_settings,
+            commands::history::search_history,
+            commands::history::prune_history,
+            commands::library::get_saved_queries,
+            commands::library::save_query,
+            commands::library::delete_saved_query,
+            commands::library::export_saved_queries,
+            commands::library::import_saved_queries,
+            commands::library::get_query_parameters,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,4 +1,6 @@
+use crate::error::AppError;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ConnectionConfig {
@@ -9,27 +11,649 @@ pub struct ConnectionConfig {
     pub database: String,
     pub username: String,
     // Password is stored encrypted, not in this struct
+    #[serde(default)]
+    pub ssl_mode: SslMode,
+    /// PEM file with the CA certificate(s) used to verify the server.
+    #[serde(default)]
+    pub ssl_root_cert: Option<String>,
+    /// PEM client certificate for certificate authentication.
+    #[serde(default)]
+    pub ssl_client_cert: Option<String>,
+    /// PKCS#8 PEM private key matching `ssl_client_cert`.
+    #[serde(default)]
+    pub ssl_client_key: Option<String>,
+    /// Reach the server through a bastion host instead of connecting directly.
+    #[serde(default)]
+    pub ssh_tunnel: Option<SshTunnelConfig>,
+}
+
+/// Mirrors libpq's `sslmode` values.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    #[default]
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SshTunnelConfig {
+    pub host: String,
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    pub username: String,
+    pub auth: SshAuthMethod,
+    /// Private key file, used when `auth` is `private-key`. The password or
+    /// key passphrase is stored in the keyring, never here.
+    #[serde(default)]
+    pub private_key_path: Option<String>,
+    /// Host key the user confirmed for a bastion that is not in
+    /// `~/.ssh/known_hosts`, as `SHA256:<base64>` like `ssh-keygen -l` prints.
+    #[serde(default)]
+    pub host_key_fingerprint: Option<String>,
+}
+
+fn default_ssh_port() -> u16 {
+    22
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SshAuthMethod {
+    Password,
+    PrivateKey,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct QueryResult {
     pub columns: Vec<String>,
+    /// PostgreSQL type name of each column, e.g. `int4`, `timestamptz`, `_text`.
+    pub column_types: Vec<String>,
     pub rows: Vec<Vec<serde_json::Value>>,
     pub row_count: usize,
+    /// `[row, column]` of cells whose type has no decoder. Those cells hold
+    /// the raw value as a `\x` hex string; SQL NULL is always `null`.
+    pub undecodable_cells: Vec<[usize; 2]>,
+    /// Handle for `fetch_more` while the result has rows left on the server.
+    pub result_id: Option<String>,
+    pub has_more: bool,
+    /// The result was cut short by the `max_result_rows` setting.
+    pub truncated: bool,
+}
+
+/// A page of table rows from `get_table_data`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableData {
+    #[serde(flatten)]
+    pub result: QueryResult,
+    /// Rows matching the filters across all pages.
+    pub total_rows: u64,
+    /// `total_rows` is the planner's estimate; the match was too large to
+    /// count exactly.
+    pub total_is_estimate: bool,
+    pub paging: PagingMode,
+    /// Pass back as `cursor` for the next page; unset on the last page.
+    pub next_cursor: Option<String>,
+    /// Pass back as `cursor` for the previous page; unset on the first page.
+    pub prev_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PagingMode {
+    /// Pages follow `next_cursor`/`prev_cursor`; `offset` is ignored.
+    Keyset,
+    /// The table has no row identity to page by, so pages use `offset`.
+    Offset,
+}
+
+/// Rows returned by `fetch_more`. Cell positions in `undecodable_cells` are
+/// relative to this page.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResultPage {
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub row_count: usize,
+    pub undecodable_cells: Vec<[usize; 2]>,
+    pub has_more: bool,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum StatementStatus {
+    Ok,
+    Error,
+    /// Not run because an earlier statement failed or the script was
+    /// canceled.
+    Skipped,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatementResult {
+    pub sql: String,
+    /// 1-based line in the script where the statement starts.
+    pub line: usize,
+    pub status: StatementStatus,
+    /// e.g. `INSERT 0 3` or `CREATE TABLE`.
+    pub command_tag: Option<String>,
+    pub rows_affected: Option<u64>,
+    /// Rows, for statements that return them (`SELECT`, `... RETURNING`).
+    pub result: Option<QueryResult>,
+    pub duration_ms: f64,
+    /// Error positions are relative to the whole script.
+    pub error: Option<AppError>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScriptResult {
+    pub statements: Vec<StatementResult>,
+    /// The script left a transaction open; it was rolled back before the
+    /// connection went back to the pool.
+    pub rolled_back_open_transaction: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExplainResult {
+    /// The plan as `FORMAT TEXT` prints it.
+    pub text: Option<String>,
+    /// The plan parsed from `FORMAT JSON`.
+    pub plan: Option<QueryPlan>,
+    /// ANALYZE executed the statement inside a transaction or savepoint that
+    /// was then rolled back, undoing its changes.
+    pub rolled_back: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueryPlan {
+    pub root: PlanNode,
+    pub planning_time_ms: Option<f64>,
+    /// Set with ANALYZE.
+    pub execution_time_ms: Option<f64>,
+    /// Settings that differ from their defaults, with SETTINGS.
+    pub settings: Map<String, Value>,
+    pub triggers: Vec<TriggerTiming>,
+    /// Likely problems, most important first.
+    pub findings: Vec<PlanFinding>,
+    /// The JSON plan as the server returned it, for other plan tools.
+    pub raw: Value,
+}
+
+/// One node of a plan tree. Actual figures are set with ANALYZE and are
+/// per loop, as Postgres reports them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanNode {
+    /// Position in a depth-first walk of the tree, starting at 0 for the
+    /// root. Findings refer to nodes by id.
+    pub id: usize,
+    pub node_type: String,
+    /// How the node feeds its parent, e.g. `Outer`, `Inner` or `InitPlan`.
+    pub parent_relationship: Option<String>,
+    pub relation_name: Option<String>,
+    pub schema: Option<String>,
+    pub alias: Option<String>,
+    pub index_name: Option<String>,
+    pub join_type: Option<String>,
+    pub startup_cost: f64,
+    pub total_cost: f64,
+    pub plan_rows: f64,
+    pub plan_width: i64,
+    pub actual_startup_time_ms: Option<f64>,
+    pub actual_total_time_ms: Option<f64>,
+    pub actual_rows: Option<f64>,
+    pub actual_loops: Option<f64>,
+    pub rows_removed_by_filter: Option<f64>,
+    /// Time spent in this node itself over all loops, without its children.
+    pub exclusive_time_ms: Option<f64>,
+    /// Actual rows divided by estimated rows; above 1 the planner
+    /// underestimated.
+    pub estimate_factor: Option<f64>,
+    pub filter: Option<String>,
+    pub index_cond: Option<String>,
+    /// Output columns, with VERBOSE.
+    pub output: Vec<String>,
+    /// Block counts, with BUFFERS.
+    pub buffers: Option<BufferUsage>,
+    /// WAL generated, with WAL.
+    pub wal: Option<WalUsage>,
+    /// Remaining properties, keyed as Postgres names them.
+    pub details: Map<String, Value>,
+    pub children: Vec<PlanNode>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct BufferUsage {
+    pub shared_hit: i64,
+    pub shared_read: i64,
+    pub shared_dirtied: i64,
+    pub shared_written: i64,
+    pub local_hit: i64,
+    pub local_read: i64,
+    pub local_dirtied: i64,
+    pub local_written: i64,
+    pub temp_read: i64,
+    pub temp_written: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct WalUsage {
+    pub records: i64,
+    pub full_page_images: i64,
+    pub bytes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FindingKind {
+    /// A node accounting for a large share of the execution time.
+    Hotspot,
+    Misestimate,
+    SeqScanOnLargeTable,
+    /// A sort, hash or aggregate that ran out of `work_mem`.
+    DiskSpill,
+    NestedLoopWithManyLoops,
+    /// A filter discarding most of the rows the node read.
+    FilterRemovesMostRows,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum FindingSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanFinding {
+    pub node_id: usize,
+    pub kind: FindingKind,
+    pub severity: FindingSeverity,
+    pub message: String,
+    /// Share of the execution time spent in the node itself, from 0 to 1.
+    pub time_share: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TriggerTiming {
+    pub name: String,
+    pub relation: Option<String>,
+    pub time_ms: f64,
+    pub calls: f64,
+}
+
+/// A figure in two plans.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct PlanDelta {
+    pub before: f64,
+    pub after: f64,
+    /// `after - before`.
+    pub change: f64,
+    /// `after / before`, unless `before` is zero.
+    pub ratio: Option<f64>,
+}
+
+/// A node found in both plans, possibly as a different node type.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MatchedPlanNode {
+    pub before_id: usize,
+    pub after_id: usize,
+    pub before_node_type: String,
+    pub after_node_type: String,
+    pub node_type_changed: bool,
+    pub relation_name: Option<String>,
+    pub total_cost: PlanDelta,
+    pub plan_rows: PlanDelta,
+    /// Set when both plans were run with ANALYZE, as are the times.
+    pub actual_rows: Option<PlanDelta>,
+    /// Time in the node and its children over all loops.
+    pub actual_time_ms: Option<PlanDelta>,
+    pub exclusive_time_ms: Option<PlanDelta>,
+}
+
+/// A node with no counterpart in the other plan.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnmatchedPlanNode {
+    pub id: usize,
+    /// `None` for the root.
+    pub parent_id: Option<usize>,
+    pub node_type: String,
+    pub relation_name: Option<String>,
+    pub total_cost: f64,
+    pub plan_rows: f64,
+    pub actual_time_ms: Option<f64>,
+}
+
+/// Structural diff of two plans of the same query. Node ids refer to
+/// `before` and `after`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanComparison {
+    pub before: QueryPlan,
+    pub after: QueryPlan,
+    pub total_cost: PlanDelta,
+    pub planning_time_ms: Option<PlanDelta>,
+    pub execution_time_ms: Option<PlanDelta>,
+    /// In `before` pre-order.
+    pub matched: Vec<MatchedPlanNode>,
+    pub only_in_before: Vec<UnmatchedPlanNode>,
+    pub only_in_after: Vec<UnmatchedPlanNode>,
+}
+
+/// Transaction state of a session's pinned connection.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransactionStatus {
+    Idle,
+    InTransaction,
+    /// A statement failed inside the transaction; the server ignores
+    /// everything but a rollback until it is resolved.
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub connection_id: String,
+    pub status: TransactionStatus,
+    pub auto_commit: bool,
+    /// Savepoints set with `create_savepoint`, oldest first.
+    pub savepoints: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TableSchema {
     pub name: String,
     pub columns: Vec<ColumnInfo>,
+    /// Columns `update_table_data` uses to address rows. Without one, rows
+    /// can only be inserted.
+    pub row_identity: Option<RowIdentity>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RowIdentityKind {
+    PrimaryKey,
+    UniqueKey,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RowIdentity {
+    pub kind: RowIdentityKind,
+    pub columns: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ColumnInfo {
     pub name: String,
+    /// The `information_schema` type, e.g. `ARRAY` or `USER-DEFINED`.
     pub data_type: String,
+    /// The type as Postgres prints it, e.g. `integer[]`, `varchar(40)` or
+    /// `public.mood`.
+    pub formatted_type: String,
+    pub type_oid: u32,
+    pub max_length: Option<i32>,
+    pub numeric_precision: Option<i32>,
+    pub numeric_scale: Option<i32>,
     pub is_nullable: bool,
     pub default_value: Option<String>,
+    pub identity: Option<IdentityKind>,
+    /// Expression of a generated column.
+    pub generation_expression: Option<String>,
+    /// Set when the column doesn't use its type's default collation.
+    pub collation: Option<String>,
+    pub comment: Option<String>,
+    pub is_primary_key: bool,
+    /// Part of a unique constraint, alone or with other columns.
+    pub is_unique: bool,
+    pub is_foreign_key: bool,
+}
+
+/// `TableSchema` plus the indexes, constraints and triggers defined on the
+/// table.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableDetails {
+    #[serde(flatten)]
+    pub schema: TableSchema,
+    pub indexes: Vec<IndexInfo>,
+    pub constraints: Vec<ConstraintInfo>,
+    pub triggers: Vec<TriggerInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexInfo {
+    pub name: String,
+    /// Access method, e.g. `btree` or `gin`.
+    pub method: String,
+    /// Key columns or expressions, in index order.
+    pub columns: Vec<String>,
+    pub is_primary: bool,
+    pub is_unique: bool,
+    /// Has a `WHERE` predicate.
+    pub is_partial: bool,
+    /// False while a concurrent build is in progress or after it failed; the
+    /// planner doesn't use invalid indexes.
+    pub is_valid: bool,
+    /// The constraint the index enforces, if any.
+    pub constraint: Option<String>,
+    /// `CREATE INDEX` statement from `pg_get_indexdef`.
+    pub definition: String,
+    pub size_bytes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConstraintKind {
+    PrimaryKey,
+    Unique,
+    ForeignKey,
+    Check,
+    Exclusion,
+    Trigger,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConstraintInfo {
+    pub name: String,
+    pub kind: ConstraintKind,
+    pub columns: Vec<String>,
+    /// Body of the constraint from `pg_get_constraintdef`, e.g.
+    /// `FOREIGN KEY (user_id) REFERENCES users(id)`.
+    pub definition: String,
+    /// False for constraints added `NOT VALID` that existing rows haven't
+    /// been checked against yet.
+    pub is_validated: bool,
+    pub is_deferrable: bool,
+    pub is_initially_deferred: bool,
+    /// Table a foreign key points at.
+    pub referenced_table: Option<String>,
+    /// Size of the index backing a primary key, unique or exclusion
+    /// constraint.
+    pub size_bytes: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TriggerInfo {
+    pub name: String,
+    /// Trigger function with its signature.
+    pub function: String,
+    pub is_enabled: bool,
+    /// Created by `CREATE CONSTRAINT TRIGGER`.
+    pub is_constraint: bool,
+    /// `CREATE TRIGGER` statement from `pg_get_triggerdef`.
+    pub definition: String,
+}
+
+/// How an identity column gets its values.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdentityKind {
+    Always,
+    ByDefault,
+}
+
+/// `pg_class.relkind` of the relations that can be listed.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RelationKind {
+    Table,
+    PartitionedTable,
+    View,
+    MaterializedView,
+    ForeignTable,
+    Sequence,
+}
+
+impl RelationKind {
+    pub const ALL: [RelationKind; 6] = [
+        RelationKind::Table,
+        RelationKind::PartitionedTable,
+        RelationKind::View,
+        RelationKind::MaterializedView,
+        RelationKind::ForeignTable,
+        RelationKind::Sequence,
+    ];
+
+    pub fn relkind(self) -> &'static str {
+        match self {
+            RelationKind::Table => "r",
+            RelationKind::PartitionedTable => "p",
+            RelationKind::View => "v",
+            RelationKind::MaterializedView => "m",
+            RelationKind::ForeignTable => "f",
+            RelationKind::Sequence => "S",
+        }
+    }
+
+    pub fn from_relkind(relkind: &str) -> Option<RelationKind> {
+        RelationKind::ALL
+            .into_iter()
+            .find(|kind| kind.relkind() == relkind)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableInfo {
+    pub name: String,
+    pub schema: String,
+    pub kind: RelationKind,
+    pub owner: String,
+    /// Planner estimate from `pg_class.reltuples`; unset for views,
+    /// sequences and tables that were never vacuumed or analyzed.
+    pub estimated_rows: Option<i64>,
+    pub comment: Option<String>,
+    /// A partition of a partitioned table.
+    pub is_partition: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum FunctionKind {
+    Function,
+    Procedure,
+    Aggregate,
+    Window,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Volatility {
+    Immutable,
+    Stable,
+    Volatile,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FunctionInfo {
+    pub schema: String,
+    pub name: String,
+    pub kind: FunctionKind,
+    /// Argument list with modes, names and defaults, e.g.
+    /// `a integer, VARIADIC b text[] DEFAULT '{}'`.
+    pub arguments: String,
+    /// Argument types only, which together with the name identify the
+    /// function among its overloads.
+    pub identity_arguments: String,
+    /// Unset for procedures.
+    pub return_type: Option<String>,
+    pub language: String,
+    pub volatility: Volatility,
+    pub is_security_definer: bool,
+    pub owner: String,
+    pub comment: Option<String>,
+    /// The extension the function belongs to.
+    pub extension: Option<String>,
+    /// `CREATE OR REPLACE` statement from `pg_get_functiondef`; unset for
+    /// aggregates.
+    pub definition: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SequenceInfo {
+    pub schema: String,
+    pub name: String,
+    pub data_type: String,
+    pub start_value: i64,
+    pub increment_by: i64,
+    pub min_value: i64,
+    pub max_value: i64,
+    pub cache_size: i64,
+    pub cycle: bool,
+    /// Value last handed out; unset before the first `nextval` or without
+    /// privileges on the sequence.
+    pub last_value: Option<i64>,
+    /// `table.column` of the column that owns the sequence, e.g. a serial.
+    pub owned_by: Option<String>,
+    pub owner: String,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TypeKind {
+    Enum,
+    Composite,
+    Domain,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TypeAttribute {
+    pub name: String,
+    pub data_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TypeInfo {
+    pub schema: String,
+    pub name: String,
+    pub kind: TypeKind,
+    /// Enum labels in sort order.
+    pub labels: Vec<String>,
+    /// Composite type fields.
+    pub attributes: Vec<TypeAttribute>,
+    /// Underlying type of a domain.
+    pub base_type: Option<String>,
+    /// Domain `NOT NULL`.
+    pub not_null: bool,
+    /// Domain default.
+    pub default_value: Option<String>,
+    /// Domain `CHECK` constraints.
+    pub constraints: Vec<String>,
+    pub owner: String,
+    pub comment: Option<String>,
+    pub extension: Option<String>,
+}
+
+/// An extension the server can install, installed or not.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtensionInfo {
+    pub name: String,
+    pub installed_version: Option<String>,
+    pub default_version: Option<String>,
+    /// Schema an installed extension's objects live in.
+    pub schema: Option<String>,
+    pub comment: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -38,6 +662,7 @@ pub struct ERDNode {
     pub label: String,
     pub schema: String,
     pub table: String,
+    pub kind: RelationKind,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
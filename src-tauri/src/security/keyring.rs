@@ -20,3 +20,27 @@ pub fn delete_password(connection_id: &str) -> Result<()> {
     entry.delete_password()?;
     Ok(())
 }
+
+/// The SSH password or private key passphrase lives under its own keyring
+/// entry so it can change independently of the database password.
+fn ssh_account(connection_id: &str) -> String {
+    format!("{}:ssh", connection_id)
+}
+
+pub fn save_ssh_secret(connection_id: &str, secret: &str) -> Result<()> {
+    save_password(&ssh_account(connection_id), secret)
+}
+
+/// Returns `None` when no secret has been stored, e.g. for an unencrypted key.
+pub fn get_ssh_secret(connection_id: &str) -> Result<Option<String>> {
+    let entry = Entry::new(SERVICE_NAME, &ssh_account(connection_id))?;
+    match entry.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub fn delete_ssh_secret(connection_id: &str) -> Result<()> {
+    delete_password(&ssh_account(connection_id))
+}
@@ -0,0 +1,91 @@
+use crate::db::cancel::RunningQueries;
+use crate::db::pool::{ConnectionPool, PoolManager, PooledClient};
+use crate::db::results::OpenResults;
+use crate::db::session::Sessions;
+use crate::error::{AppError, ErrorCategory, ErrorContext};
+use crate::security::keyring;
+use crate::storage::connections::ConnectionStore;
+use crate::storage::history::HistoryStore;
+use crate::storage::library::LibraryStore;
+use crate::storage::settings::SettingsStore;
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Everything commands share, registered once with Tauri and injected through
+/// `tauri::State`.
+pub struct AppState {
+    pub pool_manager: PoolManager,
+    pub connections: ConnectionStore,
+    pub settings: SettingsStore,
+    pub history: HistoryStore,
+    pub library: LibraryStore,
+    pub running_queries: RunningQueries,
+    pub open_results: OpenResults,
+    pub sessions: Sessions,
+}
+
+impl AppState {
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        Ok(Self {
+            pool_manager: PoolManager::new(),
+            connections: ConnectionStore::load(config_dir)?,
+            settings: SettingsStore::load(config_dir)?,
+            history: HistoryStore::open(config_dir),
+            library: LibraryStore::load(config_dir)?,
+            running_queries: RunningQueries::default(),
+            open_results: OpenResults::default(),
+            sessions: Sessions::default(),
+        })
+    }
+
+    /// Looks up a saved connection and its secrets, and checks out a client
+    /// for it from the shared pool.
+    pub async fn client(&self, connection_id: &str) -> Result<PooledClient, AppError> {
+        self.pool(connection_id)
+            .await?
+            .get()
+            .await
+            .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Connection))
+    }
+
+    /// Like `client`, but opens a client outside the pool's limit, for a
+    /// session to pin.
+    pub async fn dedicated_client(&self, connection_id: &str) -> Result<PooledClient, AppError> {
+        self.pool(connection_id)
+            .await?
+            .dedicated()
+            .await
+            .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Connection))
+    }
+
+    async fn pool(&self, connection_id: &str) -> Result<Arc<ConnectionPool>, AppError> {
+        let config = self
+            .connections
+            .get(connection_id)
+            .await
+            .ok_or_else(|| AppError::not_found("Connection not found"))?;
+
+        let password =
+            keyring::get_password(connection_id).context("Failed to get password")?;
+        let ssh_secret = match &config.ssh_tunnel {
+            Some(_) => {
+                keyring::get_ssh_secret(connection_id).context("Failed to get SSH secret")?
+            }
+            None => None,
+        };
+
+        let pool_settings = self.settings.get().await.pool;
+
+        self.pool_manager
+            .pool(
+                connection_id,
+                &config,
+                &password,
+                ssh_secret.as_deref(),
+                &pool_settings,
+            )
+            .await
+            .map_err(|e| AppError::from_anyhow(e, ErrorCategory::Connection))
+    }
+}
@@ -0,0 +1,454 @@
+use crate::models::ConnectionConfig;
+use crate::storage::{set_aside, write_atomic};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+const FILE_NAME: &str = "connections.json";
+
+/// Migration steps for the on-disk format; entry `n` upgrades a version `n + 1`
+/// file to version `n + 2`. Append a step whenever `ConnectionConfig` changes
+/// shape.
+const MIGRATIONS: &[fn(&mut Value)] = &[add_ssl_mode, add_ssh_tunnel, add_host_key_fingerprint];
+
+/// Version of the on-disk format written by this build.
+const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ConnectionsFile {
+    version: u32,
+    connections: Vec<ConnectionConfig>,
+}
+
+/// Saved connections, backed by a JSON file in the app config directory.
+/// Passwords never touch this file; they live in the system keyring.
+pub struct ConnectionStore {
+    path: PathBuf,
+    connections: RwLock<Vec<ConnectionConfig>>,
+    /// Set when the file was written by a newer build. Its connections are
+    /// usable, but saving would drop whatever this build doesn't know about.
+    newer_version: Option<u32>,
+}
+
+impl ConnectionStore {
+    /// Loads the store from `config_dir`, migrating older files in place.
+    /// A missing file yields an empty store, and so does one that cannot be
+    /// parsed, after it is moved aside. A file from a newer build is loaded
+    /// read-only.
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = config_dir.join(FILE_NAME);
+
+        let (connections, newer_version) = if path.exists() {
+            match read_file(&path)? {
+                FileContents::Current(connections) => (connections, None),
+                FileContents::Newer(version, connections) => {
+                    eprintln!(
+                        "{} is from a newer version of the app (format {}); \
+                         saved connections can't be changed",
+                        path.display(),
+                        version
+                    );
+                    (connections, Some(version))
+                }
+                FileContents::Unparsable(e) => {
+                    let backup = set_aside(&path)?;
+                    eprintln!(
+                        "{:#}; moved it to {} and starting with no saved connections",
+                        e,
+                        backup.display()
+                    );
+                    (Vec::new(), None)
+                }
+            }
+        } else {
+            (Vec::new(), None)
+        };
+
+        Ok(Self {
+            path,
+            connections: RwLock::new(connections),
+            newer_version,
+        })
+    }
+
+    pub async fn list(&self) -> Vec<ConnectionConfig> {
+        self.connections.read().await.clone()
+    }
+
+    pub async fn get(&self, id: &str) -> Option<ConnectionConfig> {
+        self.connections
+            .read()
+            .await
+            .iter()
+            .find(|c| c.id == id)
+            .cloned()
+    }
+
+    /// Inserts or replaces a connection and persists the result. The in-memory
+    /// list is only updated once the file has been written.
+    pub async fn upsert(&self, config: ConnectionConfig) -> Result<()> {
+        self.check_writable()?;
+        let mut conns = self.connections.write().await;
+
+        let mut updated = conns.clone();
+        if let Some(existing) = updated.iter_mut().find(|c| c.id == config.id) {
+            *existing = config;
+        } else {
+            updated.push(config);
+        }
+
+        write_file(&self.path, &updated)?;
+        *conns = updated;
+        Ok(())
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<()> {
+        self.check_writable()?;
+        let mut conns = self.connections.write().await;
+
+        let mut updated = conns.clone();
+        updated.retain(|c| c.id != id);
+
+        write_file(&self.path, &updated)?;
+        *conns = updated;
+        Ok(())
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        match self.newer_version {
+            Some(version) => bail!(
+                "Saved connections are from a newer version of the app (format {}); \
+                 update it to change them",
+                version
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What the connections file turned out to hold.
+enum FileContents {
+    /// Connections in the current format, upgraded if need be.
+    Current(Vec<ConnectionConfig>),
+    /// Connections from a file written by a newer build.
+    Newer(u32, Vec<ConnectionConfig>),
+    /// Not a connections file this build understands.
+    Unparsable(anyhow::Error),
+}
+
+/// Reads and, if it is older than `CURRENT_VERSION`, upgrades the file at
+/// `path`. Failing to read the file, or to make sense of one from a newer
+/// build, is an error; failing to write back the upgrade is not, since the
+/// next save tries again.
+fn read_file(path: &Path) -> Result<FileContents> {
+    let raw =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let unparsable = |e: anyhow::Error| {
+        FileContents::Unparsable(e.context(format!("Failed to parse {}", path.display())))
+    };
+    let parsed = serde_json::from_str(&raw)
+        .map_err(anyhow::Error::from)
+        .and_then(|value: Value| Ok((file_version(&value)?, value)));
+    let (version, value) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return Ok(unparsable(e)),
+    };
+
+    if version > CURRENT_VERSION {
+        let file: ConnectionsFile = serde_json::from_value(value).with_context(|| {
+            format!(
+                "{} is from a newer version of the app (format {}) and can't be read",
+                path.display(),
+                version
+            )
+        })?;
+        return Ok(FileContents::Newer(version, file.connections));
+    }
+
+    let file: ConnectionsFile = match serde_json::from_value(migrate(value, version)) {
+        Ok(file) => file,
+        Err(e) => return Ok(unparsable(e.into())),
+    };
+    if version < CURRENT_VERSION {
+        if let Err(e) = write_file(path, &file.connections) {
+            eprintln!("{:#}; keeping the version {} file until the next save", e, version);
+        }
+    }
+    Ok(FileContents::Current(file.connections))
+}
+
+fn file_version(value: &Value) -> Result<u32> {
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| anyhow!("Connections file has no version"))?;
+    let version = u32::try_from(version)?;
+
+    if version == 0 {
+        bail!("Connections file version 0 is not supported");
+    }
+    Ok(version)
+}
+
+/// Upgrades a parsed connections file one version at a time until it matches
+/// `CURRENT_VERSION`.
+fn migrate(mut value: Value, from: u32) -> Value {
+    for version in from..CURRENT_VERSION {
+        let step = MIGRATIONS[(version - 1) as usize];
+        step(&mut value);
+        value["version"] = Value::from(version + 1);
+    }
+    value
+}
+
+/// v1 -> v2: connections gained TLS settings. Spell out the libpq default so
+/// the file reflects what the app will actually do.
+fn add_ssl_mode(value: &mut Value) {
+    for_each_connection(value, |conn| {
+        conn.entry("ssl_mode").or_insert_with(|| Value::from("prefer"));
+    });
+}
+
+/// v2 -> v3: connections gained an optional SSH tunnel. Bumping the version
+/// keeps older builds from loading (and then dropping) tunnel settings.
+fn add_ssh_tunnel(value: &mut Value) {
+    for_each_connection(value, |conn| {
+        conn.entry("ssh_tunnel").or_insert(Value::Null);
+    });
+}
+
+/// v3 -> v4: tunnels gained a pinned host key fingerprint, which older
+/// builds would drop on save.
+fn add_host_key_fingerprint(value: &mut Value) {
+    for_each_connection(value, |conn| {
+        if let Some(tunnel) = conn.get_mut("ssh_tunnel").and_then(Value::as_object_mut) {
+            tunnel.entry("host_key_fingerprint").or_insert(Value::Null);
+        }
+    });
+}
+
+fn for_each_connection(value: &mut Value, mut f: impl FnMut(&mut serde_json::Map<String, Value>)) {
+    if let Some(connections) = value.get_mut("connections").and_then(Value::as_array_mut) {
+        for conn in connections.iter_mut().filter_map(Value::as_object_mut) {
+            f(conn);
+        }
+    }
+}
+
+fn write_file(path: &Path, connections: &[ConnectionConfig]) -> Result<()> {
+    let file = ConnectionsFile {
+        version: CURRENT_VERSION,
+        connections: connections.to_vec(),
+    };
+    let json = serde_json::to_vec_pretty(&file)?;
+    write_atomic(path, &json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SshAuthMethod, SslMode};
+    use serde_json::json;
+
+    /// A fresh config directory, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let path = std::env::temp_dir().join(format!("connections-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn write(&self, contents: &Value) {
+            fs::write(self.0.join(FILE_NAME), contents.to_string()).unwrap();
+        }
+
+        fn read(&self) -> Value {
+            serde_json::from_str(&fs::read_to_string(self.0.join(FILE_NAME)).unwrap()).unwrap()
+        }
+
+        /// Contents of the files moved aside so far, sorted.
+        fn backups(&self) -> Vec<String> {
+            let mut backups: Vec<String> = fs::read_dir(&self.0)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "bak"))
+                .map(|path| fs::read_to_string(path).unwrap())
+                .collect();
+            backups.sort();
+            backups
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn connection(extra: Value) -> Value {
+        let mut connection = json!({
+            "id": "1",
+            "name": "local",
+            "host": "localhost",
+            "port": 5432,
+            "database": "postgres",
+            "username": "postgres"
+        });
+        if let (Some(connection), Value::Object(extra)) = (connection.as_object_mut(), extra) {
+            connection.extend(extra);
+        }
+        connection
+    }
+
+    #[tokio::test]
+    async fn persists_saved_connections() {
+        let dir = TempDir::new();
+        let store = ConnectionStore::load(&dir.0).unwrap();
+        assert!(store.list().await.is_empty());
+
+        let config: ConnectionConfig = serde_json::from_value(connection(json!({}))).unwrap();
+        store.upsert(config.clone()).await.unwrap();
+        store
+            .upsert(ConnectionConfig {
+                name: "renamed".to_string(),
+                ..config
+            })
+            .await
+            .unwrap();
+
+        let reloaded = ConnectionStore::load(&dir.0).unwrap();
+        let connections = reloaded.list().await;
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].name, "renamed");
+        assert_eq!(dir.read()["version"], json!(CURRENT_VERSION));
+
+        reloaded.remove("1").await.unwrap();
+        assert!(ConnectionStore::load(&dir.0).unwrap().get("1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn migrates_a_v1_file() {
+        let dir = TempDir::new();
+        dir.write(&json!({ "version": 1, "connections": [connection(json!({}))] }));
+
+        let store = ConnectionStore::load(&dir.0).unwrap();
+        let connections = store.list().await;
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].ssl_mode, SslMode::Prefer);
+        assert!(connections[0].ssh_tunnel.is_none());
+
+        let file = dir.read();
+        assert_eq!(file["version"], json!(CURRENT_VERSION));
+        assert_eq!(file["connections"][0]["ssl_mode"], json!("prefer"));
+        assert_eq!(file["connections"][0]["ssh_tunnel"], Value::Null);
+    }
+
+    #[test]
+    fn migrates_tunnels_to_pinned_host_keys() {
+        let tunnel = json!({ "host": "bastion", "port": 22, "username": "me", "auth": "password" });
+        let v3 = json!({
+            "version": 3,
+            "connections": [connection(json!({ "ssl_mode": "require", "ssh_tunnel": tunnel }))]
+        });
+
+        let migrated = migrate(v3, 3);
+        assert_eq!(migrated["version"], json!(4));
+        assert_eq!(
+            migrated["connections"][0]["ssh_tunnel"]["host_key_fingerprint"],
+            Value::Null
+        );
+
+        let file: ConnectionsFile = serde_json::from_value(migrated).unwrap();
+        let config = &file.connections[0];
+        assert_eq!(config.ssl_mode, SslMode::Require);
+        let tunnel = config.ssh_tunnel.as_ref().unwrap();
+        assert_eq!(tunnel.auth, SshAuthMethod::Password);
+        assert!(tunnel.host_key_fingerprint.is_none());
+    }
+
+    #[test]
+    fn leaves_a_current_file_untouched() {
+        let dir = TempDir::new();
+        let current = json!({
+            "version": CURRENT_VERSION,
+            "connections": [connection(json!({ "ssl_mode": "disable" }))]
+        });
+        dir.write(&current);
+
+        ConnectionStore::load(&dir.0).unwrap();
+        assert_eq!(dir.read(), current);
+    }
+
+    #[tokio::test]
+    async fn sets_aside_an_unreadable_file() {
+        let dir = TempDir::new();
+        fs::write(dir.0.join(FILE_NAME), "{ not json").unwrap();
+
+        let store = ConnectionStore::load(&dir.0).unwrap();
+        assert!(store.list().await.is_empty());
+        assert!(!dir.0.join(FILE_NAME).exists());
+        assert_eq!(dir.backups(), ["{ not json"]);
+
+        // A later backup doesn't replace the earlier one
+        fs::write(dir.0.join(FILE_NAME), "{ also not json").unwrap();
+        ConnectionStore::load(&dir.0).unwrap();
+        assert_eq!(dir.backups(), ["{ also not json", "{ not json"]);
+    }
+
+    #[tokio::test]
+    async fn loads_a_newer_file_read_only() {
+        let dir = TempDir::new();
+        let newer = json!({
+            "version": CURRENT_VERSION + 1,
+            "connections": [connection(json!({ "ssl_mode": "require", "read_only": true }))]
+        });
+        dir.write(&newer);
+
+        let store = ConnectionStore::load(&dir.0).unwrap();
+        let connections = store.list().await;
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].ssl_mode, SslMode::Require);
+
+        assert!(store.upsert(connections[0].clone()).await.is_err());
+        assert!(store.remove("1").await.is_err());
+        assert_eq!(dir.read(), newer);
+        assert!(dir.backups().is_empty());
+    }
+
+    #[test]
+    fn fails_on_a_newer_file_it_cant_read() {
+        let dir = TempDir::new();
+        let newer = json!({ "version": CURRENT_VERSION + 1, "connections": { "1": {} } });
+        dir.write(&newer);
+
+        assert!(ConnectionStore::load(&dir.0).is_err());
+        assert_eq!(dir.read(), newer);
+        assert!(dir.backups().is_empty());
+    }
+
+    #[tokio::test]
+    async fn loads_a_file_whose_upgrade_cant_be_saved() {
+        let dir = TempDir::new();
+        let v1 = json!({ "version": 1, "connections": [connection(json!({}))] });
+        dir.write(&v1);
+        // Stands in the way of the temp file the upgrade is written to
+        fs::create_dir(dir.0.join("connections.tmp")).unwrap();
+
+        let store = ConnectionStore::load(&dir.0).unwrap();
+        assert_eq!(store.list().await.len(), 1);
+        assert_eq!(dir.read(), v1);
+        assert!(dir.backups().is_empty());
+    }
+
+    #[test]
+    fn rejects_files_without_a_version() {
+        assert!(file_version(&json!({ "connections": [] })).is_err());
+        assert!(file_version(&json!({ "version": 0 })).is_err());
+        assert_eq!(file_version(&json!({ "version": 1 })).unwrap(), 1);
+    }
+}
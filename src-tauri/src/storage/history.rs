@@ -0,0 +1,270 @@
+use crate::storage::set_aside;
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+const FILE_NAME: &str = "history.db";
+
+/// Schema steps; entry `n` upgrades a database at `user_version` `n` to
+/// `n + 1`. Append a step whenever the schema changes.
+const MIGRATIONS: &[&str] = &[r#"
+    CREATE TABLE query_history (
+        id INTEGER PRIMARY KEY,
+        connection_id TEXT NOT NULL,
+        sql TEXT NOT NULL,
+        started_at INTEGER NOT NULL,
+        duration_ms REAL NOT NULL,
+        row_count INTEGER,
+        succeeded INTEGER NOT NULL,
+        error_code TEXT,
+        error_message TEXT
+    );
+    CREATE INDEX query_history_started_at ON query_history (started_at);
+    CREATE INDEX query_history_connection ON query_history (connection_id, started_at);
+"#];
+
+/// Entries returned by one search unless the filter asks for more.
+const DEFAULT_SEARCH_LIMIT: u32 = 200;
+
+/// One statement run through `execute_query`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub connection_id: String,
+    pub sql: String,
+    /// Milliseconds since the Unix epoch.
+    pub started_at: i64,
+    pub duration_ms: f64,
+    /// Rows returned or affected. Unknown for results read only in part.
+    pub row_count: Option<i64>,
+    pub succeeded: bool,
+    /// SQLSTATE of a failure reported by the server.
+    pub error_code: Option<String>,
+    pub error_message: Option<String>,
+}
+
+/// Narrows a history search; unset fields match everything. Entries come
+/// back newest first.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct HistoryFilter {
+    /// Case-insensitive substring of the SQL text.
+    pub text: Option<String>,
+    pub connection_id: Option<String>,
+    /// Earliest start time, inclusive, in milliseconds since the epoch.
+    pub from: Option<i64>,
+    /// Latest start time, exclusive.
+    pub to: Option<i64>,
+    pub limit: Option<u32>,
+    pub offset: u32,
+}
+
+/// What `prune` deletes; both limits may be given.
+#[derive(Debug, Deserialize, Default)]
+#[serde(default)]
+pub struct PruneHistory {
+    /// Delete entries started before this time, in milliseconds since the
+    /// epoch.
+    pub before: Option<i64>,
+    /// Keep at most this many of the newest entries.
+    pub max_entries: Option<u32>,
+}
+
+/// History of executed statements, in an SQLite database in the app config
+/// directory. Calls block briefly on the database, which is only ever
+/// touched by this process.
+pub struct HistoryStore {
+    connection: Mutex<Connection>,
+}
+
+impl HistoryStore {
+    /// Opens the database in `config_dir`, creating or upgrading it. One
+    /// that can't be opened, e.g. because it is corrupt or from a newer
+    /// build, is moved aside to a `history.db.<timestamp>.bak` file and
+    /// started afresh; failing that, history is only kept in memory until
+    /// the app exits.
+    pub fn open(config_dir: &Path) -> Self {
+        let path = config_dir.join(FILE_NAME);
+        let connection = open_file(config_dir, &path)
+            .or_else(|e| {
+                eprintln!("{:#}; starting a new query history", e);
+                // The write-ahead log belongs to the old database
+                for file in [
+                    path.with_extension("db-wal"),
+                    path.with_extension("db-shm"),
+                    path.clone(),
+                ] {
+                    if file.exists() {
+                        set_aside(&file)?;
+                    }
+                }
+                open_file(config_dir, &path)
+            })
+            .or_else(|e| {
+                eprintln!("{:#}; keeping query history in memory only", e);
+                let mut connection = Connection::open_in_memory()?;
+                migrate(&mut connection)?;
+                Ok::<_, anyhow::Error>(connection)
+            })
+            .expect("in-memory history database");
+
+        Self {
+            connection: Mutex::new(connection),
+        }
+    }
+
+    /// Adds an entry and returns its id; `entry.id` is ignored.
+    pub fn record(&self, entry: &HistoryEntry) -> Result<i64> {
+        let connection = self.connection.lock().unwrap();
+        connection.execute(
+            r#"
+            INSERT INTO query_history
+                (connection_id, sql, started_at, duration_ms, row_count, succeeded, error_code, error_message)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            "#,
+            params![
+                entry.connection_id,
+                entry.sql,
+                entry.started_at,
+                entry.duration_ms,
+                entry.row_count,
+                entry.succeeded,
+                entry.error_code,
+                entry.error_message,
+            ],
+        )?;
+        Ok(connection.last_insert_rowid())
+    }
+
+    pub fn search(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>> {
+        let pattern = filter
+            .text
+            .as_deref()
+            .filter(|text| !text.is_empty())
+            .map(|text| format!("%{}%", escape_like(text)));
+
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare_cached(
+            r#"
+            SELECT id, connection_id, sql, started_at, duration_ms, row_count, succeeded, error_code, error_message
+            FROM query_history
+            WHERE (?1 IS NULL OR sql LIKE ?1 ESCAPE '\')
+              AND (?2 IS NULL OR connection_id = ?2)
+              AND (?3 IS NULL OR started_at >= ?3)
+              AND (?4 IS NULL OR started_at < ?4)
+            ORDER BY started_at DESC, id DESC
+            LIMIT ?5 OFFSET ?6
+            "#,
+        )?;
+        let entries = statement
+            .query_map(
+                params![
+                    pattern,
+                    filter.connection_id,
+                    filter.from,
+                    filter.to,
+                    filter.limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+                    filter.offset,
+                ],
+                history_entry,
+            )?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(entries)
+    }
+
+    /// Deletes old entries and returns how many went. The space they took
+    /// is given back to the file system.
+    pub fn prune(&self, prune: &PruneHistory) -> Result<usize> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        let mut deleted = 0;
+        if let Some(before) = prune.before {
+            deleted += transaction.execute(
+                "DELETE FROM query_history WHERE started_at < ?1",
+                params![before],
+            )?;
+        }
+        if let Some(max_entries) = prune.max_entries {
+            deleted += transaction.execute(
+                r#"
+                DELETE FROM query_history
+                WHERE id NOT IN (
+                    SELECT id FROM query_history ORDER BY started_at DESC, id DESC LIMIT ?1
+                )
+                "#,
+                params![max_entries],
+            )?;
+        }
+        transaction.commit()?;
+
+        if deleted > 0 {
+            connection.execute_batch("PRAGMA incremental_vacuum;")?;
+        }
+        Ok(deleted)
+    }
+}
+
+fn open_file(config_dir: &Path, path: &Path) -> Result<Connection> {
+    fs::create_dir_all(config_dir)
+        .with_context(|| format!("Failed to create {}", config_dir.display()))?;
+    let mut connection =
+        Connection::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+    // auto_vacuum only takes effect before the first table is created, so it
+    // is set for new databases and ignored for existing ones
+    connection
+        .execute_batch(
+            "PRAGMA auto_vacuum = INCREMENTAL; PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;",
+        )
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    migrate(&mut connection).with_context(|| format!("Failed to upgrade {}", path.display()))?;
+    Ok(connection)
+}
+
+fn migrate(connection: &mut Connection) -> Result<()> {
+    let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > MIGRATIONS.len() {
+        bail!(
+            "History database version {} is not supported (expected 0..={})",
+            version,
+            MIGRATIONS.len()
+        );
+    }
+
+    for (step, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(sql)?;
+        transaction.execute_batch(&format!("PRAGMA user_version = {}", step + 1))?;
+        transaction.commit()?;
+    }
+    Ok(())
+}
+
+fn history_entry(row: &Row) -> rusqlite::Result<HistoryEntry> {
+    Ok(HistoryEntry {
+        id: row.get("id")?,
+        connection_id: row.get("connection_id")?,
+        sql: row.get("sql")?,
+        started_at: row.get("started_at")?,
+        duration_ms: row.get("duration_ms")?,
+        row_count: row.get("row_count")?,
+        succeeded: row.get("succeeded")?,
+        error_code: row.get("error_code")?,
+        error_message: row.get("error_message")?,
+    })
+}
+
+/// Escapes the wildcards of a `LIKE ... ESCAPE '\'` pattern.
+fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
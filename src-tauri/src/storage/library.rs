@@ -0,0 +1,255 @@
+use crate::db::script::named_parameters;
+use crate::storage::{set_aside, write_atomic};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+const FILE_NAME: &str = "saved_queries.json";
+
+/// Version of the library format, both the file in the config directory
+/// and exported files.
+const CURRENT_VERSION: u32 = 1;
+
+/// A named parameter of a saved query, written `:name` in its SQL.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedQueryParameter {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Filled in when the query is opened.
+    #[serde(default)]
+    pub default_value: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedQuery {
+    pub id: String,
+    pub name: String,
+    /// `/`-separated folder path; empty at the top level.
+    #[serde(default)]
+    pub folder: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub sql: String,
+    /// Connection to open the query on. Dropped on import when the
+    /// importing machine has no such connection.
+    #[serde(default)]
+    pub default_connection_id: Option<String>,
+    /// One entry per placeholder in `sql`, in order of first use.
+    #[serde(default)]
+    pub parameters: Vec<SavedQueryParameter>,
+    /// Milliseconds since the Unix epoch.
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl SavedQuery {
+    /// Trims and deduplicates tags and folder segments, and lists exactly the
+    /// placeholders in `sql`, keeping what was said about those already
+    /// listed.
+    fn normalize(&mut self) {
+        self.name = self.name.trim().to_string();
+        self.folder = self
+            .folder
+            .split('/')
+            .map(str::trim)
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let mut tags: Vec<String> = self
+            .tags
+            .iter()
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        self.tags = tags;
+
+        let mut described = std::mem::take(&mut self.parameters);
+        self.parameters = named_parameters(&self.sql)
+            .names
+            .into_iter()
+            .map(|name| match described.iter().position(|p| p.name == name) {
+                Some(index) => described.swap_remove(index),
+                None => SavedQueryParameter {
+                    name,
+                    description: None,
+                    default_value: None,
+                },
+            })
+            .collect();
+    }
+}
+
+/// How an import changed the library.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ImportSummary {
+    pub added: usize,
+    /// Queries whose id was already in the library; the imported copy wins.
+    pub updated: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LibraryFile {
+    version: u32,
+    queries: Vec<SavedQuery>,
+}
+
+/// Saved queries, backed by a JSON file in the app config directory.
+/// Folders exist as long as a query is filed under them.
+pub struct LibraryStore {
+    path: PathBuf,
+    queries: RwLock<Vec<SavedQuery>>,
+}
+
+impl LibraryStore {
+    /// Loads the library from `config_dir`. A missing file yields an empty
+    /// library, and so does one that cannot be read, after it is moved aside
+    /// to a `saved_queries.json.<timestamp>.bak` file.
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = config_dir.join(FILE_NAME);
+        let queries = if path.exists() {
+            match read_file(&path) {
+                Ok(queries) => queries,
+                Err(e) => {
+                    let backup = set_aside(&path)?;
+                    eprintln!(
+                        "{:#}; moved it to {} and starting with an empty library",
+                        e,
+                        backup.display()
+                    );
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            path,
+            queries: RwLock::new(queries),
+        })
+    }
+
+    /// All saved queries, by folder and then name.
+    pub async fn list(&self) -> Vec<SavedQuery> {
+        let mut queries = self.queries.read().await.clone();
+        queries.sort_by(|a, b| {
+            a.folder
+                .cmp(&b.folder)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        queries
+    }
+
+    /// Inserts or replaces a query and returns it as stored. A new query
+    /// gets `created_at`; both get `updated_at`.
+    pub async fn upsert(&self, mut query: SavedQuery) -> Result<SavedQuery> {
+        query.normalize();
+
+        let mut queries = self.queries.write().await;
+        let now = chrono::Utc::now().timestamp_millis();
+        query.created_at = queries
+            .iter()
+            .find(|q| q.id == query.id)
+            .map_or(now, |existing| existing.created_at);
+        query.updated_at = now;
+
+        let mut updated = queries.clone();
+        match updated.iter_mut().find(|q| q.id == query.id) {
+            Some(existing) => *existing = query.clone(),
+            None => updated.push(query.clone()),
+        }
+        write_file(&self.path, &updated)?;
+        *queries = updated;
+        Ok(query)
+    }
+
+    pub async fn remove(&self, id: &str) -> Result<()> {
+        let mut queries = self.queries.write().await;
+        let mut updated = queries.clone();
+        updated.retain(|q| q.id != id);
+        write_file(&self.path, &updated)?;
+        *queries = updated;
+        Ok(())
+    }
+
+    /// Writes the whole library to `path` and returns how many queries it
+    /// holds.
+    pub async fn export(&self, path: &Path) -> Result<usize> {
+        let queries = self.queries.read().await;
+        write_file(path, &queries)?;
+        Ok(queries.len())
+    }
+
+    /// Merges a file written by `export` into the library. Default
+    /// connections not in `connection_ids` are cleared, since connection
+    /// ids are local to the machine that made them.
+    pub async fn import(&self, path: &Path, connection_ids: &[String]) -> Result<ImportSummary> {
+        let imported = read_file(path)?;
+
+        let mut queries = self.queries.write().await;
+        let mut updated = queries.clone();
+        let mut summary = ImportSummary::default();
+        for mut query in imported {
+            query.normalize();
+            if query
+                .default_connection_id
+                .as_ref()
+                .is_some_and(|id| !connection_ids.contains(id))
+            {
+                query.default_connection_id = None;
+            }
+            match updated.iter_mut().find(|q| q.id == query.id) {
+                Some(existing) => {
+                    *existing = query;
+                    summary.updated += 1;
+                }
+                None => {
+                    updated.push(query);
+                    summary.added += 1;
+                }
+            }
+        }
+        write_file(&self.path, &updated)?;
+        *queries = updated;
+        Ok(summary)
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<SavedQuery>> {
+    let raw =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let value: Value = serde_json::from_str(&raw)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| anyhow!("{} is not a saved query library", path.display()))?;
+    if version == 0 || version > u64::from(CURRENT_VERSION) {
+        bail!(
+            "Saved query library version {} is not supported (expected 1..={})",
+            version,
+            CURRENT_VERSION
+        );
+    }
+    let file: LibraryFile = serde_json::from_value(value)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(file.queries)
+}
+
+fn write_file(path: &Path, queries: &[SavedQuery]) -> Result<()> {
+    let file = LibraryFile {
+        version: CURRENT_VERSION,
+        queries: queries.to_vec(),
+    };
+    write_atomic(path, &serde_json::to_vec_pretty(&file)?)
+}
@@ -0,0 +1,54 @@
+pub mod connections;
+pub mod history;
+pub mod library;
+pub mod settings;
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Writes `contents` to `path` by writing a sibling temp file and renaming it
+/// over the target, so a crash mid-write never leaves a truncated file behind.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let tmp_path = path.with_extension("tmp");
+    {
+        let mut file = fs::File::create(&tmp_path)
+            .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+/// Renames a file that could not be loaded to
+/// `<name>.<UTC timestamp>.bak`, so the app can start fresh without losing
+/// it. Earlier backups are kept.
+pub fn set_aside(path: &Path) -> Result<PathBuf> {
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
+    let mut backup = backup_path(path, &stamp.to_string());
+    for n in 2.. {
+        if !backup.exists() {
+            break;
+        }
+        backup = backup_path(path, &format!("{}-{}", stamp, n));
+    }
+
+    fs::rename(path, &backup)
+        .with_context(|| format!("Failed to move {} aside", path.display()))?;
+    Ok(backup)
+}
+
+fn backup_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".{}.bak", suffix));
+    PathBuf::from(backup)
+}
@@ -0,0 +1,149 @@
+use crate::db::pool::PoolSettings;
+use crate::storage::{set_aside, write_atomic};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+const FILE_NAME: &str = "settings.json";
+
+/// User preferences. Every field has a default so older files keep loading
+/// as settings are added.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    /// Rows fetched per page when browsing a table.
+    pub table_page_size: i64,
+    /// Hard cap on rows read for one query result, however it is paged.
+    pub max_result_rows: usize,
+    /// Limits for the per-connection client pools. Changes apply to pools
+    /// opened afterwards.
+    pub pool: PoolSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            table_page_size: 100,
+            max_result_rows: 100_000,
+            pool: PoolSettings::default(),
+        }
+    }
+}
+
+pub struct SettingsStore {
+    path: PathBuf,
+    settings: RwLock<Settings>,
+}
+
+impl SettingsStore {
+    /// Loads the settings from `config_dir`. A missing file yields the
+    /// defaults, and so does one that cannot be parsed, after it is moved
+    /// aside.
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = config_dir.join(FILE_NAME);
+
+        let settings = if path.exists() {
+            let raw = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            match serde_json::from_str(&raw)
+                .with_context(|| format!("Failed to parse {}", path.display()))
+            {
+                Ok(settings) => settings,
+                Err(e) => {
+                    let backup = set_aside(&path)?;
+                    eprintln!(
+                        "{:#}; moved it to {} and starting with the default settings",
+                        e,
+                        backup.display()
+                    );
+                    Settings::default()
+                }
+            }
+        } else {
+            Settings::default()
+        };
+
+        Ok(Self {
+            path,
+            settings: RwLock::new(settings),
+        })
+    }
+
+    pub async fn get(&self) -> Settings {
+        self.settings.read().await.clone()
+    }
+
+    pub async fn update(&self, settings: Settings) -> Result<()> {
+        let mut current = self.settings.write().await;
+        write_atomic(&self.path, &serde_json::to_vec_pretty(&settings)?)?;
+        *current = settings;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh config directory, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let path = std::env::temp_dir().join(format!("settings-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[tokio::test]
+    async fn persists_settings() {
+        let dir = TempDir::new();
+        let store = SettingsStore::load(&dir.0).unwrap();
+        assert_eq!(store.get().await.table_page_size, 100);
+
+        store
+            .update(Settings {
+                table_page_size: 250,
+                ..Settings::default()
+            })
+            .await
+            .unwrap();
+        let reloaded = SettingsStore::load(&dir.0).unwrap();
+        assert_eq!(reloaded.get().await.table_page_size, 250);
+    }
+
+    #[tokio::test]
+    async fn fills_in_missing_settings() {
+        let dir = TempDir::new();
+        fs::write(dir.0.join(FILE_NAME), r#"{ "max_result_rows": 500 }"#).unwrap();
+
+        let settings = SettingsStore::load(&dir.0).unwrap().get().await;
+        assert_eq!(settings.max_result_rows, 500);
+        assert_eq!(settings.table_page_size, 100);
+    }
+
+    #[tokio::test]
+    async fn sets_aside_an_unreadable_file() {
+        let dir = TempDir::new();
+        fs::write(dir.0.join(FILE_NAME), r#"{ "table_page_size": "lots" }"#).unwrap();
+
+        let store = SettingsStore::load(&dir.0).unwrap();
+        assert_eq!(store.get().await.table_page_size, 100);
+        assert!(!dir.0.join(FILE_NAME).exists());
+
+        let backups: Vec<String> = fs::read_dir(&dir.0)
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        assert_eq!(backups, [r#"{ "table_page_size": "lots" }"#]);
+    }
+}
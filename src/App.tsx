@@ -68,6 +68,21 @@ function App() {
     setActiveTab(tabId);
   }, [selectedConnectionId]);
 
+  const handleShowDdl = useCallback((label: string, ddl: string) => {
+    if (!selectedConnectionId) return;
+
+    const tabId = String(Date.now());
+    const newTab: Tab = {
+      id: tabId,
+      label,
+      connectionId: selectedConnectionId,
+      type: 'query',
+      initialQuery: ddl,
+    };
+    setTabs((prev) => [...prev, newTab]);
+    setActiveTab(tabId);
+  }, [selectedConnectionId]);
+
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
       // Cmd/Ctrl+K to toggle theme
@@ -111,6 +126,7 @@ function App() {
           selectedConnectionId={selectedConnectionId}
           onConnectionSelect={handleConnectionSelect}
           onTableSelect={handleTableSelect}
+          onShowDdl={handleShowDdl}
         />
         <div className="flex-1 flex flex-col min-w-0">
           <TabManager
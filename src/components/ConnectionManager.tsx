@@ -3,6 +3,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { Button } from './ui/Button';
 import { Input } from './ui/Input';
 import { Label } from './ui/Label';
+import { Switch } from './ui/Switch';
 import {
   Dialog,
   DialogContent,
@@ -11,7 +12,8 @@ import {
   DialogHeader,
   DialogTitle,
 } from './ui/Dialog';
-import { ConnectionConfig } from '../hooks/useConnection';
+import { ConnectionConfig, SshTunnelConfig, SslMode } from '../hooks/useConnection';
+import { errorMessage } from '../lib/errors';
 
 interface ConnectionManagerProps {
   open: boolean;
@@ -20,6 +22,40 @@ interface ConnectionManagerProps {
   onSaved: () => void;
 }
 
+const defaultFormData = {
+  name: '',
+  host: 'localhost',
+  port: '5432',
+  database: '',
+  username: '',
+  password: '',
+  ssl_mode: 'prefer' as SslMode,
+  ssl_root_cert: '',
+  ssl_client_cert: '',
+  ssl_client_key: '',
+  ssh_enabled: false,
+  ssh_host: '',
+  ssh_port: '22',
+  ssh_username: '',
+  ssh_auth: 'password' as SshTunnelConfig['auth'],
+  ssh_private_key_path: '',
+  ssh_host_key_fingerprint: '',
+  ssh_secret: '',
+};
+
+function sshFormData(tunnel?: SshTunnelConfig | null) {
+  return {
+    ssh_enabled: !!tunnel,
+    ssh_host: tunnel?.host || '',
+    ssh_port: tunnel?.port?.toString() || '22',
+    ssh_username: tunnel?.username || '',
+    ssh_auth: tunnel?.auth || ('password' as SshTunnelConfig['auth']),
+    ssh_private_key_path: tunnel?.private_key_path || '',
+    ssh_host_key_fingerprint: tunnel?.host_key_fingerprint || '',
+    ssh_secret: '',
+  };
+}
+
 export function ConnectionManager({
   open,
   onOpenChange,
@@ -33,6 +69,11 @@ export function ConnectionManager({
     database: connection?.database || '',
     username: connection?.username || '',
     password: '',
+    ssl_mode: connection?.ssl_mode || 'prefer',
+    ssl_root_cert: connection?.ssl_root_cert || '',
+    ssl_client_cert: connection?.ssl_client_cert || '',
+    ssl_client_key: connection?.ssl_client_key || '',
+    ...sshFormData(connection?.ssh_tunnel),
   });
   const [testing, setTesting] = useState(false);
   const [saving, setSaving] = useState(false);
@@ -51,6 +92,11 @@ export function ConnectionManager({
         database: connection.database || '',
         username: connection.username || '',
         password: '', // Will be loaded separately
+        ssl_mode: connection.ssl_mode || 'prefer',
+        ssl_root_cert: connection.ssl_root_cert || '',
+        ssl_client_cert: connection.ssl_client_cert || '',
+        ssl_client_key: connection.ssl_client_key || '',
+        ...sshFormData(connection.ssh_tunnel),
       });
       
       // Load password from keyring
@@ -68,14 +114,7 @@ export function ConnectionManager({
         });
     } else if (open && !connection) {
       // New connection
-      setFormData({
-        name: '',
-        host: 'localhost',
-        port: '5432',
-        database: '',
-        username: '',
-        password: '',
-      });
+      setFormData(defaultFormData);
     }
     
     // Clear messages when dialog opens
@@ -85,6 +124,28 @@ export function ConnectionManager({
     }
   }, [open, connection]);
 
+  const sslFields = () => ({
+    ssl_mode: formData.ssl_mode,
+    ssl_root_cert: formData.ssl_root_cert || null,
+    ssl_client_cert: formData.ssl_client_cert || null,
+    ssl_client_key: formData.ssl_client_key || null,
+  });
+
+  const sshFields = () => ({
+    ssh_tunnel: formData.ssh_enabled
+      ? {
+          host: formData.ssh_host,
+          port: parseInt(formData.ssh_port),
+          username: formData.ssh_username,
+          auth: formData.ssh_auth,
+          private_key_path:
+            formData.ssh_auth === 'private-key' ? formData.ssh_private_key_path : null,
+          host_key_fingerprint: formData.ssh_host_key_fingerprint.trim() || null,
+        }
+      : null,
+    ssh_secret: formData.ssh_secret || null,
+  });
+
   const handleTest = async () => {
     setTesting(true);
     setError(null);
@@ -97,6 +158,8 @@ export function ConnectionManager({
           database: formData.database,
           username: formData.username,
           password: formData.password,
+          ...sslFields(),
+          ...sshFields(),
         },
       });
       if (response.success) {
@@ -104,8 +167,8 @@ export function ConnectionManager({
       } else {
         setError(response.message || 'Connection test failed');
       }
-    } catch (err: any) {
-      setError(err.message || 'Connection test failed');
+    } catch (err) {
+      setError(errorMessage(err, 'Connection test failed'));
     } finally {
       setTesting(false);
     }
@@ -136,20 +199,15 @@ export function ConnectionManager({
           database: formData.database,
           username: formData.username,
           password: connection && !formData.password ? null : formData.password, // null for empty password when editing, password string otherwise
+          ...sslFields(),
+          ...sshFields(),
         },
       });
       onSaved();
       onOpenChange(false);
-      setFormData({
-        name: '',
-        host: 'localhost',
-        port: '5432',
-        database: '',
-        username: '',
-        password: '',
-      });
-    } catch (err: any) {
-      setError(err.message || 'Failed to save connection');
+      setFormData(defaultFormData);
+    } catch (err) {
+      setError(errorMessage(err, 'Failed to save connection'));
     } finally {
       setSaving(false);
     }
@@ -248,6 +306,145 @@ export function ConnectionManager({
               <span className="text-xs text-muted-foreground">Loading password...</span>
             )}
           </div>
+          <div className="grid gap-2">
+            <Label htmlFor="ssl_mode">SSL Mode</Label>
+            <select
+              id="ssl_mode"
+              className="flex h-10 w-full rounded-md border border-input bg-background px-3 py-2 text-sm"
+              value={formData.ssl_mode}
+              onChange={(e) =>
+                setFormData({ ...formData, ssl_mode: e.target.value as SslMode })
+              }
+            >
+              <option value="disable">disable</option>
+              <option value="prefer">prefer</option>
+              <option value="require">require</option>
+              <option value="verify-ca">verify-ca</option>
+              <option value="verify-full">verify-full</option>
+            </select>
+          </div>
+          {formData.ssl_mode !== 'disable' && (
+            <div className="grid gap-2">
+              <Label htmlFor="ssl_root_cert">Root CA Certificate</Label>
+              <Input
+                id="ssl_root_cert"
+                value={formData.ssl_root_cert}
+                onChange={(e) =>
+                  setFormData({ ...formData, ssl_root_cert: e.target.value })
+                }
+                placeholder="/path/to/root.crt (optional)"
+              />
+              <div className="grid grid-cols-2 gap-4">
+                <Input
+                  id="ssl_client_cert"
+                  value={formData.ssl_client_cert}
+                  onChange={(e) =>
+                    setFormData({ ...formData, ssl_client_cert: e.target.value })
+                  }
+                  placeholder="Client certificate (optional)"
+                />
+                <Input
+                  id="ssl_client_key"
+                  value={formData.ssl_client_key}
+                  onChange={(e) =>
+                    setFormData({ ...formData, ssl_client_key: e.target.value })
+                  }
+                  placeholder="Client key, PKCS#8 (optional)"
+                />
+              </div>
+            </div>
+          )}
+          <div className="flex items-center gap-2">
+            <Switch
+              id="ssh_enabled"
+              checked={formData.ssh_enabled}
+              onCheckedChange={(checked) =>
+                setFormData({ ...formData, ssh_enabled: checked })
+              }
+            />
+            <Label htmlFor="ssh_enabled">Connect through SSH tunnel</Label>
+          </div>
+          {formData.ssh_enabled && (
+            <div className="grid gap-2">
+              <div className="grid grid-cols-3 gap-4">
+                <Input
+                  id="ssh_host"
+                  className="col-span-2"
+                  value={formData.ssh_host}
+                  onChange={(e) =>
+                    setFormData({ ...formData, ssh_host: e.target.value })
+                  }
+                  placeholder="bastion.example.com"
+                />
+                <Input
+                  id="ssh_port"
+                  type="number"
+                  value={formData.ssh_port}
+                  onChange={(e) =>
+                    setFormData({ ...formData, ssh_port: e.target.value })
+                  }
+                  placeholder="22"
+                />
+              </div>
+              <div className="grid grid-cols-2 gap-4">
+                <Input
+                  id="ssh_username"
+                  value={formData.ssh_username}
+                  onChange={(e) =>
+                    setFormData({ ...formData, ssh_username: e.target.value })
+                  }
+                  placeholder="SSH user"
+                />
+                <select
+                  id="ssh_auth"
+                  className="flex h-10 w-full rounded-md border border-input bg-background px-3 py-2 text-sm"
+                  value={formData.ssh_auth}
+                  onChange={(e) =>
+                    setFormData({
+                      ...formData,
+                      ssh_auth: e.target.value as SshTunnelConfig['auth'],
+                    })
+                  }
+                >
+                  <option value="password">Password</option>
+                  <option value="private-key">Private key</option>
+                </select>
+              </div>
+              {formData.ssh_auth === 'private-key' && (
+                <Input
+                  id="ssh_private_key_path"
+                  value={formData.ssh_private_key_path}
+                  onChange={(e) =>
+                    setFormData({ ...formData, ssh_private_key_path: e.target.value })
+                  }
+                  placeholder="~/.ssh/id_ed25519"
+                />
+              )}
+              <Input
+                id="ssh_secret"
+                type="password"
+                value={formData.ssh_secret}
+                onChange={(e) =>
+                  setFormData({ ...formData, ssh_secret: e.target.value })
+                }
+                placeholder={
+                  formData.ssh_auth === 'private-key'
+                    ? 'Key passphrase (optional)'
+                    : connection
+                      ? 'SSH password (leave empty to keep existing)'
+                      : 'SSH password'
+                }
+              />
+              <Input
+                id="ssh_host_key_fingerprint"
+                value={formData.ssh_host_key_fingerprint}
+                onChange={(e) =>
+                  setFormData({ ...formData, ssh_host_key_fingerprint: e.target.value })
+                }
+                placeholder="Host key fingerprint, if not in known_hosts (SHA256:...)"
+              />
+            </div>
+          )}
           {error && (
             <div className="text-sm text-destructive">{error}</div>
           )}
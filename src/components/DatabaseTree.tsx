@@ -1,25 +1,174 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { ChevronRight, ChevronDown, Database, Folder, Table as TableIcon } from 'lucide-react';
+import {
+  ChevronRight,
+  ChevronDown,
+  Database,
+  Eye,
+  FileCode,
+  Folder,
+  FunctionSquare,
+  Globe,
+  Hash,
+  Layers,
+  Puzzle,
+  Shapes,
+  Table as TableIcon,
+  Table2,
+  type LucideIcon,
+} from 'lucide-react';
 
 interface SchemaInfo {
   name: string;
 }
 
+type RelationKind =
+  | 'table'
+  | 'partitioned-table'
+  | 'view'
+  | 'materialized-view'
+  | 'foreign-table'
+  | 'sequence';
+
 interface TableInfo {
   name: string;
   schema: string;
+  kind: RelationKind;
+  owner: string;
+  estimated_rows: number | null;
+  comment: string | null;
+  is_partition: boolean;
+}
+
+type DdlObjectKind = 'table' | 'view' | 'materialized-view' | 'function' | 'sequence' | 'type';
+
+const RELATION_DDL_KINDS: Record<RelationKind, DdlObjectKind> = {
+  table: 'table',
+  'partitioned-table': 'table',
+  'foreign-table': 'table',
+  view: 'view',
+  'materialized-view': 'materialized-view',
+  sequence: 'sequence',
+};
+
+/** Relations that can be opened in a table view. */
+const BROWSABLE_KINDS: RelationKind[] = [
+  'table',
+  'partitioned-table',
+  'view',
+  'materialized-view',
+  'foreign-table',
+];
+
+const KIND_ICONS = {
+  table: TableIcon,
+  'partitioned-table': Table2,
+  view: Eye,
+  'materialized-view': Layers,
+  'foreign-table': Globe,
+  sequence: TableIcon,
+};
+
+function describeRelation(table: TableInfo): string {
+  const details = [table.kind.replace(/-/g, ' '), `owner ${table.owner}`];
+  if (table.estimated_rows !== null) {
+    details.push(`~${table.estimated_rows.toLocaleString()} rows`);
+  }
+  return table.comment ? `${table.comment}\n${details.join(', ')}` : details.join(', ');
+}
+
+interface FunctionInfo {
+  name: string;
+  kind: 'function' | 'procedure' | 'aggregate' | 'window';
+  arguments: string;
+  identity_arguments: string;
+  return_type: string | null;
+  language: string;
+  volatility: 'immutable' | 'stable' | 'volatile';
+  comment: string | null;
+  extension: string | null;
+  definition: string | null;
+}
+
+interface SequenceInfo {
+  name: string;
+  data_type: string;
+  last_value: number | null;
+  owned_by: string | null;
+  comment: string | null;
+}
+
+interface TypeInfo {
+  name: string;
+  kind: 'enum' | 'composite' | 'domain';
+  labels: string[];
+  attributes: Array<{ name: string; data_type: string }>;
+  base_type: string | null;
+  comment: string | null;
+  extension: string | null;
+}
+
+interface ExtensionInfo {
+  name: string;
+  installed_version: string | null;
+  default_version: string | null;
+  schema: string | null;
+  comment: string | null;
+}
+
+/** Everything but the tables listed under a schema. */
+interface SchemaObjects {
+  functions: FunctionInfo[];
+  sequences: SequenceInfo[];
+  types: TypeInfo[];
+}
+
+function describeFunction(fn: FunctionInfo): string {
+  const signature = `${fn.name}(${fn.arguments})${fn.return_type ? ` → ${fn.return_type}` : ''}`;
+  const details = `${fn.kind}, ${fn.language}, ${fn.volatility}`;
+  return fn.comment ? `${signature}\n${details}\n${fn.comment}` : `${signature}\n${details}`;
+}
+
+function describeSequence(sequence: SequenceInfo): string {
+  const details = [
+    sequence.data_type,
+    `current value ${sequence.last_value ?? 'not yet used'}`,
+  ];
+  if (sequence.owned_by) {
+    details.push(`owned by ${sequence.owned_by}`);
+  }
+  return sequence.comment ? `${sequence.comment}\n${details.join(', ')}` : details.join(', ');
+}
+
+function describeType(type: TypeInfo): string {
+  let details: string;
+  switch (type.kind) {
+    case 'enum':
+      details = `enum (${type.labels.join(', ')})`;
+      break;
+    case 'composite':
+      details = `(${type.attributes.map((attr) => `${attr.name} ${attr.data_type}`).join(', ')})`;
+      break;
+    case 'domain':
+      details = `domain over ${type.base_type}`;
+      break;
+  }
+  return type.comment ? `${type.comment}\n${details}` : details;
 }
 
 interface DatabaseTreeProps {
   connectionId: string;
   onTableSelect?: (schema: string, table: string) => void;
+  /** Opens the generated DDL of an object. */
+  onShowDdl?: (label: string, ddl: string) => void;
 }
 
-export function DatabaseTree({ connectionId, onTableSelect }: DatabaseTreeProps) {
+export function DatabaseTree({ connectionId, onTableSelect, onShowDdl }: DatabaseTreeProps) {
   const [expandedSchemas, setExpandedSchemas] = useState<Set<string>>(new Set());
   const [schemas, setSchemas] = useState<SchemaInfo[]>([]);
   const [tables, setTables] = useState<Map<string, TableInfo[]>>(new Map());
+  const [objects, setObjects] = useState<Map<string, SchemaObjects>>(new Map());
+  const [extensions, setExtensions] = useState<ExtensionInfo[]>([]);
   const [loading, setLoading] = useState(false);
   const [loadingSchemas, setLoadingSchemas] = useState<Set<string>>(new Set());
 
@@ -29,6 +178,8 @@ export function DatabaseTree({ connectionId, onTableSelect }: DatabaseTreeProps)
     } else {
       setSchemas([]);
       setTables(new Map());
+      setObjects(new Map());
+      setExtensions([]);
       setExpandedSchemas(new Set());
     }
   }, [connectionId]);
@@ -47,6 +198,15 @@ export function DatabaseTree({ connectionId, onTableSelect }: DatabaseTreeProps)
     } finally {
       setLoading(false);
     }
+
+    try {
+      const result = await invoke<ExtensionInfo[]>('get_extensions', {
+        request: { connection_id: connectionId },
+      });
+      setExtensions(result.filter((extension) => extension.installed_version !== null));
+    } catch (error) {
+      console.error('Failed to load extensions:', error);
+    }
   };
 
   const loadTables = async (schema: string) => {
@@ -54,17 +214,30 @@ export function DatabaseTree({ connectionId, onTableSelect }: DatabaseTreeProps)
 
     setLoadingSchemas((prev) => new Set(prev).add(schema));
     try {
-      const result = await invoke<TableInfo[]>('get_tables', {
-        request: {
-          connection_id: connectionId,
-          schema,
-        },
-      });
+      const request = { connection_id: connectionId, schema };
+      const [result, functions, sequences, types] = await Promise.all([
+        invoke<TableInfo[]>('get_tables', {
+          request: { ...request, kinds: BROWSABLE_KINDS },
+        }),
+        invoke<FunctionInfo[]>('get_functions', { request }),
+        invoke<SequenceInfo[]>('get_sequences', { request }),
+        invoke<TypeInfo[]>('get_types', { request }),
+      ]);
       setTables((prev) => {
         const newMap = new Map(prev);
         newMap.set(schema, result);
         return newMap;
       });
+      setObjects((prev) => {
+        const newMap = new Map(prev);
+        newMap.set(schema, {
+          // Extension members are listed under the extension, not the schema
+          functions: functions.filter((fn) => fn.extension === null),
+          sequences,
+          types: types.filter((type) => type.extension === null),
+        });
+        return newMap;
+      });
     } catch (error) {
       console.error(`Failed to load tables for schema ${schema}:`, error);
     } finally {
@@ -96,6 +269,19 @@ export function DatabaseTree({ connectionId, onTableSelect }: DatabaseTreeProps)
     }
   };
 
+  const showDdl = async (kind: DdlObjectKind, schema: string, name: string, args?: string) => {
+    if (!onShowDdl) return;
+
+    try {
+      const ddl = await invoke<string>('get_ddl', {
+        request: { connection_id: connectionId, kind, schema, name, arguments: args },
+      });
+      onShowDdl(`${name} DDL`, ddl);
+    } catch (error) {
+      console.error(`Failed to generate DDL for ${schema}.${name}:`, error);
+    }
+  };
+
   if (!connectionId) {
     return (
       <div className="p-4 text-sm text-muted-foreground text-center">
@@ -125,6 +311,7 @@ export function DatabaseTree({ connectionId, onTableSelect }: DatabaseTreeProps)
       {schemas.map((schema) => {
         const isExpanded = expandedSchemas.has(schema.name);
         const schemaTables = tables.get(schema.name) || [];
+        const schemaObjects = objects.get(schema.name);
         const isLoading = loadingSchemas.has(schema.name);
 
         return (
@@ -155,26 +342,150 @@ export function DatabaseTree({ connectionId, onTableSelect }: DatabaseTreeProps)
                     No tables
                   </div>
                 ) : (
-                  schemaTables.map((table) => (
-                    <div
-                      key={`${schema.name}.${table.name}`}
-                      className="flex items-center gap-1 px-2 py-1 rounded hover:bg-accent cursor-pointer group"
-                      onClick={(e) => {
-                        e.stopPropagation();
-                        handleTableClick(schema.name, table.name);
-                      }}
-                    >
-                      <div className="w-3 shrink-0" /> {/* Spacer for alignment */}
-                      <TableIcon className="h-3 w-3 shrink-0 text-muted-foreground" />
-                      <span className="text-sm truncate flex-1">{table.name}</span>
-                    </div>
-                  ))
+                  schemaTables.map((table) => {
+                    const KindIcon = KIND_ICONS[table.kind];
+                    return (
+                      <div
+                        key={`${schema.name}.${table.name}`}
+                        className="flex items-center gap-1 px-2 py-1 rounded hover:bg-accent cursor-pointer group"
+                        title={describeRelation(table)}
+                        onClick={(e) => {
+                          e.stopPropagation();
+                          handleTableClick(schema.name, table.name);
+                        }}
+                      >
+                        <div className="w-3 shrink-0" /> {/* Spacer for alignment */}
+                        <KindIcon className="h-3 w-3 shrink-0 text-muted-foreground" />
+                        <span className="text-sm truncate flex-1">{table.name}</span>
+                        <DdlButton
+                          onClick={() =>
+                            showDdl(RELATION_DDL_KINDS[table.kind], schema.name, table.name)
+                          }
+                        />
+                      </div>
+                    );
+                  })
+                )}
+                {schemaObjects && (
+                  <>
+                    <ObjectGroup
+                      label="Functions"
+                      items={schemaObjects.functions.map((fn) => ({
+                        key: `${fn.name}(${fn.identity_arguments})`,
+                        label: `${fn.name}(${fn.identity_arguments})`,
+                        title: describeFunction(fn),
+                        onShowDdl:
+                          fn.kind === 'aggregate'
+                            ? undefined
+                            : () => showDdl('function', schema.name, fn.name, fn.identity_arguments),
+                      }))}
+                      icon={FunctionSquare}
+                    />
+                    <ObjectGroup
+                      label="Sequences"
+                      items={schemaObjects.sequences.map((sequence) => ({
+                        key: sequence.name,
+                        label: sequence.name,
+                        title: describeSequence(sequence),
+                        onShowDdl: () => showDdl('sequence', schema.name, sequence.name),
+                      }))}
+                      icon={Hash}
+                    />
+                    <ObjectGroup
+                      label="Types"
+                      items={schemaObjects.types.map((type) => ({
+                        key: type.name,
+                        label: type.name,
+                        title: describeType(type),
+                        onShowDdl: () => showDdl('type', schema.name, type.name),
+                      }))}
+                      icon={Shapes}
+                    />
+                  </>
                 )}
               </div>
             )}
           </div>
         );
       })}
+      {extensions.length > 0 && (
+        <ObjectGroup
+          label="Extensions"
+          items={extensions.map((extension) => ({
+            key: extension.name,
+            label: `${extension.name} ${extension.installed_version}`,
+            title: extension.comment ?? extension.name,
+          }))}
+          icon={Puzzle}
+        />
+      )}
     </div>
   );
 }
+
+interface ObjectGroupProps {
+  label: string;
+  items: Array<{ key: string; label: string; title: string; onShowDdl?: () => void }>;
+  icon: LucideIcon;
+}
+
+/** A collapsible list of objects that can't be opened, only inspected. */
+function ObjectGroup({ label, items, icon: Icon }: ObjectGroupProps) {
+  const [expanded, setExpanded] = useState(false);
+
+  if (items.length === 0) {
+    return null;
+  }
+
+  return (
+    <div className="select-none">
+      <div
+        className="flex items-center gap-1 px-2 py-1 rounded hover:bg-accent cursor-pointer"
+        onClick={(e) => {
+          e.stopPropagation();
+          setExpanded(!expanded);
+        }}
+      >
+        {expanded ? (
+          <ChevronDown className="h-3 w-3 shrink-0" />
+        ) : (
+          <ChevronRight className="h-3 w-3 shrink-0" />
+        )}
+        <Folder className="h-3 w-3 shrink-0 text-muted-foreground" />
+        <span className="text-sm truncate flex-1">{label}</span>
+        <span className="text-xs text-muted-foreground">{items.length}</span>
+      </div>
+      {expanded && (
+        <div className="ml-4">
+          {items.map((item) => (
+            <div
+              key={item.key}
+              className="flex items-center gap-1 px-2 py-1 rounded hover:bg-accent group"
+              title={item.title}
+            >
+              <div className="w-3 shrink-0" />
+              <Icon className="h-3 w-3 shrink-0 text-muted-foreground" />
+              <span className="text-sm truncate flex-1">{item.label}</span>
+              {item.onShowDdl && <DdlButton onClick={item.onShowDdl} />}
+            </div>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
+
+function DdlButton({ onClick }: { onClick: () => void }) {
+  return (
+    <button
+      onClick={(e) => {
+        e.stopPropagation();
+        onClick();
+      }}
+      className="opacity-0 group-hover:opacity-100 p-0.5 hover:bg-muted rounded"
+      title="Show DDL"
+    >
+      <FileCode className="h-3 w-3" />
+    </button>
+  );
+}
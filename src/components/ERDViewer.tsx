@@ -23,7 +23,7 @@ export function ERDViewer({ connectionId, schema }: ERDViewerProps) {
   const loadERD = useCallback(async () => {
     try {
       const result = await invoke<{
-        nodes: Array<{ id: string; label: string; schema: string; table: string }>;
+        nodes: Array<{ id: string; label: string; schema: string; table: string; kind: string }>;
         edges: Array<{ from: string; to: string; label: string; from_column: string; to_column: string }>;
       }>('get_erd_data', {
         request: {
@@ -35,7 +35,9 @@ export function ERDViewer({ connectionId, schema }: ERDViewerProps) {
       const flowNodes: Node[] = result.nodes.map((node, idx) => ({
         id: node.id,
         type: 'default',
-        data: { label: node.label },
+        data: {
+          label: node.kind === 'table' ? node.label : `${node.label} (${node.kind.replace(/-/g, ' ')})`,
+        },
         position: {
           x: (idx % 5) * 200,
           y: Math.floor(idx / 5) * 150,
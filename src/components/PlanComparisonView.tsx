@@ -0,0 +1,168 @@
+import { PlanNode, QueryPlan } from './QueryPlanViewer';
+
+export interface PlanDelta {
+  before: number;
+  after: number;
+  change: number;
+  ratio: number | null;
+}
+
+export interface MatchedPlanNode {
+  before_id: number;
+  after_id: number;
+  before_node_type: string;
+  after_node_type: string;
+  node_type_changed: boolean;
+  relation_name: string | null;
+  total_cost: PlanDelta;
+  plan_rows: PlanDelta;
+  actual_rows: PlanDelta | null;
+  actual_time_ms: PlanDelta | null;
+  exclusive_time_ms: PlanDelta | null;
+}
+
+export interface UnmatchedPlanNode {
+  id: number;
+  parent_id: number | null;
+  node_type: string;
+  relation_name: string | null;
+  total_cost: number;
+  plan_rows: number;
+  actual_time_ms: number | null;
+}
+
+export interface PlanComparison {
+  before: QueryPlan;
+  after: QueryPlan;
+  total_cost: PlanDelta;
+  planning_time_ms: PlanDelta | null;
+  execution_time_ms: PlanDelta | null;
+  matched: MatchedPlanNode[];
+  only_in_before: UnmatchedPlanNode[];
+  only_in_after: UnmatchedPlanNode[];
+}
+
+function formatDelta(delta: PlanDelta, digits = 2): string {
+  const sign = delta.change > 0 ? '+' : '';
+  const ratio = delta.ratio !== null && delta.ratio !== 1 ? ` (${delta.ratio.toFixed(2)}x)` : '';
+  return `${delta.before.toFixed(digits)} → ${delta.after.toFixed(digits)}, ${sign}${delta.change.toFixed(digits)}${ratio}`;
+}
+
+function deltaClass(delta: PlanDelta): string {
+  if (delta.change < 0) return 'text-green-600 dark:text-green-400';
+  if (delta.change > 0) return 'text-destructive';
+  return 'text-muted-foreground';
+}
+
+function flatten(node: PlanNode, depth = 0, nodes: Array<{ node: PlanNode; depth: number }> = []) {
+  nodes.push({ node, depth });
+  node.children.forEach((child) => flatten(child, depth + 1, nodes));
+  return nodes;
+}
+
+/** Both plans side by side, with matched nodes on the same row where the tree shapes allow. */
+export function PlanComparisonView({ comparison }: { comparison: PlanComparison }) {
+  const matchByBefore = new Map(comparison.matched.map((m) => [m.before_id, m]));
+  const afterNodes = new Map(flatten(comparison.after.root).map((entry) => [entry.node.id, entry]));
+  const shown = new Set<number>();
+
+  // Rows follow the before plan; nodes only in the after plan come right
+  // after their parent's row
+  const rows: Array<{
+    before?: { node: PlanNode; depth: number };
+    after?: { node: PlanNode; depth: number };
+    match?: MatchedPlanNode;
+  }> = [];
+  const pushAddedBelow = (afterId: number | null) => {
+    for (const added of comparison.only_in_after) {
+      if (added.parent_id === afterId && !shown.has(added.id)) {
+        shown.add(added.id);
+        rows.push({ after: afterNodes.get(added.id) });
+        pushAddedBelow(added.id);
+      }
+    }
+  };
+  for (const entry of flatten(comparison.before.root)) {
+    const match = matchByBefore.get(entry.node.id);
+    if (match) {
+      rows.push({ before: entry, after: afterNodes.get(match.after_id), match });
+      pushAddedBelow(match.after_id);
+    } else {
+      rows.push({ before: entry });
+    }
+  }
+
+  return (
+    <div className="space-y-2">
+      <div className="text-xs space-x-3">
+        <span className={deltaClass(comparison.total_cost)}>
+          Cost {formatDelta(comparison.total_cost)}
+        </span>
+        {comparison.execution_time_ms && (
+          <span className={deltaClass(comparison.execution_time_ms)}>
+            Execution {formatDelta(comparison.execution_time_ms, 3)} ms
+          </span>
+        )}
+        {comparison.planning_time_ms && (
+          <span className={deltaClass(comparison.planning_time_ms)}>
+            Planning {formatDelta(comparison.planning_time_ms, 3)} ms
+          </span>
+        )}
+        <span className="text-muted-foreground">
+          {comparison.matched.length} matched, {comparison.only_in_before.length} removed,{' '}
+          {comparison.only_in_after.length} added
+        </span>
+      </div>
+      <div className="rounded-md border text-xs font-mono">
+        <div className="grid grid-cols-[1fr_1fr_16rem] border-b bg-muted px-2 py-1 font-sans font-semibold">
+          <div>Before</div>
+          <div>After</div>
+          <div>Change</div>
+        </div>
+        {rows.map((row, idx) => (
+          <div
+            key={idx}
+            className={`grid grid-cols-[1fr_1fr_16rem] border-b px-2 py-1 ${
+              !row.match ? (row.before ? 'bg-destructive/10' : 'bg-green-500/10') : ''
+            }`}
+          >
+            <div style={{ paddingLeft: `${(row.before?.depth ?? 0) * 12}px` }}>
+              {row.before && nodeLabel(row.before.node)}
+            </div>
+            <div
+              style={{ paddingLeft: `${(row.after?.depth ?? 0) * 12}px` }}
+              className={row.match?.node_type_changed ? 'font-semibold' : ''}
+            >
+              {row.after && nodeLabel(row.after.node)}
+            </div>
+            <div className="space-y-0.5">
+              {row.match ? (
+                <>
+                  <div className={deltaClass(row.match.total_cost)}>
+                    cost {formatDelta(row.match.total_cost)}
+                  </div>
+                  <div className="text-muted-foreground">
+                    rows {formatDelta(row.match.actual_rows ?? row.match.plan_rows, 0)}
+                  </div>
+                  {row.match.actual_time_ms && (
+                    <div className={deltaClass(row.match.actual_time_ms)}>
+                      time {formatDelta(row.match.actual_time_ms, 3)} ms
+                    </div>
+                  )}
+                </>
+              ) : (
+                <span className="text-muted-foreground">
+                  {row.before ? 'only before' : 'only after'}
+                </span>
+              )}
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
+
+function nodeLabel(node: PlanNode): string {
+  return `#${node.id} ${node.node_type}${node.relation_name ? ` on ${node.relation_name}` : ''}`;
+}
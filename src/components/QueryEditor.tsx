@@ -1,8 +1,13 @@
 import { useState, useEffect, useRef } from 'react';
 import Editor from '@monaco-editor/react';
-import { Play } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+import { Bookmark, Check, History, Library, ListOrdered, Network, Play, Save, Undo2 } from 'lucide-react';
 import { Button } from './ui/Button';
-import { useQuery } from '../hooks/useQuery';
+import { QueryPlanViewer } from './QueryPlanViewer';
+import { QueryHistory } from './QueryHistory';
+import { QueryLibrary, SaveQueryDialog, SavedQuery, formatDefault } from './QueryLibrary';
+import { QueryResult, useQuery } from '../hooks/useQuery';
+import { TransactionStatus, useSession } from '../hooks/useSession';
 import {
   Table,
   TableBody,
@@ -12,17 +17,66 @@ import {
   TableRow,
 } from './ui/Table';
 import { setupMonaco } from '../lib/monaco-setup';
+import { AppError } from '../lib/errors';
 
 interface QueryEditorProps {
+  /** Also the id of the tab's transaction session. */
+  tabId: string;
   connectionId?: string;
+  initialQuery?: string;
   onConnectionChange?: (id: string) => void;
 }
 
-export function QueryEditor({ connectionId, onConnectionChange }: QueryEditorProps) {
-  const [query, setQuery] = useState('SELECT * FROM ');
+const STATUS_LABELS: Record<TransactionStatus, string> = {
+  idle: 'Idle',
+  'in-transaction': 'In transaction',
+  failed: 'Transaction failed',
+};
+
+export function QueryEditor({
+  tabId,
+  connectionId,
+  initialQuery,
+  onConnectionChange,
+}: QueryEditorProps) {
+  const [query, setQuery] = useState(initialQuery ?? 'SELECT * FROM ');
   const [editorTheme, setEditorTheme] = useState<'light' | 'vs-dark'>('vs-dark');
-  const { executeQuery, loading, error, result } = useQuery();
+  const [continueOnError, setContinueOnError] = useState(false);
+  const {
+    executeQuery,
+    executeScript,
+    fetchMore,
+    cancelQuery,
+    loading,
+    error,
+    result,
+    scriptResult,
+  } = useQuery();
+  const {
+    session,
+    error: sessionError,
+    begin,
+    commit,
+    rollback,
+    setAutoCommit,
+    createSavepoint,
+    rollbackToSavepoint,
+    releaseSavepoint,
+    refresh,
+  } = useSession(tabId, connectionId);
+  const [selectedSavepoint, setSelectedSavepoint] = useState('');
+  // The statement shown in the plan viewer; a new id re-explains it
+  const [planRequest, setPlanRequest] = useState<{ id: number; query: string } | null>(null);
+  const [showHistory, setShowHistory] = useState(false);
+  const [showLibrary, setShowLibrary] = useState(false);
+  const [saveDialogOpen, setSaveDialogOpen] = useState(false);
+  // The saved query the editor was opened from, which Save updates
+  const [savedQuery, setSavedQuery] = useState<SavedQuery | null>(null);
+  // `:name` placeholders in the query and the values typed for them
+  const [paramNames, setParamNames] = useState<string[]>([]);
+  const [paramValues, setParamValues] = useState<Record<string, string>>({});
   const editorRef = useRef<any>(null);
+  const monacoRef = useRef<any>(null);
 
   useEffect(() => {
     setupMonaco();
@@ -45,6 +99,46 @@ export function QueryEditor({ connectionId, onConnectionChange }: QueryEditorPro
     return () => observer.disconnect();
   }, []);
 
+  useEffect(() => {
+    if (!query.includes(':')) {
+      setParamNames([]);
+      return;
+    }
+    const timer = setTimeout(() => {
+      invoke<string[]>('get_query_parameters', { sql: query }).then(setParamNames);
+    }, 300);
+    return () => clearTimeout(timer);
+  }, [query]);
+
+  // Underline the token the server complained about
+  useEffect(() => {
+    const editor = editorRef.current;
+    const monaco = monacoRef.current;
+    const model = editor?.getModel();
+    if (!model || !monaco) {
+      return;
+    }
+    const failed =
+      error ?? scriptResult?.statements.find((statement) => statement.error)?.error ?? null;
+    const position = failed?.db?.position;
+    if (!failed || !position) {
+      monaco.editor.setModelMarkers(model, 'postgres', []);
+      return;
+    }
+    const start = model.getPositionAt(position - 1);
+    const word = model.getWordAtPosition(start);
+    monaco.editor.setModelMarkers(model, 'postgres', [
+      {
+        severity: monaco.MarkerSeverity.Error,
+        message: failed.message,
+        startLineNumber: start.lineNumber,
+        startColumn: word?.startColumn ?? start.column,
+        endLineNumber: start.lineNumber,
+        endColumn: word?.endColumn ?? start.column + 1,
+      },
+    ]);
+  }, [error, scriptResult]);
+
   const handleExecute = () => {
     if (!connectionId) {
       alert('Please select a connection first');
@@ -53,9 +147,78 @@ export function QueryEditor({ connectionId, onConnectionChange }: QueryEditorPro
     if (!query.trim()) {
       return;
     }
-    executeQuery(connectionId, query);
+    setPlanRequest(null);
+    setShowHistory(false);
+    setShowLibrary(false);
+    // An empty value is NULL
+    const params =
+      paramNames.length > 0
+        ? Object.fromEntries(paramNames.map((name) => [name, paramValues[name] || null]))
+        : undefined;
+    executeQuery(connectionId, query, session?.session_id, params).then(refresh);
+  };
+
+  const handleExecuteScript = () => {
+    if (!connectionId) {
+      alert('Please select a connection first');
+      return;
+    }
+    if (!query.trim()) {
+      return;
+    }
+    setPlanRequest(null);
+    setShowHistory(false);
+    setShowLibrary(false);
+    executeScript(connectionId, query, continueOnError, session?.session_id).then(refresh);
   };
 
+  const handleExplain = () => {
+    if (!connectionId) {
+      alert('Please select a connection first');
+      return;
+    }
+    if (!query.trim()) {
+      return;
+    }
+    setShowHistory(false);
+    setShowLibrary(false);
+    setPlanRequest({ id: Date.now(), query });
+  };
+
+  const handleHistorySelect = (sql: string) => {
+    setQuery(sql);
+    setSavedQuery(null);
+    setShowHistory(false);
+  };
+
+  const handleOpenSaved = (saved: SavedQuery) => {
+    setQuery(saved.sql);
+    setSavedQuery(saved);
+    setParamValues(
+      Object.fromEntries(
+        saved.parameters
+          .filter((p) => p.default_value !== null)
+          .map((p) => [p.name, formatDefault(p.default_value)])
+      )
+    );
+    if (saved.default_connection_id && saved.default_connection_id !== connectionId) {
+      onConnectionChange?.(saved.default_connection_id);
+    }
+    setShowLibrary(false);
+  };
+
+  const handleSavepoint = () => {
+    const name = prompt('Savepoint name');
+    if (name) {
+      createSavepoint(name);
+    }
+  };
+
+  const inTransaction = session !== null && session.status !== 'idle';
+  const savepoint = session?.savepoints.includes(selectedSavepoint)
+    ? selectedSavepoint
+    : session?.savepoints[session.savepoints.length - 1] ?? '';
+
   const handleKeyDown = (e: React.KeyboardEvent) => {
     if ((e.metaKey || e.ctrlKey) && e.key === 'Enter') {
       e.preventDefault();
@@ -74,10 +237,176 @@ export function QueryEditor({ connectionId, onConnectionChange }: QueryEditorPro
           <Play className="h-4 w-4 mr-2" />
           {loading ? 'Executing...' : 'Execute'}
         </Button>
+        <Button
+          onClick={handleExecuteScript}
+          disabled={loading || !connectionId}
+          variant="outline"
+          size="sm"
+        >
+          <ListOrdered className="h-4 w-4 mr-2" />
+          Run Script
+        </Button>
+        <Button
+          onClick={handleExplain}
+          disabled={loading || !connectionId}
+          variant="outline"
+          size="sm"
+        >
+          <Network className="h-4 w-4 mr-2" />
+          Explain
+        </Button>
+        <Button
+          onClick={() => {
+            setShowHistory(!showHistory);
+            setShowLibrary(false);
+          }}
+          variant={showHistory ? 'default' : 'outline'}
+          size="sm"
+        >
+          <History className="h-4 w-4 mr-2" />
+          History
+        </Button>
+        <Button
+          onClick={() => {
+            setShowLibrary(!showLibrary);
+            setShowHistory(false);
+          }}
+          variant={showLibrary ? 'default' : 'outline'}
+          size="sm"
+        >
+          <Library className="h-4 w-4 mr-2" />
+          Library
+        </Button>
+        <Button
+          onClick={() => setSaveDialogOpen(true)}
+          disabled={!query.trim()}
+          variant="outline"
+          size="sm"
+          title={savedQuery ? `Update "${savedQuery.name}"` : 'Save to the library'}
+        >
+          <Save className="h-4 w-4 mr-2" />
+          Save
+        </Button>
+        <label className="flex items-center gap-1 text-xs text-muted-foreground">
+          <input
+            type="checkbox"
+            checked={continueOnError}
+            onChange={(e) => setContinueOnError(e.target.checked)}
+          />
+          Continue on error
+        </label>
+        {loading && (
+          <Button onClick={cancelQuery} variant="outline" size="sm">
+            Cancel
+          </Button>
+        )}
         <span className="text-xs text-muted-foreground">
           Cmd/Ctrl+Enter to execute
         </span>
+        <div className="ml-auto flex items-center gap-2">
+          <span
+            className={
+              session?.status === 'failed'
+                ? 'text-xs text-destructive'
+                : 'text-xs text-muted-foreground'
+            }
+          >
+            {STATUS_LABELS[session?.status ?? 'idle']}
+          </span>
+          <label className="flex items-center gap-1 text-xs text-muted-foreground">
+            <input
+              type="checkbox"
+              checked={session?.auto_commit ?? true}
+              onChange={(e) => setAutoCommit(e.target.checked)}
+              disabled={loading || !connectionId || inTransaction}
+            />
+            Auto-commit
+          </label>
+          <Button
+            onClick={begin}
+            disabled={loading || !connectionId || inTransaction}
+            variant="outline"
+            size="sm"
+          >
+            Begin
+          </Button>
+          <Button onClick={commit} disabled={loading || !inTransaction} variant="outline" size="sm">
+            <Check className="h-4 w-4 mr-2" />
+            Commit
+          </Button>
+          <Button onClick={rollback} disabled={loading || !inTransaction} variant="outline" size="sm">
+            <Undo2 className="h-4 w-4 mr-2" />
+            Rollback
+          </Button>
+          <Button
+            onClick={handleSavepoint}
+            disabled={loading || !connectionId || session?.status === 'failed'}
+            variant="outline"
+            size="sm"
+          >
+            <Bookmark className="h-4 w-4 mr-2" />
+            Savepoint
+          </Button>
+          {session && session.savepoints.length > 0 && (
+            <>
+              <select
+                value={savepoint}
+                onChange={(e) => setSelectedSavepoint(e.target.value)}
+                className="h-8 rounded border bg-background px-2 text-xs"
+              >
+                {session.savepoints.map((name, idx) => (
+                  <option key={idx} value={name}>
+                    {name}
+                  </option>
+                ))}
+              </select>
+              <Button
+                onClick={() => rollbackToSavepoint(savepoint)}
+                disabled={loading}
+                variant="outline"
+                size="sm"
+              >
+                Roll back to
+              </Button>
+              <Button
+                onClick={() => releaseSavepoint(savepoint)}
+                disabled={loading}
+                variant="outline"
+                size="sm"
+              >
+                Release
+              </Button>
+            </>
+          )}
+        </div>
       </div>
+      {paramNames.length > 0 && (
+        <div className="flex flex-wrap items-center gap-2 px-2 py-1 border-b text-xs">
+          <span className="text-muted-foreground">Parameters</span>
+          {paramNames.map((name) => {
+            const description = savedQuery?.parameters.find((p) => p.name === name)?.description;
+            return (
+              <label key={name} className="flex items-center gap-1" title={description ?? undefined}>
+                <span className="font-mono">:{name}</span>
+                <input
+                  value={paramValues[name] ?? ''}
+                  onChange={(e) => setParamValues({ ...paramValues, [name]: e.target.value })}
+                  placeholder="NULL"
+                  className="h-7 w-32 rounded-md border bg-background px-2"
+                />
+              </label>
+            );
+          })}
+        </div>
+      )}
+      <SaveQueryDialog
+        open={saveDialogOpen}
+        onOpenChange={setSaveDialogOpen}
+        sql={query}
+        connectionId={connectionId}
+        existing={savedQuery}
+        onSaved={setSavedQuery}
+      />
       <div className="flex-1 flex flex-col min-h-0">
         <div className="flex-1 border-b">
           <Editor
@@ -86,8 +415,9 @@ export function QueryEditor({ connectionId, onConnectionChange }: QueryEditorPro
             theme={editorTheme}
             value={query}
             onChange={(value) => setQuery(value || '')}
-            onMount={(editor) => {
+            onMount={(editor, monaco) => {
               editorRef.current = editor;
+              monacoRef.current = monaco;
               editor.onKeyDown((e) => {
                 if ((e.metaKey || e.ctrlKey) && e.keyCode === 3) {
                   e.preventDefault();
@@ -104,46 +434,132 @@ export function QueryEditor({ connectionId, onConnectionChange }: QueryEditorPro
           />
         </div>
         <div className="flex-1 overflow-auto">
-          {error && (
-            <div className="p-4 text-destructive bg-destructive/10">
-              <strong>Error:</strong> {error}
+          {sessionError && (
+            <div className="p-4">
+              <ErrorPanel error={sessionError} />
             </div>
           )}
-          {result && (
-            <div className="p-4">
-              <div className="mb-2 text-sm text-muted-foreground">
-                {result.row_count} row{result.row_count !== 1 ? 's' : ''}
-              </div>
-              <Table>
-                <TableHeader>
-                  <TableRow>
-                    {result.columns.map((col) => (
-                      <TableHead key={col}>{col}</TableHead>
-                    ))}
-                  </TableRow>
-                </TableHeader>
-                <TableBody>
-                  {result.rows.map((row, idx) => (
-                    <TableRow key={idx}>
-                      {row.map((cell: any, cellIdx: number) => (
-                        <TableCell key={cellIdx}>
-                          {cell === null ? (
-                            <span className="text-muted-foreground">NULL</span>
-                          ) : typeof cell === 'object' ? (
-                            JSON.stringify(cell)
-                          ) : (
-                            String(cell)
-                          )}
-                        </TableCell>
-                      ))}
-                    </TableRow>
+          {showHistory ? (
+            <QueryHistory connectionId={connectionId} onSelect={handleHistorySelect} />
+          ) : showLibrary ? (
+            <QueryLibrary onOpen={handleOpenSaved} />
+          ) : planRequest && connectionId ? (
+            <QueryPlanViewer
+              key={planRequest.id}
+              connectionId={connectionId}
+              query={planRequest.query}
+              sessionId={session?.session_id}
+            />
+          ) : (
+            <>
+              {error && (
+                <div className="p-4">
+                  <ErrorPanel error={error} />
+                </div>
+              )}
+              {result && (
+                <div className="p-4">
+                  <div className="mb-2 text-sm text-muted-foreground">
+                    {result.row_count} row{result.row_count !== 1 ? 's' : ''}
+                    {result.has_more && ' (more available)'}
+                    {result.truncated && ' (truncated at the row limit)'}
+                  </div>
+                  <ResultGrid result={result} />
+                  {result.has_more && (
+                    <Button
+                      onClick={fetchMore}
+                      disabled={loading}
+                      variant="outline"
+                      size="sm"
+                      className="mt-2"
+                    >
+                      Load more
+                    </Button>
+                  )}
+                </div>
+              )}
+              {scriptResult && (
+                <div className="p-4 space-y-4">
+                  {scriptResult.rolled_back_open_transaction && (
+                    <div className="text-sm text-muted-foreground">
+                      The script left a transaction open; it was rolled back.
+                    </div>
+                  )}
+                  {scriptResult.statements.map((statement, idx) => (
+                    <div key={idx} className="border rounded">
+                      <div className="flex items-center gap-2 px-2 py-1 text-xs border-b bg-muted/50">
+                        <span className="text-muted-foreground">Line {statement.line}</span>
+                        <span className="font-mono truncate flex-1">{statement.sql}</span>
+                        {statement.status === 'ok' && (
+                          <span>
+                            {statement.command_tag} · {statement.duration_ms.toFixed(1)} ms
+                          </span>
+                        )}
+                        {statement.status === 'skipped' && (
+                          <span className="text-muted-foreground">skipped</span>
+                        )}
+                      </div>
+                      {statement.error && <ErrorPanel error={statement.error} />}
+                      {statement.result && (
+                        <div className="p-2">
+                          <ResultGrid result={statement.result} />
+                        </div>
+                      )}
+                    </div>
                   ))}
-                </TableBody>
-              </Table>
-            </div>
+                </div>
+              )}
+            </>
           )}
         </div>
       </div>
     </div>
   );
 }
+
+function ErrorPanel({ error }: { error: AppError }) {
+  return (
+    <div className="p-2 text-sm text-destructive bg-destructive/10 space-y-1">
+      <div>
+        <strong>Error:</strong> {error.message}
+        {error.db && <span className="ml-2 font-mono text-xs">SQLSTATE {error.db.code}</span>}
+      </div>
+      {error.db?.detail && <div>Detail: {error.db.detail}</div>}
+      {error.db?.hint && <div>Hint: {error.db.hint}</div>}
+      {error.db?.where && (
+        <div className="whitespace-pre-wrap text-xs">Context: {error.db.where}</div>
+      )}
+    </div>
+  );
+}
+
+function ResultGrid({ result }: { result: QueryResult }) {
+  return (
+    <Table>
+      <TableHeader>
+        <TableRow>
+          {result.columns.map((col, idx) => (
+            <TableHead key={idx}>{col}</TableHead>
+          ))}
+        </TableRow>
+      </TableHeader>
+      <TableBody>
+        {result.rows.map((row, idx) => (
+          <TableRow key={idx}>
+            {row.map((cell: any, cellIdx: number) => (
+              <TableCell key={cellIdx}>
+                {cell === null ? (
+                  <span className="text-muted-foreground">NULL</span>
+                ) : typeof cell === 'object' ? (
+                  JSON.stringify(cell)
+                ) : (
+                  String(cell)
+                )}
+              </TableCell>
+            ))}
+          </TableRow>
+        ))}
+      </TableBody>
+    </Table>
+  );
+}
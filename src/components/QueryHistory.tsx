@@ -0,0 +1,176 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Button } from './ui/Button';
+import { Input } from './ui/Input';
+import { AppError, toAppError } from '../lib/errors';
+
+export interface HistoryEntry {
+  id: number;
+  connection_id: string;
+  sql: string;
+  /** Milliseconds since the epoch. */
+  started_at: number;
+  duration_ms: number;
+  row_count: number | null;
+  succeeded: boolean;
+  error_code: string | null;
+  error_message: string | null;
+}
+
+interface QueryHistoryProps {
+  connectionId?: string;
+  onSelect: (sql: string) => void;
+}
+
+const PAGE_SIZE = 200;
+const DAY_MS = 24 * 60 * 60 * 1000;
+
+export function QueryHistory({ connectionId, onSelect }: QueryHistoryProps) {
+  const [text, setText] = useState('');
+  const [thisConnectionOnly, setThisConnectionOnly] = useState(true);
+  // yyyy-mm-dd from the date inputs; the end date is included
+  const [fromDate, setFromDate] = useState('');
+  const [toDate, setToDate] = useState('');
+  const [entries, setEntries] = useState<HistoryEntry[]>([]);
+  const [error, setError] = useState<AppError | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [notice, setNotice] = useState<string | null>(null);
+
+  useEffect(() => {
+    const timer = setTimeout(search, 200);
+    return () => clearTimeout(timer);
+  }, [text, thisConnectionOnly, fromDate, toDate, connectionId]);
+
+  const search = async () => {
+    setLoading(true);
+    try {
+      setError(null);
+      const found = await invoke<HistoryEntry[]>('search_history', {
+        filter: {
+          text: text || null,
+          connection_id: thisConnectionOnly ? connectionId ?? null : null,
+          from: fromDate ? new Date(`${fromDate}T00:00`).getTime() : null,
+          to: toDate ? new Date(`${toDate}T00:00`).getTime() + DAY_MS : null,
+          limit: PAGE_SIZE,
+        },
+      });
+      setEntries(found);
+    } catch (err) {
+      setError(toAppError(err, 'Failed to search query history'));
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const prune = async (prune: { before?: number; max_entries?: number }, description: string) => {
+    if (!confirm(`Delete ${description} from the query history?`)) return;
+    try {
+      setError(null);
+      const deleted = await invoke<number>('prune_history', { prune });
+      setNotice(`Deleted ${deleted} entr${deleted === 1 ? 'y' : 'ies'}`);
+      await search();
+    } catch (err) {
+      setError(toAppError(err, 'Failed to prune query history'));
+    }
+  };
+
+  return (
+    <div className="p-4 space-y-3">
+      <div className="flex flex-wrap items-center gap-2 text-xs">
+        <Input
+          value={text}
+          onChange={(e) => setText(e.target.value)}
+          placeholder="Search SQL"
+          className="h-8 w-64 text-xs"
+        />
+        <label className="flex items-center gap-1">
+          <input
+            type="checkbox"
+            checked={thisConnectionOnly}
+            disabled={!connectionId}
+            onChange={(e) => setThisConnectionOnly(e.target.checked)}
+          />
+          This connection only
+        </label>
+        <label className="flex items-center gap-1">
+          From
+          <input
+            type="date"
+            value={fromDate}
+            onChange={(e) => setFromDate(e.target.value)}
+            className="h-8 rounded-md border bg-background px-2"
+          />
+        </label>
+        <label className="flex items-center gap-1">
+          To
+          <input
+            type="date"
+            value={toDate}
+            onChange={(e) => setToDate(e.target.value)}
+            className="h-8 rounded-md border bg-background px-2"
+          />
+        </label>
+        <div className="ml-auto flex items-center gap-2">
+          <Button
+            onClick={() => prune({ before: Date.now() - 30 * DAY_MS }, 'entries older than 30 days')}
+            variant="outline"
+            size="sm"
+          >
+            Delete older than 30 days
+          </Button>
+          <Button
+            onClick={() => prune({ max_entries: 1000 }, 'all but the newest 1,000 entries')}
+            variant="outline"
+            size="sm"
+          >
+            Keep newest 1,000
+          </Button>
+        </div>
+      </div>
+
+      {error && (
+        <div className="p-2 text-sm text-destructive bg-destructive/10">
+          <strong>Error:</strong> {error.message}
+        </div>
+      )}
+      {notice && <div className="text-xs text-muted-foreground">{notice}</div>}
+
+      <div className="rounded-md border text-xs">
+        {entries.map((entry) => (
+          <div
+            key={entry.id}
+            className="px-2 py-1 border-b last:border-b-0 hover:bg-accent cursor-pointer"
+            onClick={() => onSelect(entry.sql)}
+            title="Open in the editor"
+          >
+            <div className="flex items-center gap-2 text-muted-foreground">
+              <span>{new Date(entry.started_at).toLocaleString()}</span>
+              <span>{entry.duration_ms.toFixed(1)} ms</span>
+              {entry.succeeded ? (
+                entry.row_count !== null && (
+                  <span>
+                    {entry.row_count} row{entry.row_count !== 1 ? 's' : ''}
+                  </span>
+                )
+              ) : (
+                <span className="text-destructive">
+                  {entry.error_code ? `${entry.error_code}: ` : ''}
+                  {entry.error_message}
+                </span>
+              )}
+            </div>
+            <div className="font-mono truncate">{entry.sql}</div>
+          </div>
+        ))}
+        {!loading && entries.length === 0 && (
+          <div className="p-2 text-muted-foreground">No matching queries.</div>
+        )}
+      </div>
+      {entries.length === PAGE_SIZE && (
+        <div className="text-xs text-muted-foreground">
+          Showing the newest {PAGE_SIZE}; narrow the search to see older queries.
+        </div>
+      )}
+    </div>
+  );
+}
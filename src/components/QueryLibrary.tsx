@@ -0,0 +1,381 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { Download, Folder, Trash2, Upload } from 'lucide-react';
+import { Button } from './ui/Button';
+import { Input } from './ui/Input';
+import { Label } from './ui/Label';
+import {
+  Dialog,
+  DialogContent,
+  DialogDescription,
+  DialogFooter,
+  DialogHeader,
+  DialogTitle,
+} from './ui/Dialog';
+import { AppError, toAppError } from '../lib/errors';
+
+export interface SavedQueryParameter {
+  name: string;
+  description: string | null;
+  default_value: any;
+}
+
+export interface SavedQuery {
+  id: string;
+  name: string;
+  /** `/`-separated; empty at the top level. */
+  folder: string;
+  description: string;
+  tags: string[];
+  sql: string;
+  default_connection_id: string | null;
+  parameters: SavedQueryParameter[];
+  created_at: number;
+  updated_at: number;
+}
+
+/** A parameter default as typed into an input; imported files may hold any JSON. */
+export function formatDefault(value: any): string {
+  if (value === null || value === undefined) return '';
+  return typeof value === 'string' ? value : JSON.stringify(value);
+}
+
+interface QueryLibraryProps {
+  onOpen: (query: SavedQuery) => void;
+}
+
+export function QueryLibrary({ onOpen }: QueryLibraryProps) {
+  const [queries, setQueries] = useState<SavedQuery[]>([]);
+  const [search, setSearch] = useState('');
+  const [tag, setTag] = useState('');
+  const [error, setError] = useState<AppError | null>(null);
+  const [notice, setNotice] = useState<string | null>(null);
+
+  useEffect(() => {
+    loadQueries();
+  }, []);
+
+  const loadQueries = async () => {
+    try {
+      setError(null);
+      setQueries(await invoke<SavedQuery[]>('get_saved_queries'));
+    } catch (err) {
+      setError(toAppError(err, 'Failed to load saved queries'));
+    }
+  };
+
+  const handleDelete = async (query: SavedQuery) => {
+    if (!confirm(`Delete the saved query "${query.name}"?`)) return;
+    try {
+      await invoke('delete_saved_query', { id: query.id });
+      await loadQueries();
+    } catch (err) {
+      setError(toAppError(err, 'Failed to delete saved query'));
+    }
+  };
+
+  const handleExport = async () => {
+    const path = prompt('Export the library to file', 'saved-queries.json');
+    if (!path) return;
+    try {
+      setError(null);
+      const count = await invoke<number>('export_saved_queries', { path });
+      setNotice(`Exported ${count} quer${count === 1 ? 'y' : 'ies'} to ${path}`);
+    } catch (err) {
+      setError(toAppError(err, 'Failed to export saved queries'));
+    }
+  };
+
+  const handleImport = async () => {
+    const path = prompt('Import saved queries from file');
+    if (!path) return;
+    try {
+      setError(null);
+      const summary = await invoke<{ added: number; updated: number }>('import_saved_queries', {
+        path,
+      });
+      setNotice(`Imported ${summary.added} new and ${summary.updated} updated queries`);
+      await loadQueries();
+    } catch (err) {
+      setError(toAppError(err, 'Failed to import saved queries'));
+    }
+  };
+
+  const tags = Array.from(new Set(queries.flatMap((query) => query.tags))).sort();
+  const needle = search.toLowerCase();
+  const visible = queries.filter(
+    (query) =>
+      (!tag || query.tags.includes(tag)) &&
+      (!needle ||
+        query.name.toLowerCase().includes(needle) ||
+        query.description.toLowerCase().includes(needle) ||
+        query.sql.toLowerCase().includes(needle))
+  );
+  // The store returns queries sorted by folder
+  const folders = Array.from(new Set(visible.map((query) => query.folder)));
+
+  return (
+    <div className="p-4 space-y-3">
+      <div className="flex items-center gap-2 text-xs">
+        <Input
+          value={search}
+          onChange={(e) => setSearch(e.target.value)}
+          placeholder="Search saved queries"
+          className="h-8 w-64 text-xs"
+        />
+        <select
+          value={tag}
+          onChange={(e) => setTag(e.target.value)}
+          className="h-8 rounded-md border bg-background px-2"
+        >
+          <option value="">All tags</option>
+          {tags.map((name) => (
+            <option key={name} value={name}>
+              {name}
+            </option>
+          ))}
+        </select>
+        <div className="ml-auto flex items-center gap-2">
+          <Button onClick={handleImport} variant="outline" size="sm">
+            <Upload className="h-4 w-4 mr-2" />
+            Import
+          </Button>
+          <Button onClick={handleExport} variant="outline" size="sm">
+            <Download className="h-4 w-4 mr-2" />
+            Export
+          </Button>
+        </div>
+      </div>
+
+      {error && (
+        <div className="p-2 text-sm text-destructive bg-destructive/10">
+          <strong>Error:</strong> {error.message}
+        </div>
+      )}
+      {notice && <div className="text-xs text-muted-foreground">{notice}</div>}
+
+      {folders.map((folder) => (
+        <div key={folder} className="rounded-md border text-xs">
+          <div className="flex items-center gap-1 px-2 py-1 border-b bg-muted/50 font-semibold">
+            <Folder className="h-3 w-3" />
+            {folder || 'Unfiled'}
+          </div>
+          {visible
+            .filter((query) => query.folder === folder)
+            .map((query) => (
+              <div
+                key={query.id}
+                className="group flex items-start gap-2 px-2 py-1 border-b last:border-b-0 hover:bg-accent cursor-pointer"
+                onClick={() => onOpen(query)}
+              >
+                <div className="flex-1 min-w-0">
+                  <div className="flex items-center gap-2">
+                    <strong>{query.name}</strong>
+                    {query.tags.map((name) => (
+                      <span key={name} className="rounded bg-muted px-1 text-muted-foreground">
+                        {name}
+                      </span>
+                    ))}
+                    {query.parameters.length > 0 && (
+                      <span className="text-muted-foreground">
+                        {query.parameters.map((p) => `:${p.name}`).join(' ')}
+                      </span>
+                    )}
+                  </div>
+                  {query.description && (
+                    <div className="text-muted-foreground">{query.description}</div>
+                  )}
+                  <div className="font-mono truncate text-muted-foreground">{query.sql}</div>
+                </div>
+                <button
+                  onClick={(e) => {
+                    e.stopPropagation();
+                    handleDelete(query);
+                  }}
+                  className="opacity-0 group-hover:opacity-100 text-muted-foreground hover:text-destructive"
+                  title="Delete"
+                >
+                  <Trash2 className="h-3 w-3" />
+                </button>
+              </div>
+            ))}
+        </div>
+      ))}
+      {visible.length === 0 && (
+        <div className="text-sm text-muted-foreground">
+          {queries.length === 0 ? 'No saved queries yet.' : 'No matching saved queries.'}
+        </div>
+      )}
+    </div>
+  );
+}
+
+interface SaveQueryDialogProps {
+  open: boolean;
+  onOpenChange: (open: boolean) => void;
+  sql: string;
+  connectionId?: string;
+  /** The saved query being edited, if the editor was opened from one. */
+  existing: SavedQuery | null;
+  onSaved: (query: SavedQuery) => void;
+}
+
+export function SaveQueryDialog({
+  open,
+  onOpenChange,
+  sql,
+  connectionId,
+  existing,
+  onSaved,
+}: SaveQueryDialogProps) {
+  const [name, setName] = useState('');
+  const [folder, setFolder] = useState('');
+  const [description, setDescription] = useState('');
+  const [tags, setTags] = useState('');
+  const [useConnection, setUseConnection] = useState(false);
+  const [asNew, setAsNew] = useState(false);
+  const [parameters, setParameters] = useState<SavedQueryParameter[]>([]);
+  const [error, setError] = useState<AppError | null>(null);
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    if (!open) return;
+    setName(existing?.name ?? '');
+    setFolder(existing?.folder ?? '');
+    setDescription(existing?.description ?? '');
+    setTags(existing?.tags.join(', ') ?? '');
+    setUseConnection(existing ? existing.default_connection_id !== null : false);
+    setAsNew(false);
+    setError(null);
+    invoke<string[]>('get_query_parameters', { sql }).then((names) =>
+      setParameters(
+        names.map(
+          (name) =>
+            existing?.parameters.find((p) => p.name === name) ?? {
+              name,
+              description: null,
+              default_value: null,
+            }
+        )
+      )
+    );
+  }, [open]);
+
+  const setParameter = (index: number, changes: Partial<SavedQueryParameter>) => {
+    setParameters(parameters.map((p, i) => (i === index ? { ...p, ...changes } : p)));
+  };
+
+  const handleSave = async () => {
+    setSaving(true);
+    try {
+      setError(null);
+      const saved = await invoke<SavedQuery>('save_query', {
+        request: {
+          id: existing && !asNew ? existing.id : null,
+          name,
+          folder,
+          description,
+          tags: tags.split(','),
+          sql,
+          default_connection_id: useConnection ? connectionId ?? null : null,
+          parameters,
+        },
+      });
+      onSaved(saved);
+      onOpenChange(false);
+    } catch (err) {
+      setError(toAppError(err, 'Failed to save query'));
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  return (
+    <Dialog open={open} onOpenChange={onOpenChange}>
+      <DialogContent className="sm:max-w-[500px]">
+        <DialogHeader>
+          <DialogTitle>{existing && !asNew ? 'Update Saved Query' : 'Save Query'}</DialogTitle>
+          <DialogDescription>Add the editor's SQL to the query library</DialogDescription>
+        </DialogHeader>
+        <div className="grid gap-4 py-4">
+          <div className="grid gap-2">
+            <Label htmlFor="saved-query-name">Name</Label>
+            <Input id="saved-query-name" value={name} onChange={(e) => setName(e.target.value)} />
+          </div>
+          <div className="grid grid-cols-2 gap-4">
+            <div className="grid gap-2">
+              <Label htmlFor="saved-query-folder">Folder</Label>
+              <Input
+                id="saved-query-folder"
+                value={folder}
+                onChange={(e) => setFolder(e.target.value)}
+                placeholder="diagnostics/locks"
+              />
+            </div>
+            <div className="grid gap-2">
+              <Label htmlFor="saved-query-tags">Tags</Label>
+              <Input
+                id="saved-query-tags"
+                value={tags}
+                onChange={(e) => setTags(e.target.value)}
+                placeholder="comma, separated"
+              />
+            </div>
+          </div>
+          <div className="grid gap-2">
+            <Label htmlFor="saved-query-description">Description</Label>
+            <Input
+              id="saved-query-description"
+              value={description}
+              onChange={(e) => setDescription(e.target.value)}
+            />
+          </div>
+          {parameters.length > 0 && (
+            <div className="grid gap-2">
+              <Label>Parameters</Label>
+              {parameters.map((parameter, idx) => (
+                <div key={parameter.name} className="grid grid-cols-[8rem_1fr_1fr] items-center gap-2">
+                  <span className="font-mono text-sm">:{parameter.name}</span>
+                  <Input
+                    value={parameter.description ?? ''}
+                    onChange={(e) => setParameter(idx, { description: e.target.value || null })}
+                    placeholder="Description"
+                  />
+                  <Input
+                    value={formatDefault(parameter.default_value)}
+                    onChange={(e) => setParameter(idx, { default_value: e.target.value || null })}
+                    placeholder="Default value"
+                  />
+                </div>
+              ))}
+            </div>
+          )}
+          <label className="flex items-center gap-2 text-sm">
+            <input
+              type="checkbox"
+              checked={useConnection}
+              disabled={!connectionId}
+              onChange={(e) => setUseConnection(e.target.checked)}
+            />
+            Open on the current connection by default
+          </label>
+          {existing && (
+            <label className="flex items-center gap-2 text-sm">
+              <input type="checkbox" checked={asNew} onChange={(e) => setAsNew(e.target.checked)} />
+              Save as a new query
+            </label>
+          )}
+          {error && <div className="text-sm text-destructive">{error.message}</div>}
+        </div>
+        <DialogFooter>
+          <Button variant="outline" onClick={() => onOpenChange(false)}>
+            Cancel
+          </Button>
+          <Button onClick={handleSave} disabled={saving || !name.trim()}>
+            {saving ? 'Saving...' : 'Save'}
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -1,22 +1,130 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import {
-  Table,
-  TableBody,
-  TableCell,
-  TableHead,
-  TableHeader,
-  TableRow,
-} from './ui/Table';
+import { AlertTriangle, ChevronDown, ChevronRight } from 'lucide-react';
+import { Button } from './ui/Button';
+import { AppError, toAppError } from '../lib/errors';
+import { PlanComparison, PlanComparisonView } from './PlanComparisonView';
 
 interface QueryPlanViewerProps {
   connectionId: string;
   query: string;
+  sessionId?: string;
 }
 
-export function QueryPlanViewer({ connectionId, query }: QueryPlanViewerProps) {
-  const [plan, setPlan] = useState<string>('');
+export interface ExplainOptions {
+  analyze: boolean;
+  buffers: boolean;
+  verbose: boolean;
+  settings: boolean;
+  wal: boolean;
+  format: 'text' | 'json';
+}
+
+export interface BufferUsage {
+  shared_hit: number;
+  shared_read: number;
+  shared_dirtied: number;
+  shared_written: number;
+  local_hit: number;
+  local_read: number;
+  local_dirtied: number;
+  local_written: number;
+  temp_read: number;
+  temp_written: number;
+}
+
+export interface PlanNode {
+  id: number;
+  node_type: string;
+  parent_relationship: string | null;
+  relation_name: string | null;
+  schema: string | null;
+  alias: string | null;
+  index_name: string | null;
+  join_type: string | null;
+  startup_cost: number;
+  total_cost: number;
+  plan_rows: number;
+  plan_width: number;
+  actual_startup_time_ms: number | null;
+  actual_total_time_ms: number | null;
+  actual_rows: number | null;
+  actual_loops: number | null;
+  rows_removed_by_filter: number | null;
+  exclusive_time_ms: number | null;
+  estimate_factor: number | null;
+  filter: string | null;
+  index_cond: string | null;
+  output: string[];
+  buffers: BufferUsage | null;
+  wal: { records: number; full_page_images: number; bytes: number } | null;
+  details: Record<string, any>;
+  children: PlanNode[];
+}
+
+export interface QueryPlan {
+  root: PlanNode;
+  planning_time_ms: number | null;
+  execution_time_ms: number | null;
+  settings: Record<string, string>;
+  triggers: Array<{ name: string; relation: string | null; time_ms: number; calls: number }>;
+  findings: PlanFinding[];
+  raw: any;
+}
+
+export type FindingSeverity = 'info' | 'warning' | 'critical';
+
+export interface PlanFinding {
+  node_id: number;
+  kind:
+    | 'hotspot'
+    | 'misestimate'
+    | 'seq-scan-on-large-table'
+    | 'disk-spill'
+    | 'nested-loop-with-many-loops'
+    | 'filter-removes-most-rows';
+  severity: FindingSeverity;
+  message: string;
+  time_share: number | null;
+}
+
+const SEVERITY_CLASSES: Record<FindingSeverity, string> = {
+  info: 'text-muted-foreground',
+  warning: 'text-yellow-600 dark:text-yellow-400',
+  critical: 'text-destructive',
+};
+
+interface ExplainResult {
+  text: string | null;
+  plan: QueryPlan | null;
+  rolled_back: boolean;
+}
+
+const OPTION_LABELS: Array<{ key: Exclude<keyof ExplainOptions, 'format'>; label: string }> = [
+  { key: 'analyze', label: 'Analyze' },
+  { key: 'buffers', label: 'Buffers' },
+  { key: 'verbose', label: 'Verbose' },
+  { key: 'settings', label: 'Settings' },
+  { key: 'wal', label: 'WAL' },
+];
+
+export function QueryPlanViewer({ connectionId, query, sessionId }: QueryPlanViewerProps) {
+  const [options, setOptions] = useState<ExplainOptions>({
+    analyze: false,
+    buffers: false,
+    verbose: false,
+    settings: false,
+    wal: false,
+    format: 'json',
+  });
+  const [result, setResult] = useState<ExplainResult | null>(null);
+  const [error, setError] = useState<AppError | null>(null);
   const [loading, setLoading] = useState(false);
+  // A plan from an earlier run, or pasted EXPLAIN (FORMAT JSON) text
+  const [baseline, setBaseline] = useState<{ label: string; plan: QueryPlan | string } | null>(null);
+  const [pasting, setPasting] = useState(false);
+  const [pastedPlan, setPastedPlan] = useState('');
+  const [comparison, setComparison] = useState<PlanComparison | null>(null);
 
   useEffect(() => {
     if (query && connectionId) {
@@ -27,29 +135,304 @@ export function QueryPlanViewer({ connectionId, query }: QueryPlanViewerProps) {
   const loadPlan = async () => {
     setLoading(true);
     try {
-      const result = await invoke<string>('explain_query', {
+      setError(null);
+      const explained = await invoke<ExplainResult>('explain_query', {
         request: {
           connection_id: connectionId,
           query,
+          session_id: sessionId,
+          options,
         },
       });
-      setPlan(result);
-    } catch (error) {
-      console.error('Failed to load query plan:', error);
+      setResult(explained);
+      setComparison(null);
+    } catch (err) {
+      setError(toAppError(err, 'Failed to load query plan'));
     } finally {
       setLoading(false);
     }
   };
 
-  if (loading) {
-    return <div className="p-4">Loading plan...</div>;
+  const comparePlans = async () => {
+    if (!baseline || !result?.plan) return;
+    try {
+      setError(null);
+      const compared = await invoke<PlanComparison>('compare_plans', {
+        request: { before: baseline.plan, after: result.plan },
+      });
+      setComparison(compared);
+    } catch (err) {
+      setError(toAppError(err, 'Failed to compare plans'));
+    }
+  };
+
+  const adoptPastedPlan = () => {
+    setBaseline({ label: 'pasted plan', plan: pastedPlan });
+    setPasting(false);
+    setPastedPlan('');
+    setComparison(null);
+  };
+
+  const setOption = (key: keyof ExplainOptions, value: any) => {
+    const next = { ...options, [key]: value };
+    // WAL usage is only measured while the statement runs
+    if (key === 'analyze' && !value) {
+      next.wal = false;
+    }
+    setOptions(next);
+  };
+
+  return (
+    <div className="p-4 space-y-3">
+      <div className="flex items-center gap-3 text-xs">
+        {OPTION_LABELS.map(({ key, label }) => (
+          <label key={key} className="flex items-center gap-1">
+            <input
+              type="checkbox"
+              checked={options[key]}
+              disabled={key === 'wal' && !options.analyze}
+              onChange={(e) => setOption(key, e.target.checked)}
+            />
+            {label}
+          </label>
+        ))}
+        <select
+          value={options.format}
+          onChange={(e) => setOption('format', e.target.value)}
+          className="h-7 rounded-md border bg-background px-2"
+        >
+          <option value="json">Tree</option>
+          <option value="text">Text</option>
+        </select>
+        <Button onClick={loadPlan} disabled={loading} variant="outline" size="sm">
+          {loading ? 'Explaining...' : 'Explain'}
+        </Button>
+        {options.analyze && (
+          <span className="text-muted-foreground">
+            Analyze runs the statement; its changes are rolled back.
+          </span>
+        )}
+      </div>
+
+      <div className="flex items-center gap-2 text-xs">
+        {result?.plan && (
+          <Button
+            onClick={() => {
+              setBaseline({ label: `plan of ${new Date().toLocaleTimeString()}`, plan: result.plan! });
+              setComparison(null);
+            }}
+            variant="outline"
+            size="sm"
+          >
+            Use as Baseline
+          </Button>
+        )}
+        <Button onClick={() => setPasting(!pasting)} variant="outline" size="sm">
+          Paste Baseline
+        </Button>
+        {baseline && (
+          <>
+            <span className="text-muted-foreground">Baseline: {baseline.label}</span>
+            {result?.plan &&
+              (comparison ? (
+                <Button onClick={() => setComparison(null)} variant="outline" size="sm">
+                  Hide Comparison
+                </Button>
+              ) : (
+                <Button onClick={comparePlans} variant="outline" size="sm">
+                  Compare with Baseline
+                </Button>
+              ))}
+          </>
+        )}
+      </div>
+
+      {pasting && (
+        <div className="space-y-2">
+          <textarea
+            value={pastedPlan}
+            onChange={(e) => setPastedPlan(e.target.value)}
+            placeholder="Paste the output of EXPLAIN (FORMAT JSON)"
+            className="w-full h-32 rounded-md border bg-background p-2 text-xs font-mono"
+          />
+          <Button onClick={adoptPastedPlan} disabled={!pastedPlan.trim()} size="sm">
+            Use Pasted Plan
+          </Button>
+        </div>
+      )}
+
+      {comparison && <PlanComparisonView comparison={comparison} />}
+
+      {error && (
+        <div className="p-2 text-sm text-destructive bg-destructive/10">
+          <strong>Error:</strong> {error.message}
+        </div>
+      )}
+
+      {result?.text && (
+        <pre className="bg-muted p-4 rounded-md overflow-auto text-sm font-mono">{result.text}</pre>
+      )}
+
+      {result?.plan && !comparison && (
+        <div className="space-y-2">
+          <div className="text-xs text-muted-foreground">
+            {result.plan.planning_time_ms !== null &&
+              `Planning ${result.plan.planning_time_ms.toFixed(3)} ms`}
+            {result.plan.execution_time_ms !== null &&
+              ` · Execution ${result.plan.execution_time_ms.toFixed(3)} ms`}
+            {result.rolled_back && ' · rolled back'}
+          </div>
+          {result.plan.findings.length > 0 && (
+            <div className="rounded-md border text-xs">
+              {result.plan.findings.map((finding, idx) => (
+                <div key={idx} className="flex items-start gap-2 px-2 py-1 border-b last:border-b-0">
+                  <AlertTriangle
+                    className={`h-3 w-3 mt-0.5 shrink-0 ${SEVERITY_CLASSES[finding.severity]}`}
+                  />
+                  <span className="flex-1">{finding.message}</span>
+                  <span className="text-muted-foreground">#{finding.node_id}</span>
+                </div>
+              ))}
+            </div>
+          )}
+          <div className="rounded-md border text-sm font-mono">
+            <PlanNodeView node={result.plan.root} depth={0} findings={result.plan.findings} />
+          </div>
+          {result.plan.triggers.length > 0 && (
+            <div className="text-xs">
+              {result.plan.triggers.map((trigger) => (
+                <div key={trigger.name}>
+                  Trigger {trigger.name}
+                  {trigger.relation && ` on ${trigger.relation}`}: {trigger.time_ms.toFixed(3)} ms,{' '}
+                  {trigger.calls} calls
+                </div>
+              ))}
+            </div>
+          )}
+          {Object.keys(result.plan.settings).length > 0 && (
+            <div className="text-xs text-muted-foreground">
+              Settings:{' '}
+              {Object.entries(result.plan.settings)
+                .map(([name, value]) => `${name} = ${value}`)
+                .join(', ')}
+            </div>
+          )}
+        </div>
+      )}
+
+      {!loading && !result && !error && (
+        <div className="text-sm text-muted-foreground">
+          No plan available. Explain a query to see its execution plan.
+        </div>
+      )}
+    </div>
+  );
+}
+
+function describeNode(node: PlanNode): string {
+  let label = node.node_type;
+  if (node.join_type && !label.includes(node.join_type)) {
+    label = `${node.join_type} ${label}`;
   }
+  if (node.index_name) {
+    label += ` using ${node.index_name}`;
+  }
+  if (node.relation_name) {
+    label += ` on ${node.schema ? `${node.schema}.` : ''}${node.relation_name}`;
+    if (node.alias && node.alias !== node.relation_name) {
+      label += ` ${node.alias}`;
+    }
+  }
+  return label;
+}
+
+function PlanNodeView({
+  node,
+  depth,
+  findings,
+}: {
+  node: PlanNode;
+  depth: number;
+  findings: PlanFinding[];
+}) {
+  const [expanded, setExpanded] = useState(true);
+  const nodeFindings = findings.filter((finding) => finding.node_id === node.id);
 
   return (
-    <div className="p-4">
-      <pre className="bg-muted p-4 rounded-md overflow-auto text-sm font-mono">
-        {plan || 'No plan available. Execute a query to see its execution plan.'}
-      </pre>
+    <div>
+      <div
+        className="flex items-start gap-1 px-2 py-1 border-b hover:bg-accent cursor-pointer"
+        style={{ paddingLeft: `${depth * 16 + 8}px` }}
+        onClick={() => setExpanded(!expanded)}
+      >
+        {node.children.length > 0 ? (
+          expanded ? (
+            <ChevronDown className="h-3 w-3 mt-1 shrink-0" />
+          ) : (
+            <ChevronRight className="h-3 w-3 mt-1 shrink-0" />
+          )
+        ) : (
+          <div className="w-3 shrink-0" />
+        )}
+        <div className="flex-1 min-w-0">
+          <div>
+            <span className="mr-1 text-xs text-muted-foreground">#{node.id}</span>
+            <strong>{describeNode(node)}</strong>
+            {node.parent_relationship && (
+              <span className="ml-2 text-xs text-muted-foreground">{node.parent_relationship}</span>
+            )}
+          </div>
+          <div className="text-xs text-muted-foreground">
+            cost {node.startup_cost.toFixed(2)}..{node.total_cost.toFixed(2)} · rows{' '}
+            {node.plan_rows}
+            {node.actual_rows !== null &&
+              ` · actual ${node.actual_total_time_ms?.toFixed(3)} ms, rows ${node.actual_rows}, loops ${node.actual_loops}`}
+            {node.rows_removed_by_filter !== null &&
+              ` · removed by filter ${node.rows_removed_by_filter}`}
+            {node.exclusive_time_ms !== null &&
+              ` · self ${node.exclusive_time_ms.toFixed(3)} ms`}
+            {node.estimate_factor !== null &&
+              node.estimate_factor !== 1 &&
+              ` · estimate ${
+                node.estimate_factor > 1
+                  ? `${node.estimate_factor.toFixed(1)}x under`
+                  : `${(1 / node.estimate_factor).toFixed(1)}x over`
+              }`}
+          </div>
+          {nodeFindings.map((finding, idx) => (
+            <div key={idx} className={`text-xs ${SEVERITY_CLASSES[finding.severity]}`}>
+              {finding.message}
+            </div>
+          ))}
+          {(node.index_cond || node.filter) && (
+            <div className="text-xs">
+              {node.index_cond && <div>Index Cond: {node.index_cond}</div>}
+              {node.filter && <div>Filter: {node.filter}</div>}
+            </div>
+          )}
+          {node.buffers && (
+            <div className="text-xs text-muted-foreground">
+              buffers shared hit {node.buffers.shared_hit} read {node.buffers.shared_read}
+              {(node.buffers.temp_read > 0 || node.buffers.temp_written > 0) &&
+                ` · temp read ${node.buffers.temp_read} written ${node.buffers.temp_written}`}
+            </div>
+          )}
+          {node.wal && node.wal.records > 0 && (
+            <div className="text-xs text-muted-foreground">
+              WAL {node.wal.records} records, {node.wal.bytes} bytes
+            </div>
+          )}
+          {node.output.length > 0 && (
+            <div className="text-xs text-muted-foreground truncate">
+              Output: {node.output.join(', ')}
+            </div>
+          )}
+        </div>
+      </div>
+      {expanded &&
+        node.children.map((child, idx) => (
+          <PlanNodeView key={idx} node={child} depth={depth + 1} findings={findings} />
+        ))}
     </div>
   );
 }
@@ -16,9 +16,15 @@ interface SidebarProps {
   selectedConnectionId?: string;
   onConnectionSelect: (id: string) => void;
   onTableSelect?: (schema: string, table: string) => void;
+  onShowDdl?: (label: string, ddl: string) => void;
 }
 
-export function Sidebar({ selectedConnectionId, onConnectionSelect, onTableSelect }: SidebarProps) {
+export function Sidebar({
+  selectedConnectionId,
+  onConnectionSelect,
+  onTableSelect,
+  onShowDdl,
+}: SidebarProps) {
   const { connections, reload } = useConnections();
   const [connectionDialogOpen, setConnectionDialogOpen] = useState(false);
   const [editingConnection, setEditingConnection] = useState<ConnectionConfig | undefined>();
@@ -128,6 +134,7 @@ export function Sidebar({ selectedConnectionId, onConnectionSelect, onTableSelec
               <DatabaseTree
                 connectionId={selectedConnectionId}
                 onTableSelect={onTableSelect}
+                onShowDdl={onShowDdl}
               />
             </div>
           </>
@@ -12,6 +12,8 @@ export interface Tab {
   type?: 'query' | 'table';
   schema?: string;
   table?: string;
+  /** Starting text of a query tab. */
+  initialQuery?: string;
 }
 
 interface TabManagerProps {
@@ -84,7 +86,9 @@ export function TabManager({
             />
           ) : (
             <QueryEditor
+              tabId={tab.id}
               connectionId={tab.connectionId}
+              initialQuery={tab.initialQuery}
               onConnectionChange={(id) => {
                 // Update tab connection
               }}
@@ -1,4 +1,5 @@
 import { useState, useEffect } from 'react';
+import { ArrowDown, ArrowUp, X } from 'lucide-react';
 import {
   Table,
   TableBody,
@@ -10,6 +11,7 @@ import {
 import { Button } from './ui/Button';
 import { Input } from './ui/Input';
 import { QueryResult } from '../hooks/useQuery';
+import { AppError, toAppError } from '../lib/errors';
 
 interface TableViewProps {
   connectionId: string;
@@ -17,31 +19,142 @@ interface TableViewProps {
   table: string;
 }
 
+interface SortColumn {
+  column: string;
+  descending: boolean;
+}
+
+type FilterOperator =
+  | 'eq'
+  | 'not-eq'
+  | 'lt'
+  | 'lt-eq'
+  | 'gt'
+  | 'gt-eq'
+  | 'like'
+  | 'not-like'
+  | 'ilike'
+  | 'not-ilike'
+  | 'in'
+  | 'not-in'
+  | 'between'
+  | 'is-null'
+  | 'is-not-null';
+
+interface Filter {
+  column: string;
+  operator: FilterOperator;
+  value: any;
+}
+
+interface TableData extends QueryResult {
+  total_rows: number;
+  total_is_estimate: boolean;
+  /** Keyset pages follow the cursors; offset paging is the fallback for tables without a key. */
+  paging: 'keyset' | 'offset';
+  next_cursor: string | null;
+  prev_cursor: string | null;
+}
+
+/** Where a page starts: a keyset cursor or, for tables without a key, an offset. */
+interface PageStart {
+  cursor: string | null;
+  offset: number;
+}
+
+const FIRST_PAGE: PageStart = { cursor: null, offset: 0 };
+
+const OPERATORS: Array<{ value: FilterOperator; label: string }> = [
+  { value: 'eq', label: '=' },
+  { value: 'not-eq', label: '<>' },
+  { value: 'lt', label: '<' },
+  { value: 'lt-eq', label: '<=' },
+  { value: 'gt', label: '>' },
+  { value: 'gt-eq', label: '>=' },
+  { value: 'like', label: 'LIKE' },
+  { value: 'not-like', label: 'NOT LIKE' },
+  { value: 'ilike', label: 'ILIKE' },
+  { value: 'not-ilike', label: 'NOT ILIKE' },
+  { value: 'in', label: 'IN' },
+  { value: 'not-in', label: 'NOT IN' },
+  { value: 'between', label: 'BETWEEN' },
+  { value: 'is-null', label: 'IS NULL' },
+  { value: 'is-not-null', label: 'IS NOT NULL' },
+];
+
+const PAGE_SIZE = 100;
+
+/** Turns the filter input into the value shape the operator expects. */
+function filterValue(operator: FilterOperator, input: string): any {
+  switch (operator) {
+    case 'is-null':
+    case 'is-not-null':
+      return null;
+    case 'in':
+    case 'not-in':
+      return input.split(',').map((item) => item.trim());
+    case 'between': {
+      const [low, high] = input.split('..').map((bound) => bound.trim());
+      return [low || null, high || null];
+    }
+    default:
+      return input;
+  }
+}
+
+function describeFilter(filter: Filter): string {
+  const label = OPERATORS.find((op) => op.value === filter.operator)?.label;
+  if (filter.value === null) {
+    return `${filter.column} ${label}`;
+  }
+  if (filter.operator === 'between') {
+    return `${filter.column} BETWEEN ${filter.value[0] ?? '-∞'} AND ${filter.value[1] ?? '∞'}`;
+  }
+  return `${filter.column} ${label} ${Array.isArray(filter.value) ? `(${filter.value.join(', ')})` : filter.value}`;
+}
+
 export function TableView({ connectionId, schema, table }: TableViewProps) {
   const [data, setData] = useState<any[]>([]);
   const [columns, setColumns] = useState<string[]>([]);
   const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<AppError | null>(null);
   const [editingCell, setEditingCell] = useState<{ row: number; col: number } | null>(null);
   const [editValue, setEditValue] = useState('');
+  const [orderBy, setOrderBy] = useState<SortColumn[]>([]);
+  const [filters, setFilters] = useState<Filter[]>([]);
+  const [pageStart, setPageStart] = useState<PageStart>(FIRST_PAGE);
+  // Position of the first row shown, for the row counter
+  const [position, setPosition] = useState(0);
+  const [page, setPage] = useState<TableData | null>(null);
+  const [total, setTotal] = useState<{ rows: number; estimate: boolean } | null>(null);
+  const [filterColumn, setFilterColumn] = useState('');
+  const [filterOperator, setFilterOperator] = useState<FilterOperator>('eq');
+  const [filterInput, setFilterInput] = useState('');
 
   // Load table data
   const loadData = async () => {
     setLoading(true);
     try {
+      setError(null);
       const { invoke } = await import('@tauri-apps/api/core');
-      const result = await invoke<QueryResult>('get_table_data', {
+      const result = await invoke<TableData>('get_table_data', {
         request: {
           connection_id: connectionId,
           schema,
           table,
-          limit: 100,
-          offset: 0,
+          limit: PAGE_SIZE,
+          offset: pageStart.offset,
+          cursor: pageStart.cursor,
+          order_by: orderBy,
+          filters,
         },
       });
       setData(result.rows || []);
       setColumns(result.columns || []);
-    } catch (error) {
-      console.error('Failed to load table data:', error);
+      setPage(result);
+      setTotal({ rows: result.total_rows, estimate: result.total_is_estimate });
+    } catch (err) {
+      setError(toAppError(err, 'Failed to load table data'));
     } finally {
       setLoading(false);
     }
@@ -49,9 +162,86 @@ export function TableView({ connectionId, schema, table }: TableViewProps) {
 
   // Load data when component mounts or connection/table changes
   useEffect(() => {
-    loadData();
+    setOrderBy([]);
+    setFilters([]);
+    goToFirstPage();
   }, [connectionId, schema, table]);
 
+  useEffect(() => {
+    loadData();
+  }, [connectionId, schema, table, orderBy, filters, pageStart]);
+
+  const goToFirstPage = () => {
+    setPageStart(FIRST_PAGE);
+    setPosition(0);
+  };
+
+  const handleNextPage = () => {
+    if (!page) {
+      return;
+    }
+    setPosition(position + data.length);
+    setPageStart(
+      page.paging === 'keyset'
+        ? { cursor: page.next_cursor, offset: 0 }
+        : { cursor: null, offset: pageStart.offset + PAGE_SIZE }
+    );
+  };
+
+  const handlePrevPage = () => {
+    if (!page) {
+      return;
+    }
+    setPosition(Math.max(0, position - PAGE_SIZE));
+    setPageStart(
+      page.paging === 'keyset'
+        ? { cursor: page.prev_cursor, offset: 0 }
+        : { cursor: null, offset: Math.max(0, pageStart.offset - PAGE_SIZE) }
+    );
+  };
+
+  const hasNextPage = page
+    ? page.paging === 'keyset'
+      ? page.next_cursor !== null
+      : data.length === PAGE_SIZE
+    : false;
+  const hasPrevPage = page
+    ? page.paging === 'keyset'
+      ? page.prev_cursor !== null
+      : pageStart.offset > 0
+    : false;
+
+  // Cycles a column through ascending, descending and unsorted
+  const handleSort = (column: string) => {
+    const current = orderBy.find((sort) => sort.column === column);
+    goToFirstPage();
+    if (!current) {
+      setOrderBy([{ column, descending: false }]);
+    } else if (!current.descending) {
+      setOrderBy([{ column, descending: true }]);
+    } else {
+      setOrderBy([]);
+    }
+  };
+
+  const handleAddFilter = () => {
+    const column = filterColumn || columns[0];
+    if (!column) {
+      return;
+    }
+    goToFirstPage();
+    setFilters([
+      ...filters,
+      { column, operator: filterOperator, value: filterValue(filterOperator, filterInput) },
+    ]);
+    setFilterInput('');
+  };
+
+  const handleRemoveFilter = (index: number) => {
+    goToFirstPage();
+    setFilters(filters.filter((_, idx) => idx !== index));
+  };
+
   const handleCellClick = (row: number, col: number) => {
     setEditingCell({ row, col });
     setEditValue(data[row]?.[col] || '');
@@ -67,9 +257,7 @@ export function TableView({ connectionId, schema, table }: TableViewProps) {
     }
   };
 
-  if (loading) {
-    return <div className="p-4">Loading...</div>;
-  }
+  const needsValue = filterOperator !== 'is-null' && filterOperator !== 'is-not-null';
 
   return (
     <div className="flex flex-col h-full">
@@ -86,51 +274,162 @@ export function TableView({ connectionId, schema, table }: TableViewProps) {
           </Button>
         </div>
       </div>
+      <div className="p-2 border-b flex flex-wrap items-center gap-2">
+        <select
+          value={filterColumn || columns[0] || ''}
+          onChange={(e) => setFilterColumn(e.target.value)}
+          className="h-8 rounded border bg-background px-2 text-sm"
+        >
+          {columns.map((col) => (
+            <option key={col} value={col}>
+              {col}
+            </option>
+          ))}
+        </select>
+        <select
+          value={filterOperator}
+          onChange={(e) => setFilterOperator(e.target.value as FilterOperator)}
+          className="h-8 rounded border bg-background px-2 text-sm"
+        >
+          {OPERATORS.map((op) => (
+            <option key={op.value} value={op.value}>
+              {op.label}
+            </option>
+          ))}
+        </select>
+        {needsValue && (
+          <Input
+            value={filterInput}
+            onChange={(e) => setFilterInput(e.target.value)}
+            onKeyDown={(e) => {
+              if (e.key === 'Enter') {
+                handleAddFilter();
+              }
+            }}
+            placeholder={
+              filterOperator === 'in' || filterOperator === 'not-in'
+                ? 'a, b, c'
+                : filterOperator === 'between'
+                  ? 'low..high'
+                  : 'value'
+            }
+            className="h-8 w-48"
+          />
+        )}
+        <Button variant="outline" size="sm" onClick={handleAddFilter}>
+          Add filter
+        </Button>
+        {filters.map((filter, idx) => (
+          <span
+            key={idx}
+            className="flex items-center gap-1 rounded border px-2 py-1 text-xs font-mono"
+          >
+            {describeFilter(filter)}
+            <button onClick={() => handleRemoveFilter(idx)} className="hover:text-destructive">
+              <X className="h-3 w-3" />
+            </button>
+          </span>
+        ))}
+      </div>
+      {error && (
+        <div className="p-4 text-sm text-destructive bg-destructive/10">
+          <strong>Error:</strong> {error.message}
+        </div>
+      )}
       <div className="flex-1 overflow-auto">
-        <Table>
-          <TableHeader>
-            <TableRow>
-              {columns.map((col) => (
-                <TableHead key={col}>{col}</TableHead>
-              ))}
-            </TableRow>
-          </TableHeader>
-          <TableBody>
-            {data.map((row, rowIdx) => (
-              <TableRow key={rowIdx}>
-                {row.map((cell: any, colIdx: number) => (
-                  <TableCell
-                    key={colIdx}
-                    onClick={() => handleCellClick(rowIdx, colIdx)}
-                    className="cursor-pointer"
-                  >
-                    {editingCell?.row === rowIdx && editingCell?.col === colIdx ? (
-                      <Input
-                        value={editValue}
-                        onChange={(e) => setEditValue(e.target.value)}
-                        onBlur={handleSave}
-                        onKeyDown={(e) => {
-                          if (e.key === 'Enter') {
-                            handleSave();
-                          } else if (e.key === 'Escape') {
-                            setEditingCell(null);
-                          }
-                        }}
-                        autoFocus
-                      />
-                    ) : (
-                      cell === null ? (
-                        <span className="text-muted-foreground">NULL</span>
-                      ) : (
-                        String(cell)
-                      )
-                    )}
-                  </TableCell>
-                ))}
+        {loading ? (
+          <div className="p-4">Loading...</div>
+        ) : (
+          <Table>
+            <TableHeader>
+              <TableRow>
+                {columns.map((col) => {
+                  const sort = orderBy.find((s) => s.column === col);
+                  return (
+                    <TableHead
+                      key={col}
+                      onClick={() => handleSort(col)}
+                      className="cursor-pointer select-none"
+                    >
+                      <span className="flex items-center gap-1">
+                        {col}
+                        {sort &&
+                          (sort.descending ? (
+                            <ArrowDown className="h-3 w-3" />
+                          ) : (
+                            <ArrowUp className="h-3 w-3" />
+                          ))}
+                      </span>
+                    </TableHead>
+                  );
+                })}
               </TableRow>
-            ))}
-          </TableBody>
-        </Table>
+            </TableHeader>
+            <TableBody>
+              {data.map((row, rowIdx) => (
+                <TableRow key={rowIdx}>
+                  {row.map((cell: any, colIdx: number) => (
+                    <TableCell
+                      key={colIdx}
+                      onClick={() => handleCellClick(rowIdx, colIdx)}
+                      className="cursor-pointer"
+                    >
+                      {editingCell?.row === rowIdx && editingCell?.col === colIdx ? (
+                        <Input
+                          value={editValue}
+                          onChange={(e) => setEditValue(e.target.value)}
+                          onBlur={handleSave}
+                          onKeyDown={(e) => {
+                            if (e.key === 'Enter') {
+                              handleSave();
+                            } else if (e.key === 'Escape') {
+                              setEditingCell(null);
+                            }
+                          }}
+                          autoFocus
+                        />
+                      ) : (
+                        cell === null ? (
+                          <span className="text-muted-foreground">NULL</span>
+                        ) : typeof cell === 'object' ? (
+                          JSON.stringify(cell)
+                        ) : (
+                          String(cell)
+                        )
+                      )}
+                    </TableCell>
+                  ))}
+                </TableRow>
+              ))}
+            </TableBody>
+          </Table>
+        )}
+      </div>
+      <div className="p-2 border-t flex items-center justify-between text-sm text-muted-foreground">
+        <span>
+          {total &&
+            `Rows ${data.length > 0 ? position + 1 : 0}–${position + data.length} of ${
+              total.estimate ? '~' : ''
+            }${total.rows.toLocaleString()}`}
+        </span>
+        <div className="flex gap-2">
+          <Button
+            variant="outline"
+            size="sm"
+            onClick={handlePrevPage}
+            disabled={loading || !hasPrevPage}
+          >
+            Previous
+          </Button>
+          <Button
+            variant="outline"
+            size="sm"
+            onClick={handleNextPage}
+            disabled={loading || !hasNextPage}
+          >
+            Next
+          </Button>
+        </div>
       </div>
     </div>
   );
@@ -1,6 +1,18 @@
 import { invoke } from '@tauri-apps/api/core';
 import { useState, useEffect } from 'react';
 
+export type SslMode = 'disable' | 'prefer' | 'require' | 'verify-ca' | 'verify-full';
+
+export interface SshTunnelConfig {
+  host: string;
+  port: number;
+  username: string;
+  auth: 'password' | 'private-key';
+  private_key_path: string | null;
+  /** `SHA256:...` host key confirmed for a bastion missing from known_hosts. */
+  host_key_fingerprint: string | null;
+}
+
 export interface ConnectionConfig {
   id: string;
   name: string;
@@ -8,6 +20,11 @@ export interface ConnectionConfig {
   port: number;
   database: string;
   username: string;
+  ssl_mode: SslMode;
+  ssl_root_cert: string | null;
+  ssl_client_cert: string | null;
+  ssl_client_key: string | null;
+  ssh_tunnel: SshTunnelConfig | null;
 }
 
 export function useConnections() {
@@ -1,35 +1,180 @@
 import { invoke } from '@tauri-apps/api/core';
-import { useState } from 'react';
+import { useEffect, useRef, useState } from 'react';
+import { AppError, toAppError } from '../lib/errors';
 
 export interface QueryResult {
   columns: string[];
+  column_types: string[];
   rows: Array<Array<any>>;
   row_count: number;
+  /** [row, column] of cells holding raw `\x` hex because their type has no decoder. */
+  undecodable_cells: Array<[number, number]>;
+  /** Set while more rows can be pulled with `fetch_more`. */
+  result_id: string | null;
+  has_more: boolean;
+  /** The server-side row cap cut the result short. */
+  truncated: boolean;
 }
 
+interface ResultPage {
+  rows: Array<Array<any>>;
+  row_count: number;
+  undecodable_cells: Array<[number, number]>;
+  has_more: boolean;
+  truncated: boolean;
+}
+
+export interface StatementResult {
+  sql: string;
+  line: number;
+  status: 'ok' | 'error' | 'skipped';
+  command_tag: string | null;
+  rows_affected: number | null;
+  result: QueryResult | null;
+  duration_ms: number;
+  /** Positions are relative to the whole script. */
+  error: AppError | null;
+}
+
+export interface ScriptResult {
+  statements: StatementResult[];
+  rolled_back_open_transaction: boolean;
+}
+
+const PAGE_SIZE = 500;
+
 export function useQuery() {
   const [loading, setLoading] = useState(false);
-  const [error, setError] = useState<string | null>(null);
+  const [error, setError] = useState<AppError | null>(null);
   const [result, setResult] = useState<QueryResult | null>(null);
+  const [scriptResult, setScriptResult] = useState<ScriptResult | null>(null);
+  const executionId = useRef<string | null>(null);
+  // The open result outlives the tab otherwise, holding a pooled client
+  // until the backend's idle sweep gets to it
+  const openResultId = useRef<string | null>(null);
+  openResultId.current = result?.result_id ?? null;
+
+  useEffect(() => {
+    return () => {
+      if (openResultId.current) {
+        invoke<boolean>('close_result', { resultId: openResultId.current }).catch(() => {});
+      }
+    };
+  }, []);
 
-  const executeQuery = async (connectionId: string, query: string) => {
+  const closeResult = async () => {
+    if (result?.result_id) {
+      await invoke<boolean>('close_result', { resultId: result.result_id });
+    }
+  };
+
+  /** `params` holds values for the query's `:name` placeholders. */
+  const executeQuery = async (
+    connectionId: string,
+    query: string,
+    sessionId?: string,
+    params?: Record<string, unknown>
+  ) => {
     try {
       setLoading(true);
       setError(null);
+      await closeResult();
+      setScriptResult(null);
+      executionId.current = crypto.randomUUID();
       const result = await invoke<QueryResult>('execute_query', {
         request: {
           connection_id: connectionId,
           query,
+          execution_id: executionId.current,
+          page_size: PAGE_SIZE,
+          session_id: sessionId,
+          params,
         },
       });
       setResult(result);
-    } catch (err: any) {
-      setError(err.message || 'Query execution failed');
+    } catch (err) {
+      setError(toAppError(err, 'Query execution failed'));
+      setResult(null);
+    } finally {
+      executionId.current = null;
+      setLoading(false);
+    }
+  };
+
+  const executeScript = async (
+    connectionId: string,
+    script: string,
+    continueOnError: boolean,
+    sessionId?: string
+  ) => {
+    try {
+      setLoading(true);
+      setError(null);
+      await closeResult();
       setResult(null);
+      executionId.current = crypto.randomUUID();
+      const scriptResult = await invoke<ScriptResult>('execute_script', {
+        request: {
+          connection_id: connectionId,
+          script,
+          execution_id: executionId.current,
+          continue_on_error: continueOnError,
+          session_id: sessionId,
+        },
+      });
+      setScriptResult(scriptResult);
+    } catch (err) {
+      setError(toAppError(err, 'Script execution failed'));
+      setScriptResult(null);
+    } finally {
+      executionId.current = null;
+      setLoading(false);
+    }
+  };
+
+  const fetchMore = async () => {
+    if (!result?.result_id) {
+      return;
+    }
+    try {
+      setLoading(true);
+      const page = await invoke<ResultPage>('fetch_more', {
+        request: { result_id: result.result_id, page_size: PAGE_SIZE },
+      });
+      const offset = result.rows.length;
+      setResult({
+        ...result,
+        rows: [...result.rows, ...page.rows],
+        row_count: result.row_count + page.row_count,
+        undecodable_cells: [
+          ...result.undecodable_cells,
+          ...page.undecodable_cells.map(([row, col]): [number, number] => [row + offset, col]),
+        ],
+        result_id: page.has_more ? result.result_id : null,
+        has_more: page.has_more,
+        truncated: page.truncated,
+      });
+    } catch (err) {
+      setError(toAppError(err, 'Failed to fetch rows'));
     } finally {
       setLoading(false);
     }
   };
 
-  return { executeQuery, loading, error, result };
+  const cancelQuery = async () => {
+    if (executionId.current) {
+      await invoke<boolean>('cancel_query', { executionId: executionId.current });
+    }
+  };
+
+  return {
+    executeQuery,
+    executeScript,
+    fetchMore,
+    cancelQuery,
+    loading,
+    error,
+    result,
+    scriptResult,
+  };
 }
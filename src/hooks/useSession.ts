@@ -0,0 +1,118 @@
+import { invoke } from '@tauri-apps/api/core';
+import { useCallback, useEffect, useState } from 'react';
+import { AppError, toAppError } from '../lib/errors';
+
+export type TransactionStatus = 'idle' | 'in-transaction' | 'failed';
+
+export interface SessionInfo {
+  session_id: string;
+  connection_id: string;
+  status: TransactionStatus;
+  auto_commit: boolean;
+  /** Oldest first. */
+  savepoints: string[];
+}
+
+/**
+ * A pinned connection for one editor tab. The session is opened on first use
+ * (begin, savepoint or switching auto-commit off) so idle tabs don't hold a
+ * connection open, and closed when the tab goes away or changes connection.
+ */
+export function useSession(sessionId: string, connectionId?: string) {
+  const [session, setSession] = useState<SessionInfo | null>(null);
+  const [error, setError] = useState<AppError | null>(null);
+
+  useEffect(() => {
+    setSession(null);
+    return () => {
+      invoke<boolean>('close_session', { sessionId }).catch(() => {});
+    };
+  }, [sessionId, connectionId]);
+
+  const run = useCallback(async (action: () => Promise<SessionInfo>, fallback: string) => {
+    try {
+      setError(null);
+      setSession(await action());
+    } catch (err) {
+      setError(toAppError(err, fallback));
+    }
+  }, []);
+
+  const ensureSession = async (autoCommit: boolean) => {
+    if (session) {
+      return session;
+    }
+    const opened = await invoke<SessionInfo>('open_session', {
+      request: { session_id: sessionId, connection_id: connectionId, auto_commit: autoCommit },
+    });
+    setSession(opened);
+    return opened;
+  };
+
+  const begin = () =>
+    run(async () => {
+      await ensureSession(true);
+      return invoke<SessionInfo>('begin_transaction', { sessionId });
+    }, 'Failed to begin transaction');
+
+  const commit = () =>
+    run(() => invoke<SessionInfo>('commit_transaction', { sessionId }), 'Failed to commit');
+
+  const rollback = () =>
+    run(() => invoke<SessionInfo>('rollback_transaction', { sessionId }), 'Failed to roll back');
+
+  const setAutoCommit = async (enabled: boolean) => {
+    if (!session) {
+      // A tab without a session already auto-commits
+      if (!enabled) {
+        await run(() => ensureSession(false), 'Failed to open session');
+      }
+      return;
+    }
+    await run(
+      () => invoke<SessionInfo>('set_auto_commit', { request: { session_id: sessionId, enabled } }),
+      'Failed to change auto-commit'
+    );
+  };
+
+  const createSavepoint = (name: string) =>
+    run(async () => {
+      await ensureSession(true);
+      return invoke<SessionInfo>('create_savepoint', { request: { session_id: sessionId, name } });
+    }, 'Failed to create savepoint');
+
+  const rollbackToSavepoint = (name: string) =>
+    run(
+      () =>
+        invoke<SessionInfo>('rollback_to_savepoint', {
+          request: { session_id: sessionId, name },
+        }),
+      'Failed to roll back to savepoint'
+    );
+
+  const releaseSavepoint = (name: string) =>
+    run(
+      () => invoke<SessionInfo>('release_savepoint', { request: { session_id: sessionId, name } }),
+      'Failed to release savepoint'
+    );
+
+  /** Picks up status changes made by statements run in the session. */
+  const refresh = () => {
+    if (session) {
+      run(() => invoke<SessionInfo>('get_session', { sessionId }), 'Failed to load session');
+    }
+  };
+
+  return {
+    session,
+    error,
+    begin,
+    commit,
+    rollback,
+    setAutoCommit,
+    createSavepoint,
+    rollbackToSavepoint,
+    releaseSavepoint,
+    refresh,
+  };
+}
@@ -0,0 +1,50 @@
+export type ErrorCategory =
+  | 'database'
+  | 'connection'
+  | 'auth'
+  | 'tls'
+  | 'tunnel'
+  | 'keyring'
+  | 'validation'
+  | 'not-found'
+  | 'canceled'
+  | 'storage'
+  | 'internal';
+
+export interface DbErrorDetails {
+  code: string;
+  severity: string;
+  message: string;
+  detail: string | null;
+  hint: string | null;
+  /** 1-based character offset into the submitted SQL. */
+  position: number | null;
+  internal_position: number | null;
+  internal_query: string | null;
+  where: string | null;
+  schema: string | null;
+  table: string | null;
+  column: string | null;
+  datatype: string | null;
+  constraint: string | null;
+}
+
+/** What every command rejects with. */
+export interface AppError {
+  category: ErrorCategory;
+  message: string;
+  db: DbErrorDetails | null;
+}
+
+/** Normalizes whatever `invoke` rejected with into an `AppError`. */
+export function toAppError(err: unknown, fallback: string): AppError {
+  if (err && typeof err === 'object' && 'category' in err && 'message' in err) {
+    return err as AppError;
+  }
+  const message = err instanceof Error ? err.message : typeof err === 'string' ? err : fallback;
+  return { category: 'internal', message: message || fallback, db: null };
+}
+
+export function errorMessage(err: unknown, fallback: string): string {
+  return toAppError(err, fallback).message;
+}